@@ -0,0 +1,163 @@
+//! 资源审计 / Asset audit
+//!
+//! 将目录内的图片文件与整个工作区的 Markdown 引用交叉比对：列出未被引用的图片（可安全删除）
+//! 以及指向不存在文件的引用；清理时将孤立图片移入系统回收站。有文档无法读取时结果不完整，拒绝清理。
+//! Cross-references workspace images with every Markdown reference; orphans go to the trash.
+
+use super::{is_image_path, normalize_path};
+use crate::error::AppError;
+use crate::export::{is_markdown, read_source};
+use crate::markdown;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// 遍历时跳过的目录 / Directories skipped while scanning
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target"];
+
+/// 未被引用的图片 / Image not referenced by any document
+#[derive(Debug, serde::Serialize)]
+pub struct OrphanAsset {
+    pub path: String,
+    pub size: u64,
+}
+
+/// 指向不存在文件的引用 / Reference to a missing file
+#[derive(Debug, serde::Serialize)]
+pub struct MissingReference {
+    pub document: String,
+    pub line: usize,
+    pub target: String,
+}
+
+/// AssetReport
+/// 审计结果
+#[derive(Debug, serde::Serialize)]
+pub struct AssetReport {
+    pub image_count: usize,
+    pub orphans: Vec<OrphanAsset>,
+    /// 孤立图片总大小（字节） / Total size of orphaned images in bytes
+    pub orphan_size: u64,
+    pub missing: Vec<MissingReference>,
+    /// 无法读取的文档；非空时孤立列表可能有误，不能清理
+    /// Documents that could not be read; orphans may be wrong, so cleanup is refused
+    pub unreadable: Vec<String>,
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn walk(dir: &Path, markdown_files: &mut Vec<PathBuf>, images: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let p = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        if p.is_dir() {
+            if !SKIPPED_DIRS.contains(&name.as_str()) {
+                walk(&p, markdown_files, images);
+            }
+        } else if is_markdown(&p) {
+            markdown_files.push(p);
+        } else if is_image_path(&p) {
+            images.push(p);
+        }
+    }
+}
+
+/// audit
+/// 扫描 `dir` 中的图片，与工作区 `root` 内所有文档的引用比对，返回孤立图片与缺失引用
+pub fn audit(root: &Path, dir: &Path) -> Result<AssetReport, AppError> {
+    // 目录外的文档也可能引用目录内的图片，因此总是扫描整个工作区
+    // Documents outside `dir` may reference its images, so the whole workspace is scanned
+    let mut markdown_files = Vec::new();
+    let mut images = Vec::new();
+    walk(root, &mut markdown_files, &mut images);
+    markdown_files.sort();
+    images.retain(|p| p.starts_with(dir));
+    images.sort();
+
+    let mut referenced: HashSet<PathBuf> = HashSet::new();
+    let mut missing = Vec::new();
+    let mut unreadable = Vec::new();
+    for doc in &markdown_files {
+        let text = match read_source(doc) {
+            Ok(t) => t,
+            Err(e) => {
+                log::warn!("Cannot read {:?} during asset audit: {}", doc, e);
+                unreadable.push(display(doc));
+                continue;
+            }
+        };
+        let base = doc.parent().unwrap_or(root);
+        for reference in markdown::references(&text) {
+            let Some(target) = markdown::resolve_local(base, &reference.url) else {
+                continue;
+            };
+            let target = normalize_path(&target);
+            if target.exists() {
+                referenced.insert(target);
+            } else if reference.image || is_image_path(&target) || is_markdown(&target) {
+                // 普通链接只在指向图片或文档时才视为缺失
+                missing.push(MissingReference {
+                    document: display(doc),
+                    line: reference.line,
+                    target: reference.url,
+                });
+            }
+        }
+    }
+
+    let orphans: Vec<OrphanAsset> = images
+        .iter()
+        .filter(|p| !referenced.contains(&normalize_path(p)))
+        .map(|p| OrphanAsset {
+            path: display(p),
+            size: std::fs::metadata(p).map(|m| m.len()).unwrap_or(0),
+        })
+        .collect();
+    Ok(AssetReport {
+        image_count: images.len(),
+        orphan_size: orphans.iter().map(|o| o.size).sum(),
+        orphans,
+        missing,
+        unreadable,
+    })
+}
+
+/// cleanup_orphans
+/// 将孤立图片移入回收站；`only` 非空时只处理其中仍为孤立的文件，返回已移除的路径
+pub fn cleanup_orphans(
+    root: &Path,
+    dir: &Path,
+    only: Option<&[String]>,
+) -> Result<Vec<String>, AppError> {
+    let report = audit(root, dir)?;
+    if let Some(doc) = report.unreadable.first() {
+        return Err(AppError::InvalidInput(format!(
+            "无法读取文档 {}，引用不完整，已取消清理",
+            doc
+        )));
+    }
+    let selected: Vec<String> = report
+        .orphans
+        .into_iter()
+        .map(|o| o.path)
+        .filter(|p| match only {
+            Some(list) => list.iter().any(|s| s.replace('\\', "/") == *p),
+            None => true,
+        })
+        .collect();
+    if selected.is_empty() {
+        return Ok(selected);
+    }
+    trash::delete_all(&selected).map_err(|e| {
+        log::warn!("Failed to move orphans to trash: {}", e);
+        AppError::Unknown("移入回收站失败".to_string())
+    })?;
+    Ok(selected)
+}
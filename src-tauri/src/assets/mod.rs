@@ -0,0 +1,269 @@
+//! 图片资源管理 / Image asset management
+//!
+//! 粘贴或拖入的图片保存到文档旁的资源目录：按内容哈希去重，自动生成不冲突的文件名。
+//! Pasted or dropped images are stored next to the document, deduplicated by content hash.
+
+pub mod audit;
+pub mod optimize;
+pub mod protocol;
+pub mod remote;
+pub mod thumbnail;
+
+use crate::error::AppError;
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
+
+/// 默认资源目录（相对文档所在目录） / Default assets folder, relative to the document
+pub const DEFAULT_ASSETS_DIR: &str = "assets";
+
+/// 视为图片资源的扩展名 / Extensions treated as image assets
+pub const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "svg", "bmp", "ico", "avif", "tif", "tiff",
+];
+
+pub fn is_image_path(path: &Path) -> bool {
+    path.extension()
+        .map(|e| IMAGE_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// 词法上规范化路径（处理 `.` 与 `..`，不访问文件系统）
+/// Lexically normalizes `.` and `..` without touching the file system
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+/// SaveImageOptions
+/// 图片保存选项
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct SaveImageOptions {
+    /// 资源目录（相对文档所在目录），支持 `{name}` 表示文档名，缺省为 `assets`
+    #[serde(default)]
+    pub assets_dir: Option<String>,
+    /// 原始文件名（拖拽文件时提供），用于生成新文件名
+    #[serde(default)]
+    pub file_name: Option<String>,
+    /// 保存前的优化处理（缩放、重新压缩、去除元数据），缺省不处理
+    #[serde(default)]
+    pub optimize: Option<optimize::OptimizeOptions>,
+    /// 发起保存的窗口（后端填写），资源目录须在其工作区内
+    #[serde(skip)]
+    pub window: String,
+}
+
+/// 文件名中只保留字母数字（含中文）、`-` 与 `_`
+pub fn sanitize_file_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if c.is_alphanumeric() || c == '-' || c == '_' {
+            out.push(c);
+        } else if !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    let out = out.trim_matches('-').to_string();
+    if out.is_empty() {
+        "image".to_string()
+    } else {
+        out
+    }
+}
+
+/// 根据文件头识别图片格式，返回扩展名 / Detects the image format from its magic bytes
+pub fn detect_extension(bytes: &[u8]) -> Option<&'static str> {
+    if let Ok(format) = image::guess_format(bytes) {
+        return format.extensions_str().first().copied();
+    }
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]).to_lowercase();
+    if head.trim_start().starts_with("<svg") || head.contains("<svg") && head.contains("<?xml") {
+        return Some("svg");
+    }
+    None
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 在目录中查找内容相同的文件 / Finds a file with identical content in `dir`
+pub fn find_duplicate(dir: &Path, bytes: &[u8]) -> Option<PathBuf> {
+    let hash = sha256_hex(bytes);
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.is_file()
+                && std::fs::metadata(p)
+                    .map(|m| m.len() == bytes.len() as u64)
+                    .unwrap_or(false)
+        })
+        .find(|p| {
+            std::fs::read(p)
+                .map(|existing| sha256_hex(&existing) == hash)
+                .unwrap_or(false)
+        })
+}
+
+/// 生成不冲突的文件路径：`name.ext`、`name-1.ext`…
+/// Picks a collision-free path: `name.ext`, `name-1.ext`, …
+pub fn unique_path(dir: &Path, stem: &str, ext: &str) -> PathBuf {
+    let mut candidate = dir.join(format!("{}.{}", stem, ext));
+    let mut n = 1;
+    while candidate.exists() {
+        candidate = dir.join(format!("{}-{}.{}", stem, n, ext));
+        n += 1;
+    }
+    candidate
+}
+
+/// 相对文档的链接路径（`/` 分隔，空格编码为 `%20`） / Link path relative to the document
+pub fn relative_link(doc_dir: &Path, target: &Path) -> String {
+    relative_path(doc_dir, target)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().replace(' ', "%20"))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 从 `from_dir` 到 `target` 的相对路径，必要时使用 `..`（两者须为同一根下的绝对路径）
+/// Relative path from `from_dir` to `target`, stepping up with `..` where needed
+pub fn relative_path(from_dir: &Path, target: &Path) -> PathBuf {
+    let from: Vec<_> = from_dir.components().collect();
+    let to: Vec<_> = target.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut rel = PathBuf::new();
+    for _ in common..from.len() {
+        rel.push("..");
+    }
+    for c in &to[common..] {
+        rel.push(c.as_os_str());
+    }
+    rel
+}
+
+/// 解析文档的资源目录 / Resolves the assets folder for a document
+pub fn assets_dir_for(doc_path: &Path, template: Option<&str>) -> Result<PathBuf, AppError> {
+    let doc_dir = doc_path.parent().unwrap_or(Path::new("."));
+    let stem = doc_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let rel = template
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .unwrap_or(DEFAULT_ASSETS_DIR)
+        .replace("{name}", &stem);
+    let rel_path = Path::new(&rel);
+    if rel_path.is_absolute()
+        || rel_path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(AppError::InvalidInput(
+            "资源目录必须是文档所在目录下的相对路径".to_string(),
+        ));
+    }
+    Ok(doc_dir.join(rel_path))
+}
+
+/// save_image
+/// 保存图片到资源目录；已存在相同内容时直接复用，返回插入 Markdown 的相对路径
+pub fn save_image(
+    bytes: &[u8],
+    doc_path: &Path,
+    options: &SaveImageOptions,
+) -> Result<String, AppError> {
+    if bytes.is_empty() {
+        return Err(AppError::InvalidInput("图片内容为空".to_string()));
+    }
+    if bytes.len() as u64 > crate::MAX_FILE_SIZE {
+        return Err(AppError::InvalidInput(format!(
+            "图片过大 ({:.2} MB)",
+            bytes.len() as f64 / 1024.0 / 1024.0
+        )));
+    }
+    let ext = detect_extension(bytes)
+        .ok_or_else(|| AppError::InvalidInput("不支持的图片格式".to_string()))?;
+    // 先处理再去重：相同输入的处理结果一致 / Optimize first; output is deterministic
+    let (bytes, ext) = match &options.optimize {
+        Some(opts) => optimize::optimize(bytes, ext, opts)?,
+        None => (bytes.to_vec(), ext),
+    };
+    let bytes = bytes.as_slice();
+
+    let doc_dir = doc_path.parent().unwrap_or(Path::new("."));
+    let dir = assets_dir_for(doc_path, options.assets_dir.as_deref())?;
+    // 先检查再创建，不在工作区外留下空目录 / Check before creating so nothing is made outside the workspace
+    crate::ensure_target_in_workspace(&options.window, &dir).map_err(AppError::WorkspaceError)?;
+    std::fs::create_dir_all(&dir)?;
+
+    if let Some(existing) = find_duplicate(&dir, bytes) {
+        return Ok(relative_link(doc_dir, &existing));
+    }
+
+    let stem = options
+        .file_name
+        .as_deref()
+        .map(|n| {
+            Path::new(n)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| sanitize_file_name(&s))
+        .unwrap_or_else(|| format!("image-{}", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    let target = unique_path(&dir, &stem, ext);
+    std::fs::write(&target, bytes)?;
+    Ok(relative_link(doc_dir, &target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_path_between_folders() {
+        let rel = |from: &str, to: &str| relative_path(Path::new(from), Path::new(to));
+        assert_eq!(
+            rel("/w/docs", "/w/docs/assets/a.png"),
+            PathBuf::from("assets/a.png")
+        );
+        assert_eq!(
+            rel("/w/docs/sub", "/w/assets/a.png"),
+            PathBuf::from("../../assets/a.png")
+        );
+        assert_eq!(rel("/w/docs", "/w/docs"), PathBuf::new());
+    }
+
+    #[test]
+    fn relative_link_encodes_spaces() {
+        let link = relative_link(Path::new("/w/my docs"), Path::new("/w/my assets/a b.png"));
+        assert_eq!(link, "../my%20assets/a%20b.png");
+    }
+
+    #[test]
+    fn normalize_path_is_lexical() {
+        assert_eq!(
+            normalize_path(Path::new("/w/./a/../b")),
+            PathBuf::from("/w/b")
+        );
+        assert_eq!(normalize_path(Path::new("../a")), PathBuf::from("../a"));
+    }
+}
@@ -0,0 +1,369 @@
+//! 图片优化 / Image optimization
+//!
+//! 保存前可选处理：超出最大边长时等比缩小、PNG 转 WebP 或重新压缩、JPEG 按质量重新编码，
+//! 并去除 EXIF/GPS 等元数据。仅去除元数据时尽量无损地删除元数据块，无法无损处理时才重新编码。
+//! Optional processing before saving; metadata-only stripping is lossless where possible.
+
+use crate::error::AppError;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType as PngFilter, PngEncoder};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use std::io::Cursor;
+
+/// OptimizeOptions
+/// 图片优化选项
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct OptimizeOptions {
+    /// 最大边长（像素），超过时等比缩小
+    #[serde(default)]
+    pub max_dimension: Option<u32>,
+    /// PNG 处理方式：`webp`（无损 WebP）/ `png`（重新压缩），缺省保持原样
+    #[serde(default)]
+    pub png_format: Option<String>,
+    /// JPEG 重新编码质量（1-100），缺省不重新编码
+    #[serde(default)]
+    pub jpeg_quality: Option<u8>,
+    /// 去除 EXIF/GPS 等元数据
+    #[serde(default = "default_true")]
+    pub strip_metadata: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        Self {
+            max_dimension: None,
+            png_format: None,
+            jpeg_quality: None,
+            strip_metadata: true,
+        }
+    }
+}
+
+/// 未指定质量但必须重新编码 JPEG 时（缩放或校正方向）使用的质量
+const FALLBACK_JPEG_QUALITY: u8 = 90;
+
+fn image_error(e: image::ImageError) -> AppError {
+    AppError::InvalidInput(format!("图片处理失败: {}", e))
+}
+
+/// optimize
+/// 按选项处理图片，返回 (新内容, 扩展名)；不需要处理时原样返回
+pub fn optimize(
+    bytes: &[u8],
+    ext: &'static str,
+    options: &OptimizeOptions,
+) -> Result<(Vec<u8>, &'static str), AppError> {
+    // 动图与矢量图不处理 / Leave animations and vector images alone
+    if !matches!(
+        ext,
+        "png" | "jpg" | "jpeg" | "webp" | "bmp" | "tif" | "tiff"
+    ) {
+        return Ok((bytes.to_vec(), ext));
+    }
+
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .into_decoder()
+        .map_err(image_error)?;
+    let (width, height) = decoder.dimensions();
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+
+    let too_large = options
+        .max_dimension
+        .filter(|m| *m > 0)
+        .map(|m| width > m || height > m)
+        .unwrap_or(false);
+    let png_target = match options.png_format.as_deref() {
+        Some("webp") => Some("webp"),
+        Some("png") => Some("png"),
+        _ => None,
+    };
+    let is_jpeg = matches!(ext, "jpg" | "jpeg");
+    // 去除元数据会丢失方向信息，此时需要先把方向应用到像素上
+    let must_rotate = options.strip_metadata && orientation != Orientation::NoTransforms;
+    let reencode = too_large
+        || must_rotate
+        || (ext == "png" && png_target.is_some())
+        || (is_jpeg && options.jpeg_quality.is_some())
+        || matches!(ext, "bmp" | "tif" | "tiff");
+
+    if !reencode {
+        if !options.strip_metadata {
+            return Ok((bytes.to_vec(), ext));
+        }
+        // 无法无损去除时改为重新编码，不把元数据原样带出
+        // Fall back to re-encoding rather than keeping metadata we could not remove
+        if let Some(out) = strip_metadata(bytes, ext) {
+            return Ok((out, ext));
+        }
+    }
+
+    let mut img = DynamicImage::from_decoder(decoder).map_err(image_error)?;
+    img.apply_orientation(orientation);
+    if let Some(max) = options.max_dimension.filter(|_| too_large) {
+        img = img.resize(max, max, FilterType::Lanczos3);
+    }
+
+    // 重新编码不会携带原有元数据 / Re-encoding never carries the original metadata
+    let mut out = Vec::new();
+    let target = match ext {
+        "jpg" | "jpeg" => {
+            let quality = options
+                .jpeg_quality
+                .unwrap_or(FALLBACK_JPEG_QUALITY)
+                .clamp(1, 100);
+            img.to_rgb8()
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut out, quality))
+                .map_err(image_error)?;
+            "jpg"
+        }
+        "png" if png_target == Some("webp") => {
+            encode_webp(&img, &mut out)?;
+            "webp"
+        }
+        "webp" => {
+            encode_webp(&img, &mut out)?;
+            "webp"
+        }
+        _ => {
+            img.write_with_encoder(PngEncoder::new_with_quality(
+                &mut out,
+                CompressionType::Best,
+                PngFilter::Adaptive,
+            ))
+            .map_err(image_error)?;
+            "png"
+        }
+    };
+    Ok((out, target))
+}
+
+/// WebP 编码器只支持 8 位 RGB/RGBA / The WebP encoder only takes 8-bit RGB(A)
+fn encode_webp(img: &DynamicImage, out: &mut Vec<u8>) -> Result<(), AppError> {
+    let encoder = WebPEncoder::new_lossless(out);
+    if img.color().has_alpha() {
+        img.to_rgba8().write_with_encoder(encoder)
+    } else {
+        img.to_rgb8().write_with_encoder(encoder)
+    }
+    .map_err(image_error)
+}
+
+/// strip_metadata
+/// 无损删除元数据块；格式无法识别或文件结构损坏时返回 None
+pub fn strip_metadata(bytes: &[u8], ext: &str) -> Option<Vec<u8>> {
+    match ext {
+        "jpg" | "jpeg" => strip_jpeg(bytes),
+        "png" => strip_png(bytes),
+        "webp" => strip_webp(bytes),
+        _ => None,
+    }
+}
+
+/// 删除 APP1（EXIF/XMP）、APP13（IPTC）与注释段 / Drops APP1, APP13 and COM segments
+fn strip_jpeg(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.get(..2)? != [0xFF, 0xD8] {
+        return None;
+    }
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(&bytes[..2]);
+    let mut i = 2;
+    while i + 4 <= bytes.len() {
+        if bytes[i] != 0xFF {
+            return None;
+        }
+        let marker = bytes[i + 1];
+        // SOS 之后是压缩数据，原样复制 / Entropy-coded data follows SOS
+        if marker == 0xDA {
+            out.extend_from_slice(&bytes[i..]);
+            return Some(out);
+        }
+        let len = u16::from_be_bytes([bytes[i + 2], bytes[i + 3]]) as usize;
+        let end = i + 2 + len;
+        if len < 2 || end > bytes.len() {
+            return None;
+        }
+        if !matches!(marker, 0xE1 | 0xED | 0xFE) {
+            out.extend_from_slice(&bytes[i..end]);
+        }
+        i = end;
+    }
+    None
+}
+
+/// 删除 eXIf 与文本、时间块 / Drops eXIf, text and time chunks
+fn strip_png(bytes: &[u8]) -> Option<Vec<u8>> {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    if bytes.get(..8)? != SIGNATURE {
+        return None;
+    }
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(&SIGNATURE);
+    let mut i = 8;
+    while i + 12 <= bytes.len() {
+        let len = u32::from_be_bytes(bytes[i..i + 4].try_into().ok()?) as usize;
+        let end = i.checked_add(12)?.checked_add(len)?;
+        if end > bytes.len() {
+            return None;
+        }
+        let kind = &bytes[i + 4..i + 8];
+        if !matches!(kind, b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME") {
+            out.extend_from_slice(&bytes[i..end]);
+        }
+        i = end;
+    }
+    Some(out)
+}
+
+/// 删除 RIFF 中的 EXIF 与 XMP 块并更新 VP8X 标志 / Drops EXIF/XMP chunks
+fn strip_webp(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.get(..4)? != b"RIFF" || bytes.get(8..12)? != b"WEBP" {
+        return None;
+    }
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(&bytes[..12]);
+    let mut i = 12;
+    while i + 8 <= bytes.len() {
+        let len = u32::from_le_bytes(bytes[i + 4..i + 8].try_into().ok()?) as usize;
+        let data_end = i.checked_add(8)?.checked_add(len)?;
+        // 截断的块说明文件已损坏；只容许缺少末尾的填充字节
+        // A truncated chunk means a damaged file; only a missing trailing pad byte is tolerated
+        if data_end > bytes.len() {
+            return None;
+        }
+        let end = (data_end + (len & 1)).min(bytes.len());
+        let kind = &bytes[i..i + 4];
+        match kind {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" if len >= 1 => {
+                let start = out.len();
+                out.extend_from_slice(&bytes[i..end]);
+                // 清除 EXIF(0x08) 与 XMP(0x04) 标志位
+                out[start + 8] &= !0x0C;
+            }
+            _ => out.extend_from_slice(&bytes[i..end]),
+        }
+        i = end;
+    }
+    let riff_len = (out.len() - 8) as u32;
+    out[4..8].copy_from_slice(&riff_len.to_le_bytes());
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jpeg_segment(marker: u8, data: &[u8]) -> Vec<u8> {
+        let mut out = vec![0xFF, marker];
+        out.extend_from_slice(&((data.len() + 2) as u16).to_be_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut out = (data.len() as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(data);
+        out.extend_from_slice(&[0; 4]);
+        out
+    }
+
+    fn webp_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut out = kind.to_vec();
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            out.push(0);
+        }
+        out
+    }
+
+    fn webp(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut out = b"RIFF".to_vec();
+        out.extend_from_slice(&((body.len() + 4) as u32).to_le_bytes());
+        out.extend_from_slice(b"WEBP");
+        out.extend_from_slice(&body);
+        out
+    }
+
+    #[test]
+    fn strip_jpeg_drops_metadata_segments() {
+        let app0 = jpeg_segment(0xE0, b"JFIF\0\x01\x01");
+        let dqt = jpeg_segment(0xDB, &[0; 5]);
+        let scan = [0xFF, 0xDA, 0x00, 0x02, 0x12, 0x34, 0xFF, 0xD9];
+        let input = [
+            &[0xFF, 0xD8][..],
+            &app0,
+            &jpeg_segment(0xE1, b"Exif\0\0GPS"),
+            &jpeg_segment(0xFE, b"comment"),
+            &dqt,
+            &scan,
+        ]
+        .concat();
+        let expected = [&[0xFF, 0xD8][..], &app0, &dqt, &scan].concat();
+        assert_eq!(strip_metadata(&input, "jpg"), Some(expected));
+    }
+
+    #[test]
+    fn strip_jpeg_rejects_damaged_files() {
+        assert_eq!(strip_jpeg(b"not a jpeg"), None);
+        let truncated = [&[0xFF, 0xD8][..], &[0xFF, 0xE1, 0x00, 0x40, 0x00]].concat();
+        assert_eq!(strip_jpeg(&truncated), None);
+    }
+
+    #[test]
+    fn strip_png_drops_text_and_exif_chunks() {
+        let ihdr = png_chunk(b"IHDR", &[0; 13]);
+        let idat = png_chunk(b"IDAT", &[1, 2, 3]);
+        let iend = png_chunk(b"IEND", &[]);
+        let mut input = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        let signature = input.clone();
+        for chunk in [
+            &ihdr,
+            &png_chunk(b"tEXt", b"Author\0me"),
+            &png_chunk(b"eXIf", b"MM"),
+            &idat,
+            &png_chunk(b"tIME", &[0; 7]),
+            &iend,
+        ] {
+            input.extend_from_slice(chunk);
+        }
+        let expected = [signature, ihdr, idat, iend].concat();
+        assert_eq!(strip_metadata(&input, "png"), Some(expected));
+    }
+
+    #[test]
+    fn strip_webp_drops_chunks_and_clears_flags() {
+        let mut flags = [0u8; 10];
+        flags[0] = 0x0C | 0x10;
+        let image = webp_chunk(b"VP8L", &[1, 2, 3]);
+        let input = webp(&[
+            webp_chunk(b"VP8X", &flags),
+            image.clone(),
+            webp_chunk(b"EXIF", b"MM\0*\0"),
+            webp_chunk(b"XMP ", b"<x/>"),
+        ]);
+        let mut cleared = flags;
+        cleared[0] = 0x10;
+        let expected = webp(&[webp_chunk(b"VP8X", &cleared), image]);
+        assert_eq!(strip_metadata(&input, "webp"), Some(expected));
+    }
+
+    #[test]
+    fn strip_webp_rejects_truncated_chunks() {
+        let mut input = webp(&[webp_chunk(b"VP8L", &[1, 2, 3, 4])]);
+        input.truncate(input.len() - 2);
+        assert_eq!(strip_webp(&input), None);
+        assert_eq!(strip_metadata(b"RIFF", "webp"), None);
+        assert_eq!(strip_metadata(b"GIF89a", "gif"), None);
+    }
+}
@@ -0,0 +1,138 @@
+//! 工作区资源协议 / Workspace asset protocol
+//!
+//! `mm-asset://localhost/<引用>?doc=<文档路径>`：按当前文档解析相对路径，返回正确的 MIME 类型，
+//! 并支持 Range 请求，供预览中的图片、音频与视频使用，无需放开 `fs:scope`。
+//! Resolves references against the current document and serves them with range support.
+
+use super::normalize_path;
+use crate::error::AppError;
+use crate::markdown;
+use percent_encoding::percent_decode_str;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// 协议名 / URI scheme name
+pub const SCHEME: &str = "mm-asset";
+
+/// 未指定结束位置的 Range 请求每次最多返回的字节数
+/// Upper bound for open-ended range requests such as `bytes=0-`
+const MAX_RANGE_CHUNK: u64 = 4 * 1024 * 1024;
+
+/// 不带 Range 时整体读取的上限；更大的文件（通常是视频）只返回开头一段，播放器会继续按区间请求
+/// Largest file served whole; bigger ones get their first chunk and players continue with ranges
+const MAX_FULL_READ: u64 = 32 * 1024 * 1024;
+
+/// 应用自身页面的来源，只有它们可以用 fetch() 读取响应
+/// Origins of the app's own pages, the only ones allowed to read responses via fetch()
+const APP_ORIGINS: &[&str] = &[
+    "tauri://localhost",
+    "http://tauri.localhost",
+    "https://tauri.localhost",
+];
+
+fn decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().to_string()
+}
+
+/// 从请求路径与查询串中取出（引用, 文档路径） / Splits a request into (reference, document)
+pub fn parse_request(path: &str, query: Option<&str>) -> (String, Option<String>) {
+    let reference = decode(path.trim_start_matches('/'));
+    let document = query.and_then(|q| {
+        q.split('&')
+            .find_map(|pair| pair.strip_prefix("doc="))
+            .map(decode)
+            .filter(|d| !d.is_empty())
+    });
+    (reference, document)
+}
+
+/// resolve
+/// 按 Markdown 的规则解析资源路径（含百分号解码）：绝对路径原样使用，相对路径相对文档所在目录
+pub fn resolve(reference: &str, document: Option<&str>) -> Result<PathBuf, AppError> {
+    let base = document
+        .map(|d| Path::new(d).parent().unwrap_or(Path::new(".")))
+        .unwrap_or(Path::new(""));
+    let path = markdown::resolve_local(base, reference)
+        .ok_or_else(|| AppError::InvalidInput(format!("不是本地资源: {}", reference)))?;
+    if path.as_os_str().is_empty() {
+        return Err(AppError::InvalidInput("资源路径为空".to_string()));
+    }
+    if path.is_relative() {
+        return Err(AppError::InvalidInput("缺少当前文档路径".to_string()));
+    }
+    Ok(normalize_path(&path))
+}
+
+/// 是否为应用自身页面的来源 / Whether `origin` is one of the app's own pages
+pub fn is_app_origin(origin: &str, dev_origin: Option<&str>) -> bool {
+    APP_ORIGINS.contains(&origin) || dev_origin == Some(origin)
+}
+
+/// 根据扩展名推断 MIME 类型 / MIME type guessed from the extension
+pub fn mime_type(path: &Path) -> String {
+    mime_guess::from_path(path)
+        .first_or_octet_stream()
+        .essence_str()
+        .to_string()
+}
+
+/// Range 解析结果 / Outcome of parsing a `Range` header
+#[derive(Debug, PartialEq, Eq)]
+pub enum ByteRange {
+    /// 返回整个文件 / Serve the whole file
+    Full,
+    /// 闭区间 `[start, end]` / Inclusive byte range
+    Partial(u64, u64),
+    /// 无法满足（416） / Not satisfiable
+    Unsatisfiable,
+}
+
+/// 整个文件，过大时只取开头一段 / The whole file, or its first chunk when too large
+fn full(len: u64) -> ByteRange {
+    if len > MAX_FULL_READ {
+        ByteRange::Partial(0, MAX_RANGE_CHUNK - 1)
+    } else {
+        ByteRange::Full
+    }
+}
+
+/// parse_range
+/// 解析 `bytes=start-end`、`bytes=start-` 与 `bytes=-suffix`；多个区间时只取第一个。
+/// 不带 Range 的大文件也按区间返回，避免整个读入内存
+pub fn parse_range(header: Option<&str>, len: u64) -> ByteRange {
+    let Some(spec) = header.and_then(|h| h.trim().strip_prefix("bytes=")) else {
+        return full(len);
+    };
+    let first = spec.split(',').next().unwrap_or_default().trim();
+    let Some((start, end)) = first.split_once('-') else {
+        return full(len);
+    };
+    let (start, end) = (start.trim(), end.trim());
+    if len == 0 {
+        return ByteRange::Unsatisfiable;
+    }
+    let last = len - 1;
+    match (start.parse::<u64>().ok(), end.parse::<u64>().ok()) {
+        (Some(s), _) if s > last => ByteRange::Unsatisfiable,
+        (Some(s), Some(e)) if e >= s => {
+            ByteRange::Partial(s, e.min(last).min(s + MAX_FULL_READ - 1))
+        }
+        (Some(s), None) if end.is_empty() => {
+            ByteRange::Partial(s, last.min(s + MAX_RANGE_CHUNK - 1))
+        }
+        (None, Some(n)) if start.is_empty() && n > 0 => {
+            let s = len.saturating_sub(n);
+            ByteRange::Partial(s, last.min(s + MAX_FULL_READ - 1))
+        }
+        _ => full(len),
+    }
+}
+
+/// 读取文件的闭区间 `[start, end]` / Reads the inclusive range `[start, end]`
+pub fn read_range(path: &Path, start: u64, end: u64) -> Result<Vec<u8>, AppError> {
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut buf = Vec::with_capacity((end - start + 1) as usize);
+    file.take(end - start + 1).read_to_end(&mut buf)?;
+    Ok(buf)
+}
@@ -0,0 +1,210 @@
+//! 远程图片本地化 / Remote image localization
+//!
+//! 下载文档中引用的 `http(s)` 图片（限制大小与超时），保存到资源目录并改写链接，
+//! 防止图床失效导致图片丢失；每个地址单独报告成功或失败原因。
+//! Downloads hotlinked images into the assets folder and rewrites the links.
+
+use super::{save_image, SaveImageOptions};
+use crate::error::AppError;
+use crate::markdown;
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+
+/// 默认单个请求超时（秒） / Default per-request timeout in seconds
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// 同时进行的下载数 / Concurrent downloads
+const CONCURRENCY: usize = 4;
+
+/// LocalizeOptions
+/// 远程图片本地化选项
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct LocalizeOptions {
+    /// 资源目录（同 `save_image`），缺省为 `assets`
+    #[serde(default)]
+    pub assets_dir: Option<String>,
+    /// 单张图片大小上限（字节），不超过 10MB
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    /// 单个请求超时（秒）
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// 发起请求的窗口（后端填写），图片保存在其工作区内
+    #[serde(skip)]
+    pub window: String,
+}
+
+/// 单个地址的处理结果 / Outcome for one remote URL
+#[derive(Debug, serde::Serialize)]
+pub struct RemoteImage {
+    pub url: String,
+    /// 成功时为新的相对链接 / New relative link on success
+    pub link: Option<String>,
+    pub error: Option<String>,
+}
+
+/// LocalizeReport
+/// 本地化结果
+#[derive(Debug, serde::Serialize)]
+pub struct LocalizeReport {
+    pub images: Vec<RemoteImage>,
+    /// 改写的链接数 / Number of rewritten links
+    pub rewritten: usize,
+}
+
+fn is_remote(url: &str) -> bool {
+    let lower = url.trim().to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// 失败原因只保留可读部分 / Human-readable failure reason
+fn describe(err: AppError) -> String {
+    match err {
+        AppError::Network(msg) | AppError::InvalidInput(msg) => msg,
+        other => other.to_string(),
+    }
+}
+
+/// URL 最后一段作为文件名来源 / Last path segment of the URL, used as the file name hint
+fn file_name_hint(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next()?;
+    let after_host = path.split_once("://").map(|(_, rest)| rest)?;
+    let (_, path) = after_host.split_once('/')?;
+    path.rsplit('/')
+        .find(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+async fn download(client: &reqwest::Client, url: &str, max_size: u64) -> Result<Vec<u8>, AppError> {
+    let response = client.get(url).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(AppError::Network(format!("HTTP {}", status.as_u16())));
+    }
+    let too_large = || {
+        AppError::InvalidInput(format!(
+            "超过大小限制 ({:.2} MB)",
+            max_size as f64 / 1024.0 / 1024.0
+        ))
+    };
+    if response.content_length().is_some_and(|len| len > max_size) {
+        return Err(too_large());
+    }
+    // 服务器可能不返回或谎报长度，边下载边检查
+    // Content-Length may be missing or wrong, so enforce the limit while streaming
+    let mut bytes = Vec::new();
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        if (bytes.len() + chunk.len()) as u64 > max_size {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    if super::detect_extension(&bytes).is_none() {
+        return Err(AppError::InvalidInput("下载的内容不是图片".to_string()));
+    }
+    Ok(bytes)
+}
+
+/// localize
+/// 下载文档中的远程图片并改写链接，返回每个地址的结果
+pub async fn localize(
+    doc_path: &Path,
+    options: &LocalizeOptions,
+) -> Result<LocalizeReport, AppError> {
+    crate::check_file_size(doc_path).map_err(AppError::InvalidInput)?;
+    let src = std::fs::read_to_string(doc_path)?;
+    let references: Vec<markdown::Reference> = markdown::references(&src)
+        .into_iter()
+        .filter(|r| r.image && is_remote(&r.url))
+        .collect();
+    let mut urls: Vec<String> = Vec::new();
+    for r in &references {
+        if !urls.contains(&r.url) {
+            urls.push(r.url.clone());
+        }
+    }
+    if urls.is_empty() {
+        return Ok(LocalizeReport {
+            images: Vec::new(),
+            rewritten: 0,
+        });
+    }
+
+    let max_size = options
+        .max_size
+        .unwrap_or(crate::MAX_FILE_SIZE)
+        .min(crate::MAX_FILE_SIZE);
+    let timeout = Duration::from_secs(options.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).max(1));
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .connect_timeout(timeout.min(Duration::from_secs(10)))
+        .user_agent(concat!("MarkdownMonkey/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+
+    let downloads: Vec<Result<Vec<u8>, AppError>> = futures_util::stream::iter(&urls)
+        .map(|url| download(&client, url, max_size))
+        .buffered(CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut images = Vec::with_capacity(urls.len());
+    let mut links: HashMap<&str, String> = HashMap::new();
+    for (url, result) in urls.iter().zip(downloads) {
+        let saved = result.and_then(|bytes| {
+            save_image(
+                &bytes,
+                doc_path,
+                &SaveImageOptions {
+                    assets_dir: options.assets_dir.clone(),
+                    file_name: file_name_hint(url),
+                    optimize: None,
+                    window: options.window.clone(),
+                },
+            )
+        });
+        match saved {
+            Ok(link) => {
+                links.insert(url, link.clone());
+                images.push(RemoteImage {
+                    url: url.clone(),
+                    link: Some(link),
+                    error: None,
+                });
+            }
+            Err(e) => {
+                log::warn!("Failed to localize {}: {}", url, e);
+                images.push(RemoteImage {
+                    url: url.clone(),
+                    link: None,
+                    error: Some(describe(e)),
+                });
+            }
+        }
+    }
+
+    // 行内图片直接在其范围内改写；引用式图片改写对应的链接定义
+    // Inline images are rewritten in place; reference-style ones via their definitions
+    let mut spans: Vec<Range<usize>> = references
+        .iter()
+        .filter(|r| links.contains_key(r.url.as_str()))
+        .map(|r| r.span.clone())
+        .collect();
+    spans.extend(
+        markdown::reference_definitions(&src)
+            .into_iter()
+            .filter(|(dest, _)| links.contains_key(dest.as_str()))
+            .map(|(_, span)| span),
+    );
+    spans.sort_by_key(|s| s.start);
+    spans.dedup();
+    let (output, rewritten) = markdown::rewrite_urls(&src, &spans, &links);
+    if rewritten > 0 {
+        std::fs::write(doc_path, output)?;
+    }
+    Ok(LocalizeReport { images, rewritten })
+}
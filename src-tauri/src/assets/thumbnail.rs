@@ -0,0 +1,102 @@
+//! 缩略图缓存 / Thumbnail cache
+//!
+//! 解码本地图片并生成 PNG 缩略图，缓存在应用缓存目录中，以路径 + 修改时间 + 尺寸为键；
+//! 原图修改后键随之变化，旧缓存不再命中。前端通过自定义 URI 协议加载缓存文件。
+//! Thumbnails are cached under the app cache dir, keyed by path + mtime + size.
+
+use super::sha256_hex;
+use crate::error::AppError;
+use image::codecs::png::PngEncoder;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// 缓存子目录 / Cache subfolder
+pub const CACHE_DIR: &str = "thumbnails";
+
+/// 缩略图边长范围（像素） / Allowed thumbnail edge length in pixels
+const MIN_SIZE: u32 = 16;
+const MAX_SIZE: u32 = 1024;
+
+/// 可生成缩略图的格式（矢量图由前端直接显示） / Raster formats we can decode
+const SUPPORTED: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "bmp", "ico", "tif", "tiff",
+];
+
+fn image_error(e: image::ImageError) -> AppError {
+    AppError::InvalidInput(format!("生成缩略图失败: {}", e))
+}
+
+/// 缩略图缓存目录 / Thumbnail folder inside the app cache dir
+pub fn cache_dir(app_cache_dir: &Path) -> PathBuf {
+    app_cache_dir.join(CACHE_DIR)
+}
+
+/// 缓存文件名：路径、修改时间与尺寸的哈希 / Cache file name derived from path, mtime and size
+pub fn cache_key(path: &Path, size: u32) -> Result<String, AppError> {
+    let modified = std::fs::metadata(path)?
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let source = format!(
+        "{}\n{}\n{}",
+        path.to_string_lossy().replace('\\', "/"),
+        modified,
+        size
+    );
+    Ok(format!("{}.png", sha256_hex(source.as_bytes())))
+}
+
+/// 协议请求中的文件名只允许为缓存键，防止越出缓存目录
+/// Only bare cache keys are served; anything else could escape the cache folder
+pub fn is_cache_key(name: &str) -> bool {
+    name.strip_suffix(".png")
+        .map(|stem| stem.len() == 64 && stem.bytes().all(|b| b.is_ascii_hexdigit()))
+        .unwrap_or(false)
+}
+
+/// thumbnail
+/// 返回缩略图缓存文件名；缓存不存在时解码原图生成
+pub fn thumbnail(app_cache_dir: &Path, path: &Path, size: u32) -> Result<String, AppError> {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !SUPPORTED.contains(&ext.as_str()) {
+        return Err(AppError::InvalidInput(
+            "不支持生成该格式的缩略图".to_string(),
+        ));
+    }
+    let size = size.clamp(MIN_SIZE, MAX_SIZE);
+    let key = cache_key(path, size)?;
+    let dir = cache_dir(app_cache_dir);
+    let target = dir.join(&key);
+    if target.is_file() {
+        return Ok(key);
+    }
+
+    let mut decoder = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()
+        .map_err(image_error)?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut img = DynamicImage::from_decoder(decoder).map_err(image_error)?;
+    img.apply_orientation(orientation);
+    // 小图不放大 / Small images are never upscaled
+    if img.width() > size || img.height() > size {
+        img = img.thumbnail(size, size);
+    }
+
+    let mut out = Vec::new();
+    img.write_with_encoder(PngEncoder::new(&mut out))
+        .map_err(image_error)?;
+    std::fs::create_dir_all(&dir)?;
+    // 先写临时文件再改名，避免并发请求读到半个文件
+    // Write then rename so concurrent requests never see a partial file
+    let tmp = dir.join(format!("{}.tmp-{}", key, std::process::id()));
+    std::fs::write(&tmp, &out)?;
+    std::fs::rename(&tmp, &target)?;
+    Ok(key)
+}
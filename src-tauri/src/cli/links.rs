@@ -0,0 +1,181 @@
+//! 链接检查 / Link checking
+//!
+//! 检查文档中的本地链接、图片与锚点（`#标题`）是否存在；可选检查外部链接的 HTTP 状态。
+//! Verifies local links, images and heading anchors; optionally probes external URLs.
+
+use crate::export::is_markdown;
+use crate::git::display;
+use crate::markdown::{self, SlugSet};
+use futures_util::StreamExt;
+use percent_encoding::percent_decode_str;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 外部链接请求超时 / Timeout for external requests
+const EXTERNAL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 同时检查的外部链接数 / Concurrent external checks
+const EXTERNAL_CONCURRENCY: usize = 8;
+
+/// 失效的链接 / A broken link
+#[derive(Debug, serde::Serialize)]
+pub struct BrokenLink {
+    pub document: String,
+    pub line: usize,
+    pub target: String,
+    pub reason: String,
+}
+
+/// 缓存每个文档的标题锚点 / Heading anchors per document, cached
+#[derive(Default)]
+struct Anchors {
+    cache: HashMap<PathBuf, HashSet<String>>,
+}
+
+impl Anchors {
+    fn of(&mut self, path: &Path) -> &HashSet<String> {
+        self.cache.entry(path.to_path_buf()).or_insert_with(|| {
+            let text = std::fs::read_to_string(path).unwrap_or_default();
+            markdown::collect_headings(&text, &mut SlugSet::default())
+                .into_iter()
+                .map(|h| h.id)
+                .collect()
+        })
+    }
+}
+
+fn is_remote(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// check
+/// 检查文档列表中的链接；`external` 为真时同时请求外部链接
+pub fn check(files: &[PathBuf], external: bool) -> Vec<BrokenLink> {
+    let mut anchors = Anchors::default();
+    let mut broken = Vec::new();
+    // (文档, 行号, 地址) / (document, line, url)
+    let mut remote: Vec<(String, usize, String)> = Vec::new();
+
+    for doc in files {
+        let text = match crate::export::read_source(doc) {
+            Ok(t) => t,
+            Err(e) => {
+                broken.push(BrokenLink {
+                    document: display(doc),
+                    line: 0,
+                    target: String::new(),
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let base = doc.parent().unwrap_or(Path::new("."));
+        for reference in markdown::references(&text) {
+            let url = reference.url.trim();
+            let mut report = |reason: &str| {
+                broken.push(BrokenLink {
+                    document: display(doc),
+                    line: reference.line,
+                    target: url.to_string(),
+                    reason: reason.to_string(),
+                })
+            };
+            if url.is_empty() {
+                continue;
+            }
+            if let Some(fragment) = url.strip_prefix('#') {
+                let id = percent_decode_str(fragment).decode_utf8_lossy();
+                if !anchors.of(doc).contains(id.as_ref()) {
+                    report("anchor not found");
+                }
+                continue;
+            }
+            if is_remote(url) {
+                if external {
+                    remote.push((display(doc), reference.line, url.to_string()));
+                }
+                continue;
+            }
+            let Some(target) = markdown::resolve_local(base, url) else {
+                continue;
+            };
+            if !target.exists() {
+                report("file not found");
+                continue;
+            }
+            if let Some((_, fragment)) = url.split_once('#') {
+                if is_markdown(&target) && !fragment.is_empty() {
+                    let id = percent_decode_str(fragment).decode_utf8_lossy();
+                    if !anchors.of(&target).contains(id.as_ref()) {
+                        report("anchor not found");
+                    }
+                }
+            }
+        }
+    }
+
+    if !remote.is_empty() {
+        broken.extend(tauri::async_runtime::block_on(check_remote(remote)));
+    }
+    broken
+}
+
+/// 请求外部链接：先 HEAD，不支持时改用 GET / HEAD first, GET when HEAD is refused
+async fn probe(client: &reqwest::Client, url: &str) -> Result<(), String> {
+    let response = match client.head(url).send().await {
+        Ok(r) if r.status() != reqwest::StatusCode::METHOD_NOT_ALLOWED => r,
+        _ => client.get(url).send().await.map_err(|e| {
+            if e.is_timeout() {
+                "timed out".to_string()
+            } else {
+                "request failed".to_string()
+            }
+        })?,
+    };
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        return Err(format!("HTTP {}", status.as_u16()));
+    }
+    Ok(())
+}
+
+async fn check_remote(links: Vec<(String, usize, String)>) -> Vec<BrokenLink> {
+    let client = match reqwest::Client::builder()
+        .timeout(EXTERNAL_TIMEOUT)
+        .user_agent(concat!("MarkdownMonkey/", env!("CARGO_PKG_VERSION")))
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            log::warn!("Failed to build HTTP client: {}", e);
+            return Vec::new();
+        }
+    };
+    // 相同地址只请求一次 / Each distinct URL is requested once
+    let mut urls: Vec<&str> = links.iter().map(|(_, _, u)| u.as_str()).collect();
+    urls.sort_unstable();
+    urls.dedup();
+    let results: HashMap<&str, Result<(), String>> = futures_util::stream::iter(urls)
+        .map(|url| {
+            let client = &client;
+            async move { (url, probe(client, url).await) }
+        })
+        .buffer_unordered(EXTERNAL_CONCURRENCY)
+        .collect()
+        .await;
+
+    links
+        .iter()
+        .filter_map(|(document, line, url)| match results.get(url.as_str()) {
+            Some(Err(reason)) => Some(BrokenLink {
+                document: document.clone(),
+                line: *line,
+                target: url.clone(),
+                reason: reason.clone(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,453 @@
+//! 命令行模式 / Command-line mode
+//!
+//! 同一个可执行文件在第一个参数为子命令时以无窗口模式运行（导出、渲染、链接检查、搜索、AI 改写），
+//! 供 CI 与脚本复用应用的 Rust 逻辑；否则照常启动图形界面。
+//! Runs headless subcommands before the Tauri builder starts; otherwise the GUI launches.
+
+mod links;
+pub mod open;
+mod search;
+
+use crate::export;
+use crate::git::display;
+use crate::markdown::SlugSet;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// 识别为子命令的第一个参数；其余情况（如文件路径）交给图形界面
+/// First arguments that select headless mode; anything else (e.g. a file path) opens the GUI
+const SUBCOMMANDS: &[&str] = &[
+    "export",
+    "render",
+    "check-links",
+    "search",
+    "ai",
+    "help",
+    "--help",
+    "-h",
+    "--version",
+    "-V",
+];
+
+/// 退出码：发现问题（失效链接、没有匹配） / Exit code when findings are reported
+const EXIT_FINDINGS: i32 = 1;
+/// 退出码：执行出错 / Exit code on errors
+const EXIT_ERROR: i32 = 2;
+
+/// 命令行的工作区以此标签登记，相当于一个窗口 / Label the CLI workspace is registered under
+const WINDOW: &str = "cli";
+
+/// 默认跳过的目录 / Directories skipped when collecting files
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target"];
+
+#[derive(Parser)]
+#[command(
+    name = "markdownmonkey",
+    version,
+    about = "MarkdownMonkey 命令行工具 / command-line tools"
+)]
+struct Cli {
+    /// 工作区根目录，缺省为当前目录 / Workspace root, defaults to the current directory
+    #[arg(long, global = true)]
+    workspace: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Html,
+    Docx,
+    Pdf,
+    Epub,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 导出文档或文件夹 / Export a document or folder
+    Export {
+        /// Markdown 文件或文件夹 / Markdown file or folder
+        input: PathBuf,
+        #[arg(short, long, value_enum)]
+        format: Format,
+        /// 输出路径，缺省写在源文件旁 / Output path, defaults to next to the input
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long)]
+        title: Option<String>,
+    },
+    /// 渲染为 HTML 并输出到标准输出 / Render to HTML on stdout
+    Render {
+        /// Markdown 文件，`-` 表示标准输入 / Markdown file, `-` for stdin
+        input: String,
+        /// 输出完整页面（含样式与目录） / Emit a full page with styles and TOC
+        #[arg(long)]
+        standalone: bool,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// 检查本地链接、图片与锚点 / Check local links, images and anchors
+    CheckLinks {
+        /// 文件或文件夹，缺省为当前目录 / Files or folders, defaults to the current directory
+        paths: Vec<PathBuf>,
+        /// 同时检查外部链接 / Also probe external links
+        #[arg(long)]
+        external: bool,
+        #[arg(long)]
+        json: bool,
+    },
+    /// 在 Markdown 文件中搜索 / Search Markdown files
+    Search {
+        /// 正则表达式 / Regular expression
+        pattern: String,
+        /// 文件或文件夹，缺省为当前目录 / Files or folders, defaults to the current directory
+        paths: Vec<PathBuf>,
+        #[arg(short, long)]
+        ignore_case: bool,
+        /// 按普通文本匹配 / Match the pattern literally
+        #[arg(short = 'F', long)]
+        fixed_strings: bool,
+        #[arg(long)]
+        json: bool,
+    },
+    /// AI 相关命令 / AI commands
+    Ai {
+        #[command(subcommand)]
+        command: AiCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum AiCommand {
+    /// 按指令改写文档 / Rewrite a document following an instruction
+    Rewrite {
+        input: PathBuf,
+        #[arg(short, long)]
+        instruction: String,
+        /// open_ai / claude / deep_seek / kimi / open_router / ollama
+        #[arg(long, env = "MARKDOWNMONKEY_PROVIDER", default_value = "open_ai")]
+        provider: String,
+        #[arg(
+            long,
+            env = "MARKDOWNMONKEY_API_KEY",
+            hide_env_values = true,
+            default_value = ""
+        )]
+        api_key: String,
+        #[arg(long, env = "MARKDOWNMONKEY_MODEL")]
+        model: Option<String>,
+        #[arg(long, env = "MARKDOWNMONKEY_BASE_URL")]
+        base_url: Option<String>,
+        /// 回复长度上限，须容纳整篇改写后的文档 / Reply budget; must fit the whole rewritten document
+        #[arg(long, env = "MARKDOWNMONKEY_MAX_TOKENS")]
+        max_tokens: Option<u32>,
+        #[arg(short, long, conflicts_with = "in_place")]
+        output: Option<PathBuf>,
+        /// 直接覆盖源文件 / Overwrite the input file
+        #[arg(long)]
+        in_place: bool,
+    },
+}
+
+const REWRITE_SYSTEM_PROMPT: &str = "You are an editor. Rewrite the Markdown document according to the instruction. Reply with the complete rewritten document only, without commentary and without wrapping it in a code fence.";
+
+/// run
+/// 第一个参数是子命令时执行并返回退出码；否则返回 `None`，由调用方启动图形界面
+pub fn run(args: &[String]) -> Option<i32> {
+    let first = args.get(1)?;
+    if !SUBCOMMANDS.contains(&first.as_str()) {
+        return None;
+    }
+    attach_console();
+    let cli = match Cli::try_parse_from(args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            e.print().ok();
+            return Some(e.exit_code());
+        }
+    };
+    let workspace = match cli
+        .workspace
+        .clone()
+        .map(Ok)
+        .unwrap_or_else(std::env::current_dir)
+        .and_then(|dir| Ok(std::env::current_dir()?.join(dir)))
+    {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return Some(EXIT_ERROR);
+        }
+    };
+    crate::set_workspace_root(WINDOW, &workspace.to_string_lossy());
+    Some(match execute(cli.command) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            EXIT_ERROR
+        }
+    })
+}
+
+/// Windows 发布版是 GUI 子系统，需要挂到父进程的控制台才能输出
+/// Release builds on Windows use the GUI subsystem; attach to the parent console for output
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// 展开文件与文件夹为 Markdown 文件列表 / Expands files and folders into Markdown files
+fn markdown_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let p = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            if p.is_dir() {
+                if !SKIPPED_DIRS.contains(&name.as_str()) {
+                    walk(&p, out);
+                }
+            } else if export::is_markdown(&p) {
+                out.push(p);
+            }
+        }
+    }
+
+    let defaults = [PathBuf::from(".")];
+    let paths = if paths.is_empty() {
+        &defaults[..]
+    } else {
+        paths
+    };
+    let mut out = Vec::new();
+    for p in paths {
+        if p.is_dir() {
+            walk(p, &mut out);
+        } else {
+            out.push(p.clone());
+        }
+    }
+    // 缺省目录下的结果不带 `./` 前缀 / Drop the `./` prefix from default-directory results
+    for p in out.iter_mut() {
+        if let Ok(rest) = p.strip_prefix(".") {
+            *p = rest.to_path_buf();
+        }
+    }
+    out.sort();
+    out.dedup();
+    out
+}
+
+fn write_output(output: Option<&Path>, content: &str) -> Result<(), String> {
+    match output {
+        Some(path) => std::fs::write(path, content).map_err(|e| e.to_string()),
+        None => std::io::stdout()
+            .write_all(content.as_bytes())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+fn execute(command: Command) -> Result<i32, String> {
+    match command {
+        Command::Export {
+            input,
+            format,
+            output,
+            title,
+        } => {
+            let output = output.map(|p| p.to_string_lossy().to_string());
+            let written = match format {
+                Format::Html => {
+                    let mut options = export::html::HtmlExportOptions {
+                        output,
+                        title,
+                        window: WINDOW.to_string(),
+                        ..Default::default()
+                    };
+                    crate::apply_export_config(WINDOW, &mut options);
+                    export::html::export(&input, &options)?
+                }
+                Format::Docx => export::docx::export(
+                    &input,
+                    &export::docx::DocxExportOptions {
+                        output,
+                        title,
+                        window: WINDOW.to_string(),
+                        ..Default::default()
+                    },
+                )?,
+                Format::Pdf => export::pdf::export(
+                    &input,
+                    &export::pdf::PdfExportOptions {
+                        output,
+                        title,
+                        window: WINDOW.to_string(),
+                        ..Default::default()
+                    },
+                )?,
+                Format::Epub => {
+                    if !input.is_dir() {
+                        return Err("EPUB export expects a folder of chapters".to_string());
+                    }
+                    export::epub::export(
+                        &input,
+                        &export::epub::EpubManifest {
+                            output,
+                            title,
+                            window: WINDOW.to_string(),
+                            ..Default::default()
+                        },
+                    )?
+                }
+            };
+            println!("{}", display(&written));
+            Ok(0)
+        }
+        Command::Render {
+            input,
+            standalone,
+            output,
+        } => {
+            let html = if input == "-" {
+                if standalone {
+                    return Err("--standalone requires a file".to_string());
+                }
+                let mut text = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut text)
+                    .map_err(|e| e.to_string())?;
+                export::html::render_fragment(&text, &mut SlugSet::default(), |_| None).0
+            } else if standalone {
+                export::html::render_document(
+                    &[PathBuf::from(&input)],
+                    &export::html::HtmlExportOptions {
+                        window: WINDOW.to_string(),
+                        ..Default::default()
+                    },
+                )?
+            } else {
+                let text = export::read_source(Path::new(&input))?;
+                export::html::render_fragment(&text, &mut SlugSet::default(), |_| None).0
+            };
+            write_output(output.as_deref(), &html)?;
+            Ok(0)
+        }
+        Command::CheckLinks {
+            paths,
+            external,
+            json,
+        } => {
+            let files = markdown_files(&paths);
+            let broken = links::check(&files, external);
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&broken).map_err(|e| e.to_string())?
+                );
+            } else {
+                for b in &broken {
+                    println!("{}:{}: {} ({})", b.document, b.line, b.target, b.reason);
+                }
+                eprintln!(
+                    "checked {} file(s), {} broken link(s)",
+                    files.len(),
+                    broken.len()
+                );
+            }
+            Ok(if broken.is_empty() { 0 } else { EXIT_FINDINGS })
+        }
+        Command::Search {
+            pattern,
+            paths,
+            ignore_case,
+            fixed_strings,
+            json,
+        } => {
+            let pattern = if fixed_strings {
+                regex::escape(&pattern)
+            } else {
+                pattern
+            };
+            let regex = regex::RegexBuilder::new(&pattern)
+                .case_insensitive(ignore_case)
+                .build()
+                .map_err(|e| e.to_string())?;
+            let matches = search::search(&markdown_files(&paths), &regex);
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&matches).map_err(|e| e.to_string())?
+                );
+            } else {
+                for m in &matches {
+                    println!("{}:{}:{}: {}", m.path, m.line, m.column, m.text);
+                }
+            }
+            Ok(if matches.is_empty() { EXIT_FINDINGS } else { 0 })
+        }
+        Command::Ai {
+            command:
+                AiCommand::Rewrite {
+                    input,
+                    instruction,
+                    provider,
+                    api_key,
+                    model,
+                    base_url,
+                    max_tokens,
+                    output,
+                    in_place,
+                },
+        } => {
+            let text = export::read_source(&input)?;
+            let provider = serde_json::from_value(serde_json::Value::String(provider.clone()))
+                .map_err(|_| format!("unknown provider: {}", provider))?;
+            let request = crate::AiRequest {
+                provider,
+                api_key,
+                prompt: format!("Instruction: {}\n\n---\n\n{}", instruction, text),
+                model,
+                system_prompt: Some(REWRITE_SYSTEM_PROMPT.to_string()),
+                temperature: None,
+                max_tokens,
+                base_url,
+                messages: None,
+            };
+            let reply = tauri::async_runtime::block_on(crate::ai_complete(request))?;
+            let rewritten = format!("{}\n", strip_fence(&reply).trim_end());
+            let target = if in_place {
+                Some(input.as_path())
+            } else {
+                output.as_deref()
+            };
+            write_output(target, &rewritten)?;
+            Ok(0)
+        }
+    }
+}
+
+/// 模型有时仍会用代码围栏包裹整篇文档 / Models sometimes wrap the whole document in a fence
+fn strip_fence(text: &str) -> &str {
+    let trimmed = text.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+    let Some(body) = rest.strip_suffix("```") else {
+        return trimmed;
+    };
+    // 去掉围栏后的语言标记行 / Drop the info string line after the opening fence
+    body.split_once('\n').map(|(_, b)| b).unwrap_or(body)
+}
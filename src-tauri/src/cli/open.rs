@@ -0,0 +1,140 @@
+//! 图形界面启动参数 / GUI launch arguments
+//!
+//! 解析启动或第二实例传入的参数：多个文件、`file.md:42:7` 形式的跳转位置、作为工作区打开的文件夹，
+//! 以及 `--new-window`、`--readonly` 等选项。首次启动与单实例回调共用同一套逻辑。
+//! Parses file, `path:line:column` and folder arguments plus flags, shared by launch and single-instance.
+
+use super::display;
+use crate::assets::normalize_path;
+use std::path::{Path, PathBuf};
+
+/// 要打开的文件及可选的跳转位置 / A file to open with an optional cursor position
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OpenTarget {
+    pub path: String,
+    /// 行号（从 1 开始） / 1-based line
+    pub line: Option<u32>,
+    /// 列号（从 1 开始） / 1-based column
+    pub column: Option<u32>,
+}
+
+/// OpenRequest
+/// 一次启动（或第二实例）请求打开的内容
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct OpenRequest {
+    pub files: Vec<OpenTarget>,
+    /// 作为工作区打开的文件夹，多个时以最后一个为准 / Folders to open as workspace; the last one wins
+    pub folders: Vec<String>,
+    /// 在新窗口中打开 / Open in a new window
+    pub new_window: bool,
+    /// 以只读方式打开文件 / Open the files read-only
+    pub readonly: bool,
+}
+
+impl OpenRequest {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.folders.is_empty()
+    }
+
+    /// push
+    /// 加入一个绝对路径：文件夹作为工作区，Markdown 文件作为文档；其他路径返回 false
+    pub fn push(&mut self, path: &Path, line: Option<u32>, column: Option<u32>) -> bool {
+        if path.is_dir() {
+            self.folders.push(display(path));
+        } else if !crate::export::is_markdown(path) || !path.is_file() {
+            return false;
+        } else {
+            let path = display(path);
+            if !self.files.iter().any(|f| f.path == path) {
+                self.files.push(OpenTarget { path, line, column });
+            }
+        }
+        true
+    }
+}
+
+/// 拆分 `path:line[:column]` 后缀；数字段之外的冒号（如 `C:\`）保持不变
+/// Splits a trailing `:line[:column]`; other colons such as `C:\` are left alone
+fn split_position(arg: &str) -> (&str, Option<u32>, Option<u32>) {
+    let number = |s: &str| -> Option<u32> {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        s.parse().ok().filter(|n| *n > 0)
+    };
+    let Some((rest, last)) = arg.rsplit_once(':') else {
+        return (arg, None, None);
+    };
+    let Some(last) = number(last) else {
+        return (arg, None, None);
+    };
+    if let Some((path, line)) = rest.rsplit_once(':') {
+        if let Some(line) = number(line) {
+            return (path, Some(line), Some(last));
+        }
+    }
+    (rest, Some(last), None)
+}
+
+fn absolute(path: &str, cwd: &Path) -> PathBuf {
+    normalize_path(&cwd.join(path))
+}
+
+/// parse
+/// 解析命令行参数（含程序名），相对路径基于 `cwd`（第二实例为其自身的工作目录）
+pub fn parse(args: &[String], cwd: &Path) -> OpenRequest {
+    let mut request = OpenRequest::default();
+    let mut options_done = false;
+    for arg in args.iter().skip(1) {
+        if !options_done && arg.starts_with('-') {
+            match arg.as_str() {
+                "--" => options_done = true,
+                "--new-window" => request.new_window = true,
+                "--readonly" | "--read-only" => request.readonly = true,
+                // 系统可能附加其他参数（如 macOS 的 `-psn_`），忽略即可
+                // The OS may append its own flags (e.g. macOS `-psn_`); ignore them
+                _ => log::debug!("Ignoring launch option {}", arg),
+            }
+            continue;
+        }
+
+        // 整个参数本身存在时不再拆分位置（文件名可能含冒号）
+        // Only split a position off when the argument as a whole does not exist
+        let whole = absolute(arg, cwd);
+        let (path, line, column) = if whole.exists() {
+            (whole, None, None)
+        } else {
+            let (path, line, column) = split_position(arg);
+            (absolute(path, cwd), line, column)
+        };
+
+        if !request.push(&path, line, column) {
+            log::warn!("Ignoring launch argument {}", arg);
+        }
+    }
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_position_suffixes() {
+        assert_eq!(split_position("a.md"), ("a.md", None, None));
+        assert_eq!(split_position("a.md:12"), ("a.md", Some(12), None));
+        assert_eq!(split_position("a.md:12:3"), ("a.md", Some(12), Some(3)));
+        assert_eq!(split_position("a.md:x:3"), ("a.md:x", Some(3), None));
+        assert_eq!(split_position("a.md:0"), ("a.md:0", None, None));
+        assert_eq!(split_position("a.md:"), ("a.md:", None, None));
+    }
+
+    #[test]
+    fn split_position_keeps_drive_letters() {
+        assert_eq!(split_position(r"C:\a.md"), (r"C:\a.md", None, None));
+        assert_eq!(
+            split_position(r"C:\a.md:4:2"),
+            (r"C:\a.md", Some(4), Some(2))
+        );
+    }
+}
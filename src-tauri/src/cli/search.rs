@@ -0,0 +1,41 @@
+//! 全文搜索 / Full-text search
+//!
+//! 在 Markdown 文件中按正则或普通文本搜索，输出 `路径:行:列`，便于编辑器直接跳转。
+//! Searches Markdown files and reports `path:line:column` locations.
+
+use crate::git::display;
+use regex::Regex;
+use std::path::PathBuf;
+
+/// 匹配结果 / A single match
+#[derive(Debug, serde::Serialize)]
+pub struct SearchMatch {
+    pub path: String,
+    /// 行号（从 1 开始） / 1-based line
+    pub line: usize,
+    /// 列号（从 1 开始，按字符计） / 1-based column, in characters
+    pub column: usize,
+    pub text: String,
+}
+
+/// search
+/// 逐行搜索文件列表，返回所有匹配
+pub fn search(files: &[PathBuf], pattern: &Regex) -> Vec<SearchMatch> {
+    let mut out = Vec::new();
+    for path in files {
+        let Ok(text) = crate::export::read_source(path) else {
+            continue;
+        };
+        for (i, line) in text.lines().enumerate() {
+            for m in pattern.find_iter(line) {
+                out.push(SearchMatch {
+                    path: display(path),
+                    line: i + 1,
+                    column: line[..m.start()].chars().count() + 1,
+                    text: line.to_string(),
+                });
+            }
+        }
+    }
+    out
+}
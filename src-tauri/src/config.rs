@@ -0,0 +1,102 @@
+//! 工作区配置 / Workspace configuration
+//!
+//! 读取工作区根目录下的 `.markdownmonkey/config.toml`，覆盖全局设置中的资源目录、默认 AI 服务、
+//! 忽略规则、导出主题、日记路径与检查规则；该文件可提交到仓库，供团队共享。
+//! Loads the per-workspace `config.toml` whose values take precedence over global settings.
+
+use crate::error::AppError;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// 配置目录（相对工作区根目录） / Config directory, relative to the workspace root
+pub const CONFIG_DIR: &str = ".markdownmonkey";
+
+/// 配置文件名 / Config file name
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// 默认 AI 服务 / Default AI provider
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AiSettings {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub base_url: Option<String>,
+}
+
+/// 导出设置 / Export settings
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportSettings {
+    /// 内置主题：light / dark
+    pub theme: Option<String>,
+    /// 追加的 CSS 文件（相对工作区根目录） / Extra CSS, relative to the workspace root
+    pub css: Option<String>,
+}
+
+/// 日记设置 / Daily notes settings
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JournalSettings {
+    /// 日记路径模式（相对工作区根目录），如 `journal/{yyyy}/{mm}-{dd}.md`
+    pub path: Option<String>,
+    /// 新建日记使用的模板名（见 `.markdownmonkey/templates/`）
+    pub template: Option<String>,
+}
+
+/// WorkspaceConfig
+/// 工作区配置，未设置的项沿用全局设置
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceConfig {
+    /// 资源目录模板（同 `save_image` 的 `assets_dir`）
+    pub assets_dir: Option<String>,
+    /// 文件列表中忽略的路径（glob，相对工作区根目录），如 `drafts/**`
+    pub ignore: Vec<String>,
+    pub ai: AiSettings,
+    pub export: ExportSettings,
+    pub journal: JournalSettings,
+    /// 检查规则：规则名 → 开关或选项，原样交给前端 / Rule name → on/off or options, passed through
+    pub lint: BTreeMap<String, serde_json::Value>,
+    #[serde(skip)]
+    ignore_set: GlobSet,
+}
+
+impl WorkspaceConfig {
+    /// is_ignored
+    /// 相对工作区根目录的路径是否被忽略规则匹配
+    pub fn is_ignored(&self, rel: &Path) -> bool {
+        !self.ignore.is_empty() && self.ignore_set.is_match(rel)
+    }
+}
+
+/// 配置文件路径 / Path of the config file inside `root`
+pub fn config_path(root: &Path) -> PathBuf {
+    root.join(CONFIG_DIR).join(CONFIG_FILE)
+}
+
+fn invalid(msg: impl std::fmt::Display) -> AppError {
+    AppError::InvalidInput(format!(
+        "工作区配置无效 ({}/{}): {}",
+        CONFIG_DIR, CONFIG_FILE, msg
+    ))
+}
+
+/// load
+/// 读取工作区配置；文件不存在时返回默认配置
+pub fn load(root: &Path) -> Result<WorkspaceConfig, AppError> {
+    let path = config_path(root);
+    if !path.is_file() {
+        return Ok(WorkspaceConfig::default());
+    }
+    crate::check_file_size(&path).map_err(AppError::InvalidInput)?;
+    let text = std::fs::read_to_string(&path)?;
+    let mut config: WorkspaceConfig = toml::from_str(&text).map_err(|e| invalid(e.message()))?;
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in &config.ignore {
+        builder.add(Glob::new(pattern.trim_start_matches("./")).map_err(invalid)?);
+    }
+    config.ignore_set = builder.build().map_err(invalid)?;
+    Ok(config)
+}
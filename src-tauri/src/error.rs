@@ -11,6 +11,7 @@ pub enum AppError {
     Unauthorized,
     InvalidInput(String),
     WorkspaceError(String),
+    Git(String),
     Unknown(String),
 }
 
@@ -25,6 +26,7 @@ impl fmt::Display for AppError {
             AppError::Unauthorized => write!(f, "认证失败，请检查 API Key"),
             AppError::InvalidInput(msg) => write!(f, "输入无效: {}", msg),
             AppError::WorkspaceError(msg) => write!(f, "工作区错误: {}", msg),
+            AppError::Git(msg) => write!(f, "Git 操作失败: {}", msg),
             AppError::Unknown(_) => write!(f, "未知错误"),
         }
     }
@@ -65,6 +67,21 @@ impl From<keyring::Error> for AppError {
     }
 }
 
+impl From<zip::result::ZipError> for AppError {
+    fn from(err: zip::result::ZipError) -> Self {
+        match err {
+            zip::result::ZipError::Io(e) => AppError::Io(e),
+            _ => AppError::InvalidInput("无效的压缩包".to_string()),
+        }
+    }
+}
+
+impl From<git2::Error> for AppError {
+    fn from(err: git2::Error) -> Self {
+        AppError::Git(err.message().to_string())
+    }
+}
+
 /// 清理错误消息，移除可能的敏感信息
 fn sanitize_error_message(msg: &str) -> String {
     // 移除可能包含的 API Key、Token 等敏感信息
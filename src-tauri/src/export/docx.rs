@@ -0,0 +1,801 @@
+//! DOCX 导出 / DOCX export
+//!
+//! 将 Markdown 事件流转换为 Office Open XML 包：标题映射到 Word 标题样式，
+//! 支持表格、代码块、列表、图片（相对文档解析）与脚注；可选参考 .docx 提供样式。
+
+use super::{collect_sources, default_output, read_source};
+use crate::error::AppError;
+use crate::markdown::{self, escape_html as xml_escape, SlugSet};
+use pulldown_cmark::{Alignment, Event, Tag, TagEnd};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// 版心宽度（EMU，约 6 英寸） / Max image width in EMU (about 6 inches)
+const MAX_IMAGE_WIDTH_EMU: u64 = 5_486_400;
+const EMU_PER_PIXEL: u64 = 9525;
+/// 表格总宽（twips） / Table width in twips
+const TABLE_WIDTH_TWIPS: usize = 9000;
+
+/// DocxExportOptions
+/// DOCX 导出选项
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct DocxExportOptions {
+    #[serde(default)]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 参考文档：使用其 word/styles.xml 作为样式表
+    #[serde(default)]
+    pub reference_docx: Option<String>,
+    /// 发起导出的窗口（后端填写），图片与参考文档须在其工作区内
+    #[serde(skip)]
+    pub window: String,
+}
+
+struct Para {
+    style: Option<String>,
+    num: Option<(usize, usize)>,
+    jc: Option<&'static str>,
+    runs: String,
+}
+
+#[derive(Default)]
+struct Format {
+    bold: u32,
+    italic: u32,
+    strike: u32,
+    link: u32,
+}
+
+struct MediaFile {
+    rel_id: String,
+    name: String,
+    bytes: Vec<u8>,
+}
+
+/// DocxWriter
+/// 状态机：逐个消费 pulldown-cmark 事件，写入 document.xml 及其附属部件
+#[derive(Default)]
+struct DocxWriter {
+    /// 输出缓冲栈：正文 / 表格 / 行 / 单元格 / 脚注
+    out: Vec<String>,
+    para: Option<Para>,
+    fmt: Format,
+    /// 当前嵌套列表对应的 numId / numIds of the currently open lists
+    lists: Vec<usize>,
+    /// (是否有序, 起始编号, 所在层级)
+    nums: Vec<(bool, u64, usize)>,
+    quote_depth: usize,
+    in_footnote: bool,
+    code_block: Option<String>,
+    image_alt: Option<(String, String)>,
+    skip_text: bool,
+    table_aligns: Vec<Alignment>,
+    cell_index: usize,
+    in_table_head: bool,
+    /// 按（源文件序号, 标签）编号，不同文件的同名脚注互不影响
+    /// Keyed by (source index, label) so equal labels in different files stay apart
+    footnote_ids: HashMap<(usize, String), usize>,
+    footnotes: Vec<(usize, String)>,
+    hyperlinks: Vec<(String, String)>,
+    media: Vec<MediaFile>,
+    bookmark_id: usize,
+    drawing_id: usize,
+    base_dir: PathBuf,
+    window: String,
+    /// 当前源文件的序号 / Index of the source being written
+    source: usize,
+}
+
+impl DocxWriter {
+    fn new() -> Self {
+        Self {
+            out: vec![String::new()],
+            ..Default::default()
+        }
+    }
+
+    fn buf(&mut self) -> &mut String {
+        self.out.last_mut().expect("output stack is never empty")
+    }
+
+    fn context_style(&self) -> Option<String> {
+        if self.in_footnote {
+            Some("FootnoteText".to_string())
+        } else if self.quote_depth > 0 {
+            Some("Quote".to_string())
+        } else if !self.lists.is_empty() {
+            Some("ListParagraph".to_string())
+        } else {
+            None
+        }
+    }
+
+    fn start_para(&mut self, style: Option<String>, num: Option<(usize, usize)>) {
+        self.flush_para();
+        self.para = Some(Para {
+            style,
+            num,
+            jc: None,
+            runs: String::new(),
+        });
+    }
+
+    fn ensure_para(&mut self) {
+        if self.para.is_none() {
+            let style = self.context_style();
+            self.start_para(style, None);
+        }
+    }
+
+    fn flush_para(&mut self) {
+        if let Some(p) = self.para.take() {
+            let mut ppr = String::new();
+            if let Some(style) = &p.style {
+                ppr.push_str(&format!("<w:pStyle w:val=\"{}\"/>", style));
+            }
+            if let Some((num_id, ilvl)) = p.num {
+                ppr.push_str(&format!(
+                    "<w:numPr><w:ilvl w:val=\"{}\"/><w:numId w:val=\"{}\"/></w:numPr>",
+                    ilvl, num_id
+                ));
+            }
+            if let Some(jc) = p.jc {
+                ppr.push_str(&format!("<w:jc w:val=\"{}\"/>", jc));
+            }
+            let xml = format!("<w:p><w:pPr>{}</w:pPr>{}</w:p>", ppr, p.runs);
+            self.buf().push_str(&xml);
+        }
+    }
+
+    fn run_props(&self, code: bool) -> String {
+        let mut rpr = String::new();
+        if code {
+            rpr.push_str("<w:rStyle w:val=\"CodeChar\"/>");
+        } else if self.fmt.link > 0 {
+            rpr.push_str("<w:rStyle w:val=\"Hyperlink\"/>");
+        }
+        if self.fmt.bold > 0 {
+            rpr.push_str("<w:b/>");
+        }
+        if self.fmt.italic > 0 {
+            rpr.push_str("<w:i/>");
+        }
+        if self.fmt.strike > 0 {
+            rpr.push_str("<w:strike/>");
+        }
+        rpr
+    }
+
+    fn push_run(&mut self, text: &str, code: bool) {
+        if text.is_empty() {
+            return;
+        }
+        self.ensure_para();
+        let rpr = self.run_props(code);
+        let rpr = if rpr.is_empty() {
+            rpr
+        } else {
+            format!("<w:rPr>{}</w:rPr>", rpr)
+        };
+        let run = format!(
+            "<w:r>{}<w:t xml:space=\"preserve\">{}</w:t></w:r>",
+            rpr,
+            xml_escape(text)
+        );
+        if let Some(p) = self.para.as_mut() {
+            p.runs.push_str(&run);
+        }
+    }
+
+    fn push_raw_run(&mut self, xml: &str) {
+        self.ensure_para();
+        if let Some(p) = self.para.as_mut() {
+            p.runs.push_str(xml);
+        }
+    }
+
+    fn footnote_id(&mut self, label: &str) -> usize {
+        let next = self.footnote_ids.len() + 1;
+        *self
+            .footnote_ids
+            .entry((self.source, label.to_string()))
+            .or_insert(next)
+    }
+
+    fn add_hyperlink(&mut self, url: &str) -> String {
+        let rel_id = format!("rIdLink{}", self.hyperlinks.len() + 1);
+        self.hyperlinks.push((rel_id.clone(), url.to_string()));
+        rel_id
+    }
+
+    fn start_table(&mut self, aligns: Vec<Alignment>) {
+        self.flush_para();
+        self.table_aligns = aligns;
+        self.out.push(String::new());
+    }
+
+    fn end_table(&mut self) {
+        self.flush_para();
+        let rows = self.out.pop().unwrap_or_default();
+        let cols = self.table_aligns.len().max(1);
+        let col_w = TABLE_WIDTH_TWIPS / cols;
+        let grid: String = (0..cols)
+            .map(|_| format!("<w:gridCol w:w=\"{}\"/>", col_w))
+            .collect();
+        let xml = format!(
+            "<w:tbl><w:tblPr><w:tblStyle w:val=\"TableGrid\"/><w:tblW w:w=\"0\" w:type=\"auto\"/></w:tblPr><w:tblGrid>{}</w:tblGrid>{}</w:tbl><w:p/>",
+            grid, rows
+        );
+        self.buf().push_str(&xml);
+    }
+
+    fn start_row(&mut self) {
+        self.cell_index = 0;
+        self.out.push(String::new());
+    }
+
+    fn end_row(&mut self) {
+        let cells = self.out.pop().unwrap_or_default();
+        let trpr = if self.in_table_head {
+            "<w:trPr><w:tblHeader/></w:trPr>"
+        } else {
+            ""
+        };
+        let xml = format!("<w:tr>{}{}</w:tr>", trpr, cells);
+        self.buf().push_str(&xml);
+    }
+
+    fn start_cell(&mut self) {
+        self.out.push(String::new());
+        let jc = match self.table_aligns.get(self.cell_index) {
+            Some(Alignment::Center) => Some("center"),
+            Some(Alignment::Right) => Some("right"),
+            _ => None,
+        };
+        self.start_para(None, None);
+        if let Some(p) = self.para.as_mut() {
+            p.jc = jc;
+        }
+        if self.in_table_head {
+            self.fmt.bold += 1;
+        }
+    }
+
+    fn end_cell(&mut self) {
+        self.flush_para();
+        if self.in_table_head {
+            self.fmt.bold = self.fmt.bold.saturating_sub(1);
+        }
+        let mut content = self.out.pop().unwrap_or_default();
+        if content.is_empty() {
+            content.push_str("<w:p/>");
+        }
+        let xml = format!("<w:tc><w:tcPr/>{}</w:tc>", content);
+        self.buf().push_str(&xml);
+        self.cell_index += 1;
+    }
+
+    fn write_code_block(&mut self, code: &str) {
+        self.flush_para();
+        for line in code.trim_end_matches('\n').split('\n') {
+            self.start_para(Some("SourceCode".to_string()), None);
+            self.push_run(line.trim_end_matches('\r'), false);
+            self.flush_para();
+        }
+    }
+
+    /// 嵌入图片；无法读取时退化为替代文本
+    fn write_image(&mut self, url: &str, alt: &str) {
+        match self.load_image(url) {
+            Some((bytes, ext, (w, h))) => {
+                self.drawing_id += 1;
+                let id = self.drawing_id;
+                let rel_id = format!("rIdImg{}", id);
+                let name = format!("image{}.{}", id, ext);
+                let mut cx = w as u64 * EMU_PER_PIXEL;
+                let mut cy = h as u64 * EMU_PER_PIXEL;
+                if cx > MAX_IMAGE_WIDTH_EMU {
+                    cy = cy * MAX_IMAGE_WIDTH_EMU / cx;
+                    cx = MAX_IMAGE_WIDTH_EMU;
+                }
+                let xml = format!(
+                    concat!(
+                        "<w:r><w:drawing><wp:inline distT=\"0\" distB=\"0\" distL=\"0\" distR=\"0\">",
+                        "<wp:extent cx=\"{cx}\" cy=\"{cy}\"/><wp:docPr id=\"{id}\" name=\"Picture {id}\" descr=\"{alt}\"/>",
+                        "<wp:cNvGraphicFramePr><a:graphicFrameLocks xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\" noChangeAspect=\"1\"/></wp:cNvGraphicFramePr>",
+                        "<a:graphic xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\">",
+                        "<a:graphicData uri=\"http://schemas.openxmlformats.org/drawingml/2006/picture\">",
+                        "<pic:pic xmlns:pic=\"http://schemas.openxmlformats.org/drawingml/2006/picture\">",
+                        "<pic:nvPicPr><pic:cNvPr id=\"{id}\" name=\"{name}\"/><pic:cNvPicPr/></pic:nvPicPr>",
+                        "<pic:blipFill><a:blip r:embed=\"{rel}\"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill>",
+                        "<pic:spPr><a:xfrm><a:off x=\"0\" y=\"0\"/><a:ext cx=\"{cx}\" cy=\"{cy}\"/></a:xfrm>",
+                        "<a:prstGeom prst=\"rect\"><a:avLst/></a:prstGeom></pic:spPr>",
+                        "</pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r>"
+                    ),
+                    cx = cx,
+                    cy = cy,
+                    id = id,
+                    alt = xml_escape(alt),
+                    name = name,
+                    rel = rel_id
+                );
+                self.push_raw_run(&xml);
+                self.media.push(MediaFile {
+                    rel_id,
+                    name,
+                    bytes,
+                });
+            }
+            None => {
+                self.fmt.italic += 1;
+                let text = if alt.is_empty() { url } else { alt };
+                self.push_run(&format!("[{}]", text), false);
+                self.fmt.italic -= 1;
+            }
+        }
+    }
+
+    /// 读取本地图片：Word 不支持的格式转码为 PNG
+    fn load_image(&self, url: &str) -> Option<(Vec<u8>, &'static str, (u32, u32))> {
+        let p = markdown::resolve_local(&self.base_dir, url)?;
+        if let Err(e) = crate::ensure_in_workspace(&self.window, &p) {
+            log::warn!("Skip image {:?}: {}", p, e);
+            return None;
+        }
+        let bytes = std::fs::read(&p).ok()?;
+        let ext = p
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let native = match ext.as_str() {
+            "png" => Some("png"),
+            "jpg" | "jpeg" => Some("jpeg"),
+            "gif" => Some("gif"),
+            "bmp" => Some("bmp"),
+            _ => None,
+        };
+        let img = image::load_from_memory(&bytes).ok()?;
+        let dims = (img.width(), img.height());
+        match native {
+            Some(ext) => Some((bytes, ext, dims)),
+            None => {
+                let mut png = std::io::Cursor::new(Vec::new());
+                img.write_to(&mut png, image::ImageFormat::Png).ok()?;
+                Some((png.into_inner(), "png", dims))
+            }
+        }
+    }
+
+    fn write_document(&mut self, src: &str, slugs: &mut SlugSet) {
+        let (events, _) = markdown::parse_with_anchors(src, slugs);
+        for event in events {
+            self.handle(event);
+        }
+        self.flush_para();
+    }
+
+    fn handle(&mut self, event: Event) {
+        if let Some(code) = self.code_block.as_mut() {
+            match event {
+                Event::Text(t) => code.push_str(&t),
+                Event::End(TagEnd::CodeBlock) => {
+                    let code = self.code_block.take().unwrap_or_default();
+                    self.write_code_block(&code);
+                }
+                _ => {}
+            }
+            return;
+        }
+        if let Some((_, alt)) = self.image_alt.as_mut() {
+            match event {
+                Event::Text(t) | Event::Code(t) => alt.push_str(&t),
+                Event::End(TagEnd::Image) => {
+                    let (url, alt) = self.image_alt.take().unwrap_or_default();
+                    self.write_image(&url, &alt);
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match event {
+            Event::Start(tag) => self.start_tag(tag),
+            Event::End(tag) => self.end_tag(tag),
+            Event::Text(t) if !self.skip_text => self.push_run(&t, false),
+            Event::Code(t) => self.push_run(&t, true),
+            Event::SoftBreak => self.push_run(" ", false),
+            Event::HardBreak => self.push_raw_run("<w:r><w:br/></w:r>"),
+            Event::Rule => {
+                self.flush_para();
+                self.buf().push_str(
+                    "<w:p><w:pPr><w:pBdr><w:bottom w:val=\"single\" w:sz=\"6\" w:space=\"1\" w:color=\"auto\"/></w:pBdr></w:pPr></w:p>",
+                );
+            }
+            Event::FootnoteReference(label) => {
+                let id = self.footnote_id(&label);
+                self.push_raw_run(&format!(
+                    "<w:r><w:rPr><w:rStyle w:val=\"FootnoteReference\"/></w:rPr><w:footnoteReference w:id=\"{}\"/></w:r>",
+                    id
+                ));
+            }
+            Event::TaskListMarker(checked) => {
+                self.push_run(if checked { "☑ " } else { "☐ " }, false);
+            }
+            _ => {}
+        }
+    }
+
+    fn start_tag(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {
+                // 紧凑列表项已开启空段落时直接复用 / Reuse the paragraph opened by a list item
+                let reuse = self
+                    .para
+                    .as_ref()
+                    .map(|p| p.num.is_some() && p.runs.is_empty())
+                    .unwrap_or(false);
+                if !reuse {
+                    let style = self.context_style();
+                    self.start_para(style, None);
+                }
+            }
+            Tag::Heading { level, id, .. } => {
+                let level = markdown::heading_level_number(level);
+                self.start_para(Some(format!("Heading{}", level)), None);
+                if let Some(id) = id {
+                    self.bookmark_id += 1;
+                    let mark = format!(
+                        "<w:bookmarkStart w:id=\"{0}\" w:name=\"{1}\"/><w:bookmarkEnd w:id=\"{0}\"/>",
+                        self.bookmark_id,
+                        xml_escape(&bookmark_name(&id))
+                    );
+                    self.push_raw_run(&mark);
+                }
+            }
+            Tag::BlockQuote(_) => {
+                self.flush_para();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.flush_para();
+                self.code_block = Some(String::new());
+            }
+            Tag::List(start) => {
+                self.flush_para();
+                let ilvl = self.lists.len().min(8);
+                self.nums.push((start.is_some(), start.unwrap_or(1), ilvl));
+                self.lists.push(self.nums.len());
+            }
+            Tag::Item => {
+                let depth = self.lists.len().saturating_sub(1).min(8);
+                let num_id = self.lists.last().copied().unwrap_or(1);
+                let style = if self.quote_depth > 0 {
+                    "Quote"
+                } else {
+                    "ListParagraph"
+                };
+                self.start_para(Some(style.to_string()), Some((num_id, depth)));
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.flush_para();
+                let id = self.footnote_id(&label);
+                self.in_footnote = true;
+                self.footnotes.push((id, String::new()));
+                self.out.push(String::new());
+            }
+            Tag::Table(aligns) => self.start_table(aligns),
+            Tag::TableHead => {
+                self.in_table_head = true;
+                self.start_row();
+            }
+            Tag::TableRow => self.start_row(),
+            Tag::TableCell => self.start_cell(),
+            Tag::Emphasis => self.fmt.italic += 1,
+            Tag::Strong => self.fmt.bold += 1,
+            Tag::Strikethrough => self.fmt.strike += 1,
+            Tag::Link { dest_url, .. } => {
+                let open = if let Some(anchor) = dest_url.strip_prefix('#') {
+                    format!(
+                        "<w:hyperlink w:anchor=\"{}\">",
+                        xml_escape(&bookmark_name(anchor))
+                    )
+                } else {
+                    let rel_id = self.add_hyperlink(&dest_url);
+                    format!("<w:hyperlink r:id=\"{}\">", rel_id)
+                };
+                self.push_raw_run(&open);
+                self.fmt.link += 1;
+            }
+            Tag::Image { dest_url, .. } => {
+                self.image_alt = Some((dest_url.to_string(), String::new()));
+            }
+            Tag::MetadataBlock(_) | Tag::HtmlBlock => self.skip_text = true,
+            _ => {}
+        }
+    }
+
+    fn end_tag(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item => self.flush_para(),
+            TagEnd::BlockQuote(_) => {
+                self.flush_para();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::List(_) => {
+                self.flush_para();
+                self.lists.pop();
+            }
+            TagEnd::FootnoteDefinition => {
+                self.flush_para();
+                self.in_footnote = false;
+                let content = self.out.pop().unwrap_or_default();
+                if let Some(last) = self.footnotes.last_mut() {
+                    last.1 = content;
+                }
+            }
+            TagEnd::Table => self.end_table(),
+            TagEnd::TableHead => {
+                self.end_row();
+                self.in_table_head = false;
+            }
+            TagEnd::TableRow => self.end_row(),
+            TagEnd::TableCell => self.end_cell(),
+            TagEnd::Emphasis => self.fmt.italic = self.fmt.italic.saturating_sub(1),
+            TagEnd::Strong => self.fmt.bold = self.fmt.bold.saturating_sub(1),
+            TagEnd::Strikethrough => self.fmt.strike = self.fmt.strike.saturating_sub(1),
+            TagEnd::Link => {
+                self.fmt.link = self.fmt.link.saturating_sub(1);
+                self.push_raw_run("</w:hyperlink>");
+            }
+            TagEnd::MetadataBlock(_) | TagEnd::HtmlBlock => self.skip_text = false,
+            _ => {}
+        }
+    }
+
+    fn page_break(&mut self) {
+        self.flush_para();
+        self.buf()
+            .push_str("<w:p><w:r><w:br w:type=\"page\"/></w:r></w:p>");
+    }
+}
+
+/// Word 书签名：最长 40 字符，不能含空白 / Word bookmark names: max 40 chars, no whitespace
+fn bookmark_name(id: &str) -> String {
+    let name: String = id
+        .chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .take(39)
+        .collect();
+    format!("_{}", name)
+}
+
+const CONTENT_TYPES_HEAD: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Default Extension="png" ContentType="image/png"/>
+<Default Extension="jpeg" ContentType="image/jpeg"/>
+<Default Extension="gif" ContentType="image/gif"/>
+<Default Extension="bmp" ContentType="image/bmp"/>
+<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+<Override PartName="/word/numbering.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml"/>
+<Override PartName="/word/footnotes.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.footnotes+xml"/>
+<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+</Types>"#;
+
+const ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+</Relationships>"#;
+
+const DEFAULT_STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:eastAsia="Microsoft YaHei" w:cs="Calibri"/><w:sz w:val="22"/><w:szCs w:val="22"/></w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after="120" w:line="276" w:lineRule="auto"/></w:pPr></w:pPrDefault></w:docDefaults>
+<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/></w:style>
+<w:style w:type="paragraph" w:styleId="Title"><w:name w:val="Title"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:rPr><w:sz w:val="56"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="360" w:after="120"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="36"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="120"/><w:outlineLvl w:val="1"/></w:pPr><w:rPr><w:b/><w:sz w:val="30"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading3"><w:name w:val="heading 3"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="80"/><w:outlineLvl w:val="2"/></w:pPr><w:rPr><w:b/><w:sz w:val="26"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading4"><w:name w:val="heading 4"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:outlineLvl w:val="3"/></w:pPr><w:rPr><w:b/><w:i/><w:sz w:val="24"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading5"><w:name w:val="heading 5"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:outlineLvl w:val="4"/></w:pPr><w:rPr><w:b/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading6"><w:name w:val="heading 6"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:outlineLvl w:val="5"/></w:pPr><w:rPr><w:i/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:pBdr><w:left w:val="single" w:sz="18" w:space="8" w:color="D0D7DE"/></w:pBdr><w:ind w:left="360"/></w:pPr><w:rPr><w:color w:val="57606A"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="ListParagraph"><w:name w:val="List Paragraph"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:spacing w:after="40"/><w:ind w:left="720"/></w:pPr></w:style>
+<w:style w:type="paragraph" w:styleId="SourceCode"><w:name w:val="Source Code"/><w:basedOn w:val="Normal"/><w:pPr><w:shd w:val="clear" w:color="auto" w:fill="F6F8FA"/><w:spacing w:after="0" w:line="240" w:lineRule="auto"/></w:pPr><w:rPr><w:rFonts w:ascii="Consolas" w:hAnsi="Consolas" w:cs="Consolas"/><w:sz w:val="20"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="FootnoteText"><w:name w:val="footnote text"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="0"/></w:pPr><w:rPr><w:sz w:val="18"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="FootnoteReference"><w:name w:val="footnote reference"/><w:rPr><w:vertAlign w:val="superscript"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="CodeChar"><w:name w:val="Verbatim Char"/><w:rPr><w:rFonts w:ascii="Consolas" w:hAnsi="Consolas" w:cs="Consolas"/><w:shd w:val="clear" w:color="auto" w:fill="F6F8FA"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr><w:color w:val="0969DA"/><w:u w:val="single"/></w:rPr></w:style>
+<w:style w:type="table" w:styleId="TableGrid"><w:name w:val="Table Grid"/><w:tblPr><w:tblBorders><w:top w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:left w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:bottom w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:right w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideH w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideV w:val="single" w:sz="4" w:space="0" w:color="auto"/></w:tblBorders><w:tblCellMar><w:left w:w="108" w:type="dxa"/><w:right w:w="108" w:type="dxa"/></w:tblCellMar></w:tblPr></w:style>
+</w:styles>"#;
+
+fn numbering_xml(nums: &[(bool, u64, usize)]) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+    );
+    let bullets = ["•", "◦", "▪"];
+    for (abstract_id, ordered) in [(0, false), (1, true)] {
+        xml.push_str(&format!(
+            "<w:abstractNum w:abstractNumId=\"{}\"><w:multiLevelType w:val=\"hybridMultilevel\"/>",
+            abstract_id
+        ));
+        for ilvl in 0..9 {
+            let (fmt, text) = if ordered {
+                ("decimal", format!("%{}.", ilvl + 1))
+            } else {
+                ("bullet", bullets[ilvl % bullets.len()].to_string())
+            };
+            xml.push_str(&format!(
+                "<w:lvl w:ilvl=\"{}\"><w:start w:val=\"1\"/><w:numFmt w:val=\"{}\"/><w:lvlText w:val=\"{}\"/><w:lvlJc w:val=\"left\"/><w:pPr><w:ind w:left=\"{}\" w:hanging=\"360\"/></w:pPr></w:lvl>",
+                ilvl,
+                fmt,
+                text,
+                720 * (ilvl + 1)
+            ));
+        }
+        xml.push_str("</w:abstractNum>");
+    }
+    // 每个列表一个 w:num，保证有序列表重新编号 / One w:num per list so ordered lists restart
+    for (i, (ordered, start, ilvl)) in nums.iter().enumerate() {
+        xml.push_str(&format!(
+            "<w:num w:numId=\"{}\"><w:abstractNumId w:val=\"{}\"/>",
+            i + 1,
+            if *ordered { 1 } else { 0 }
+        ));
+        if *ordered {
+            xml.push_str(&format!(
+                "<w:lvlOverride w:ilvl=\"{}\"><w:startOverride w:val=\"{}\"/></w:lvlOverride>",
+                ilvl, start
+            ));
+        }
+        xml.push_str("</w:num>");
+    }
+    xml.push_str("</w:numbering>");
+    xml
+}
+
+fn footnotes_xml(footnotes: &[(usize, String)]) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:footnotes xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+<w:footnote w:type="separator" w:id="-1"><w:p><w:r><w:separator/></w:r></w:p></w:footnote>
+<w:footnote w:type="continuationSeparator" w:id="0"><w:p><w:r><w:continuationSeparator/></w:r></w:p></w:footnote>"#,
+    );
+    for (id, content) in footnotes {
+        let content = if content.is_empty() {
+            "<w:p/>"
+        } else {
+            content.as_str()
+        };
+        xml.push_str(&format!(
+            "<w:footnote w:id=\"{}\">{}</w:footnote>",
+            id, content
+        ));
+    }
+    xml.push_str("</w:footnotes>");
+    xml
+}
+
+fn document_rels(writer: &DocxWriter) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rIdStyles" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+<Relationship Id="rIdNumbering" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering" Target="numbering.xml"/>
+<Relationship Id="rIdFootnotes" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/footnotes" Target="footnotes.xml"/>"#,
+    );
+    for m in &writer.media {
+        xml.push_str(&format!(
+            "<Relationship Id=\"{}\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/image\" Target=\"media/{}\"/>",
+            m.rel_id, m.name
+        ));
+    }
+    for (rel_id, url) in &writer.hyperlinks {
+        xml.push_str(&format!(
+            "<Relationship Id=\"{}\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink\" Target=\"{}\" TargetMode=\"External\"/>",
+            rel_id,
+            xml_escape(url)
+        ));
+    }
+    xml.push_str("</Relationships>");
+    xml
+}
+
+fn core_xml(title: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><dc:title>{}</dc:title><dc:creator>MarkdownMonkey</dc:creator></cp:coreProperties>"#,
+        xml_escape(title)
+    )
+}
+
+/// 从参考文档中读取 styles.xml / Reads styles.xml from a reference document
+fn reference_styles(window: &str, path: &Path) -> Result<String, AppError> {
+    crate::ensure_in_workspace(window, path).map_err(AppError::WorkspaceError)?;
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+    let mut styles = String::new();
+    archive
+        .by_name("word/styles.xml")?
+        .read_to_string(&mut styles)?;
+    Ok(styles)
+}
+
+/// export
+/// 渲染并写出 DOCX，返回输出路径
+pub fn export(path: &Path, options: &DocxExportOptions) -> Result<PathBuf, AppError> {
+    let sources = collect_sources(path)?;
+    let mut writer = DocxWriter::new();
+    writer.window = options.window.clone();
+    let mut slugs = SlugSet::default();
+    let mut title = options.title.clone();
+
+    for (i, source) in sources.iter().enumerate() {
+        let text = read_source(source)?;
+        if title.is_none() {
+            title = Some(markdown::document_title(&text, source));
+        }
+        if i > 0 {
+            writer.page_break();
+        }
+        writer.base_dir = source.parent().unwrap_or(Path::new(".")).to_path_buf();
+        writer.source = i;
+        writer.write_document(&text, &mut slugs);
+    }
+
+    let styles = match &options.reference_docx {
+        Some(reference) => reference_styles(&options.window, Path::new(reference))?,
+        None => DEFAULT_STYLES.to_string(),
+    };
+    let body = writer.out.first().cloned().unwrap_or_default();
+    let document = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing"><w:body>{}<w:sectPr><w:pgSz w:w="11906" w:h="16838"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="708" w:footer="708" w:gutter="0"/></w:sectPr></w:body></w:document>"#,
+        body
+    );
+
+    let output = options
+        .output
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_output(path, "docx"));
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&output)?);
+    let opts = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let parts: Vec<(String, Vec<u8>)> = vec![
+        ("[Content_Types].xml".into(), CONTENT_TYPES_HEAD.into()),
+        ("_rels/.rels".into(), ROOT_RELS.into()),
+        (
+            "docProps/core.xml".into(),
+            core_xml(title.as_deref().unwrap_or("Document")).into_bytes(),
+        ),
+        ("word/document.xml".into(), document.into_bytes()),
+        ("word/styles.xml".into(), styles.into_bytes()),
+        (
+            "word/numbering.xml".into(),
+            numbering_xml(&writer.nums).into_bytes(),
+        ),
+        (
+            "word/footnotes.xml".into(),
+            footnotes_xml(&writer.footnotes).into_bytes(),
+        ),
+        (
+            "word/_rels/document.xml.rels".into(),
+            document_rels(&writer).into_bytes(),
+        ),
+    ];
+    for (name, bytes) in parts {
+        zip.start_file(name, opts)?;
+        zip.write_all(&bytes)?;
+    }
+    for m in &writer.media {
+        zip.start_file(format!("word/media/{}", m.name), opts)?;
+        zip.write_all(&m.bytes)?;
+    }
+    zip.finish()?;
+    Ok(output)
+}
@@ -0,0 +1,431 @@
+//! EPUB 导出 / EPUB export
+//!
+//! 将文件夹中按顺序排列的章节（SUMMARY.md 或 front matter `order`）渲染为 XHTML，
+//! 打包图片、样式与导航文档，生成符合 EPUB 3 结构要求的电子书。
+
+use super::{default_output, is_markdown, ordered_chapters, read_source};
+use crate::error::AppError;
+use crate::markdown::{self, escape_html as xml_escape, Heading, SlugSet};
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const BOOK_CSS: &str = r#"body{font-family:serif;line-height:1.6;margin:0 1em}
+h1,h2,h3,h4,h5,h6{font-family:sans-serif;line-height:1.3}
+pre{background:#f6f8fa;padding:.6em;white-space:pre-wrap;font-size:.85em}
+code{font-family:monospace}
+blockquote{margin:0 0 0 1em;padding-left:.8em;border-left:3px solid #ccc;color:#555}
+table{border-collapse:collapse}
+th,td{border:1px solid #ccc;padding:.2em .5em}
+img{max-width:100%}
+"#;
+
+/// EpubManifest
+/// EPUB 元数据与章节清单；`chapters` 为空时按文件夹中的顺序自动收集
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct EpubManifest {
+    #[serde(default)]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub identifier: Option<String>,
+    /// 封面图片（相对文件夹） / Cover image, relative to the folder
+    #[serde(default)]
+    pub cover: Option<String>,
+    /// 显式章节顺序（相对文件夹） / Explicit chapter order, relative to the folder
+    #[serde(default)]
+    pub chapters: Option<Vec<String>>,
+    /// 发起导出的窗口（后端填写），章节、封面与图片须在其工作区内
+    #[serde(skip)]
+    pub window: String,
+}
+
+struct Chapter {
+    file: String,
+    title: String,
+    headings: Vec<Heading>,
+    xhtml: String,
+}
+
+struct Resource {
+    id: String,
+    href: String,
+    media_type: String,
+    bytes: Vec<u8>,
+}
+
+/// 打包到 OEBPS/images 下的图片（按源路径去重）
+#[derive(Default)]
+struct ImageStore {
+    window: String,
+    by_path: HashMap<PathBuf, String>,
+    items: Vec<Resource>,
+}
+
+impl ImageStore {
+    fn add(&mut self, path: &Path) -> Option<String> {
+        let key = std::fs::canonicalize(path).ok()?;
+        if let Some(href) = self.by_path.get(&key) {
+            return Some(href.clone());
+        }
+        if let Err(e) = crate::ensure_in_workspace(&self.window, &key) {
+            log::warn!("Skip image {:?}: {}", key, e);
+            return None;
+        }
+        let bytes = std::fs::read(&key).ok()?;
+        let Some((ext, mime)) = image_type(&bytes) else {
+            log::warn!("Skip non-image file {:?}", key);
+            return None;
+        };
+        let n = self.items.len() + 1;
+        let href = format!("images/img{:03}.{}", n, ext);
+        self.items.push(Resource {
+            id: format!("img{:03}", n),
+            href: href.clone(),
+            media_type: mime,
+            bytes,
+        });
+        self.by_path.insert(key, href.clone());
+        Some(href)
+    }
+}
+
+/// 按内容判断图片类型，返回 (扩展名, MIME)；不是图片时为 `None`
+/// Image type from the content as (extension, MIME), `None` for anything else
+fn image_type(bytes: &[u8]) -> Option<(&'static str, String)> {
+    let ext = crate::assets::detect_extension(bytes)?;
+    let mime = mime_guess::from_ext(ext).first()?;
+    (mime.type_() == mime_guess::mime::IMAGE).then(|| (ext, mime.essence_str().to_string()))
+}
+
+fn chapter_file(index: usize) -> String {
+    format!("chapter-{:03}.xhtml", index + 1)
+}
+
+/// 渲染单个章节为 XHTML：改写图片与章节间链接，丢弃无法保证良构的原始 HTML
+fn render_chapter(
+    source: &Path,
+    text: &str,
+    slugs: &mut SlugSet,
+    files: &HashMap<PathBuf, String>,
+    images: &mut ImageStore,
+    language: &str,
+) -> (String, Vec<Heading>) {
+    let base_dir = source.parent().unwrap_or(Path::new("."));
+    let (events, headings) = markdown::parse_with_anchors(text, slugs);
+    // 无法打包的图片（远程、工作区外、缺失）只保留替代文字，电子书中不能引用包外资源
+    // Images that cannot be packaged keep only their alt text; a book may not reference outside resources
+    let mut packaged = Vec::new();
+    let events = events.into_iter().filter_map(|event| match event {
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => {
+            let href = markdown::resolve_local(base_dir, &dest_url).and_then(|p| images.add(&p));
+            packaged.push(href.is_some());
+            href.map(|href| {
+                Event::Start(Tag::Image {
+                    link_type,
+                    dest_url: CowStr::from(href),
+                    title,
+                    id,
+                })
+            })
+        }
+        Event::End(TagEnd::Image) => packaged
+            .pop()
+            .unwrap_or(true)
+            .then_some(Event::End(TagEnd::Image)),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => {
+            let fragment = dest_url.split_once('#').map(|(_, f)| f.to_string());
+            let target = markdown::resolve_local(base_dir, &dest_url)
+                .filter(|p| is_markdown(p))
+                .and_then(|p| std::fs::canonicalize(p).ok())
+                .and_then(|p| files.get(&p).cloned());
+            let dest_url = match target {
+                Some(file) => match fragment {
+                    Some(f) => CowStr::from(format!("{}#{}", file, f)),
+                    None => CowStr::from(file),
+                },
+                None => dest_url,
+            };
+            Some(Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }))
+        }
+        Event::Html(_) | Event::InlineHtml(_) => None,
+        other => Some(other),
+    });
+    let mut body = String::new();
+    pulldown_cmark::html::push_html(&mut body, events);
+
+    let title = markdown::document_title(text, source);
+    let xhtml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{lang}" lang="{lang}">
+<head><meta charset="utf-8"/><title>{title}</title><link rel="stylesheet" type="text/css" href="style.css"/></head>
+<body>
+{body}</body>
+</html>
+"#,
+        lang = xml_escape(language),
+        title = xml_escape(&title),
+        body = body
+    );
+    (xhtml, headings)
+}
+
+/// 导航文档：每章一项，章节内标题作为子目录
+fn nav_xhtml(chapters: &[Chapter], title: &str, language: &str) -> String {
+    let mut ol = String::from("<ol>");
+    for ch in chapters {
+        ol.push_str(&format!(
+            "<li><a href=\"{}\">{}</a>",
+            ch.file,
+            xml_escape(&ch.title)
+        ));
+        let subs: Vec<&Heading> = ch.headings.iter().filter(|h| h.level == 2).collect();
+        if !subs.is_empty() {
+            ol.push_str("<ol>");
+            for h in subs {
+                ol.push_str(&format!(
+                    "<li><a href=\"{}#{}\">{}</a></li>",
+                    ch.file,
+                    xml_escape(&h.id),
+                    xml_escape(&h.text)
+                ));
+            }
+            ol.push_str("</ol>");
+        }
+        ol.push_str("</li>");
+    }
+    ol.push_str("</ol>");
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{lang}" lang="{lang}">
+<head><meta charset="utf-8"/><title>{title}</title></head>
+<body>
+<nav epub:type="toc" id="toc"><h1>{title}</h1>{ol}</nav>
+</body>
+</html>
+"#,
+        lang = xml_escape(language),
+        title = xml_escape(title),
+        ol = ol
+    )
+}
+
+fn generate_identifier(title: &str) -> String {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    title.hash(&mut hasher);
+    std::time::SystemTime::now().hash(&mut hasher);
+    let a = hasher.finish();
+    a.rotate_left(17).hash(&mut hasher);
+    let b = hasher.finish();
+    format!(
+        "urn:uuid:{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
+        a >> 32,
+        (a >> 16) & 0xffff,
+        a & 0x0fff,
+        ((b >> 48) & 0x3fff) | 0x8000,
+        b & 0xffff_ffff_ffff
+    )
+}
+
+struct PackageMeta<'a> {
+    title: &'a str,
+    author: Option<&'a str>,
+    language: &'a str,
+    identifier: &'a str,
+    cover: Option<&'a Resource>,
+}
+
+fn content_opf(meta: &PackageMeta, chapters: &[Chapter], images: &[Resource]) -> String {
+    let modified = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+    let mut metadata = format!(
+        "<dc:identifier id=\"book-id\">{}</dc:identifier><dc:title>{}</dc:title><dc:language>{}</dc:language><meta property=\"dcterms:modified\">{}</meta>",
+        xml_escape(meta.identifier),
+        xml_escape(meta.title),
+        xml_escape(meta.language),
+        modified
+    );
+    if let Some(author) = meta.author {
+        metadata.push_str(&format!("<dc:creator>{}</dc:creator>", xml_escape(author)));
+    }
+
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/><item id=\"css\" href=\"style.css\" media-type=\"text/css\"/>",
+    );
+    let mut spine = String::new();
+    for (i, ch) in chapters.iter().enumerate() {
+        manifest.push_str(&format!(
+            "<item id=\"ch{:03}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>",
+            i + 1,
+            ch.file
+        ));
+        spine.push_str(&format!("<itemref idref=\"ch{:03}\"/>", i + 1));
+    }
+    for img in images.iter().chain(meta.cover) {
+        let props = if meta.cover.map(|c| c.id == img.id).unwrap_or(false) {
+            " properties=\"cover-image\""
+        } else {
+            ""
+        };
+        manifest.push_str(&format!(
+            "<item id=\"{}\" href=\"{}\" media-type=\"{}\"{}/>",
+            img.id, img.href, img.media_type, props
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" xml:lang="{lang}">
+<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">{metadata}</metadata>
+<manifest>{manifest}</manifest>
+<spine>{spine}</spine>
+</package>
+"#,
+        lang = xml_escape(meta.language),
+        metadata = metadata,
+        manifest = manifest,
+        spine = spine
+    )
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>
+"#;
+
+/// export
+/// 生成 EPUB 并返回输出路径
+pub fn export(dir: &Path, manifest: &EpubManifest) -> Result<PathBuf, AppError> {
+    let sources = match &manifest.chapters {
+        Some(list) if !list.is_empty() => {
+            let mut out = Vec::new();
+            for rel in list {
+                let p = dir.join(rel);
+                crate::ensure_in_workspace(&manifest.window, &p)
+                    .map_err(AppError::WorkspaceError)?;
+                out.push(p);
+            }
+            out
+        }
+        _ => ordered_chapters(dir, &manifest.window)?,
+    };
+
+    // 源文件 -> 章节文件名，用于改写章节间链接
+    let files: HashMap<PathBuf, String> = sources
+        .iter()
+        .enumerate()
+        .filter_map(|(i, p)| std::fs::canonicalize(p).ok().map(|c| (c, chapter_file(i))))
+        .collect();
+    let language = manifest
+        .language
+        .clone()
+        .unwrap_or_else(|| "en".to_string());
+
+    let mut slugs = SlugSet::default();
+    let mut images = ImageStore {
+        window: manifest.window.clone(),
+        ..Default::default()
+    };
+    let mut chapters = Vec::new();
+    for (i, source) in sources.iter().enumerate() {
+        let text = read_source(source)?;
+        let (xhtml, headings) =
+            render_chapter(source, &text, &mut slugs, &files, &mut images, &language);
+        chapters.push(Chapter {
+            file: chapter_file(i),
+            title: markdown::document_title(&text, source),
+            headings,
+            xhtml,
+        });
+    }
+
+    let title = manifest.title.clone().unwrap_or_else(|| {
+        dir.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Book".to_string())
+    });
+    let cover = match &manifest.cover {
+        Some(rel) => {
+            let p = dir.join(rel);
+            crate::ensure_in_workspace(&manifest.window, &p).map_err(AppError::WorkspaceError)?;
+            let bytes = std::fs::read(&p)?;
+            let (ext, media_type) = image_type(&bytes).ok_or_else(|| {
+                AppError::InvalidInput(format!("封面不是图片: {}", crate::git::display(&p)))
+            })?;
+            Some(Resource {
+                id: "cover-image".to_string(),
+                href: format!("images/cover.{}", ext),
+                media_type,
+                bytes,
+            })
+        }
+        None => None,
+    };
+    let identifier = manifest
+        .identifier
+        .clone()
+        .unwrap_or_else(|| generate_identifier(&title));
+    let meta = PackageMeta {
+        title: &title,
+        author: manifest.author.as_deref(),
+        language: &language,
+        identifier: &identifier,
+        cover: cover.as_ref(),
+    };
+
+    let output = manifest
+        .output
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_output(dir, "epub"));
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&output)?);
+    // mimetype 必须是第一个条目且不压缩 / mimetype must be the first entry, stored uncompressed
+    let stored =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    let deflated = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(CONTAINER_XML.as_bytes())?;
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    zip.write_all(content_opf(&meta, &chapters, &images.items).as_bytes())?;
+    zip.start_file("OEBPS/nav.xhtml", deflated)?;
+    zip.write_all(nav_xhtml(&chapters, &title, &language).as_bytes())?;
+    zip.start_file("OEBPS/style.css", deflated)?;
+    zip.write_all(BOOK_CSS.as_bytes())?;
+    for ch in &chapters {
+        zip.start_file(format!("OEBPS/{}", ch.file), deflated)?;
+        zip.write_all(ch.xhtml.as_bytes())?;
+    }
+    for img in images.items.iter().chain(cover.as_ref()) {
+        zip.start_file(format!("OEBPS/{}", img.href), stored)?;
+        zip.write_all(&img.bytes)?;
+    }
+    zip.finish()?;
+    Ok(output)
+}
@@ -0,0 +1,274 @@
+//! 独立 HTML 导出 / Standalone HTML export
+//!
+//! 不依赖预览 DOM：在后端渲染 Markdown，内联本地图片（data URI）、主题 CSS 与目录。
+
+use super::{collect_sources, default_output, read_source};
+use crate::error::AppError;
+use crate::markdown::{self, Heading, SlugSet};
+use base64::Engine;
+use pulldown_cmark::{CowStr, Event, Tag};
+use std::path::{Path, PathBuf};
+
+const LIGHT_CSS: &str = r#"
+body{font-family:system-ui,-apple-system,"Segoe UI",Roboto,Helvetica,Arial,"PingFang SC","Microsoft YaHei",sans-serif;max-width:840px;margin:24px auto;padding:0 16px;line-height:1.7;color:#24292f;background:#fff}
+a{color:#0969da}
+h1,h2,h3,h4,h5,h6{margin:1.2em 0 .6em;line-height:1.3}
+h1,h2{border-bottom:1px solid #d0d7de;padding-bottom:.3em}
+code{font-family:ui-monospace,SFMono-Regular,Menlo,Consolas,monospace;background:#f6f8fa;padding:.1em .3em;border-radius:4px}
+pre{background:#f6f8fa;padding:12px;border-radius:6px;overflow:auto}
+pre code{background:none;padding:0}
+blockquote{margin:0;padding:0 1em;color:#57606a;border-left:.25em solid #d0d7de}
+table{border-collapse:collapse;margin:1em 0}
+th,td{border:1px solid #d0d7de;padding:6px 12px}
+th{background:#f6f8fa}
+img{max-width:100%}
+nav.toc{border:1px solid #d0d7de;border-radius:6px;padding:8px 16px;margin-bottom:2em}
+nav.toc ul{padding-left:1.2em;margin:.2em 0}
+section.chapter+section.chapter{margin-top:3em;border-top:1px dashed #d0d7de}
+"#;
+
+const DARK_CSS: &str = r#"
+body{font-family:system-ui,-apple-system,"Segoe UI",Roboto,Helvetica,Arial,"PingFang SC","Microsoft YaHei",sans-serif;max-width:840px;margin:24px auto;padding:0 16px;line-height:1.7;color:#e6edf3;background:#0d1117}
+a{color:#4493f8}
+h1,h2,h3,h4,h5,h6{margin:1.2em 0 .6em;line-height:1.3}
+h1,h2{border-bottom:1px solid #30363d;padding-bottom:.3em}
+code{font-family:ui-monospace,SFMono-Regular,Menlo,Consolas,monospace;background:#161b22;padding:.1em .3em;border-radius:4px}
+pre{background:#0b0b0b;color:#f3f3f3;padding:12px;border-radius:6px;overflow:auto}
+pre code{background:none;padding:0}
+blockquote{margin:0;padding:0 1em;color:#8b949e;border-left:.25em solid #30363d}
+table{border-collapse:collapse;margin:1em 0}
+th,td{border:1px solid #30363d;padding:6px 12px}
+th{background:#161b22}
+img{max-width:100%}
+nav.toc{border:1px solid #30363d;border-radius:6px;padding:8px 16px;margin-bottom:2em}
+nav.toc ul{padding-left:1.2em;margin:.2em 0}
+section.chapter+section.chapter{margin-top:3em;border-top:1px dashed #30363d}
+"#;
+
+/// HtmlExportOptions
+/// HTML 导出选项
+#[derive(Debug, serde::Deserialize)]
+pub struct HtmlExportOptions {
+    /// 输出文件路径，缺省时写在源文件旁 / Output path, defaults to next to the source
+    #[serde(default)]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 内置主题：light / dark，缺省取工作区配置，否则为 light
+    #[serde(default)]
+    pub theme: Option<String>,
+    /// 追加的自定义 CSS 文件（需位于工作区内）
+    #[serde(default)]
+    pub css_path: Option<String>,
+    #[serde(default = "default_true")]
+    pub toc: bool,
+    #[serde(default = "default_toc_depth")]
+    pub toc_depth: u8,
+    #[serde(default = "default_true")]
+    pub embed_images: bool,
+    /// 发起导出的窗口（后端填写），内嵌的文件须在其工作区内
+    #[serde(skip)]
+    pub window: String,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_toc_depth() -> u8 {
+    3
+}
+
+impl Default for HtmlExportOptions {
+    fn default() -> Self {
+        Self {
+            output: None,
+            title: None,
+            theme: None,
+            css_path: None,
+            toc: true,
+            toc_depth: default_toc_depth(),
+            embed_images: true,
+            window: String::new(),
+        }
+    }
+}
+
+/// render_fragment
+/// 渲染单个文档为 HTML 片段；`rewrite` 可替换图片地址（Markdown 图片与内联 `<img>`）
+pub fn render_fragment<F>(src: &str, slugs: &mut SlugSet, mut rewrite: F) -> (String, Vec<Heading>)
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let (events, headings) = markdown::parse_with_anchors(src, slugs);
+    let mut mapped = Vec::with_capacity(events.len());
+    for event in events {
+        let event = match event {
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Image {
+                link_type,
+                dest_url: rewrite(&dest_url).map(CowStr::from).unwrap_or(dest_url),
+                title,
+                id,
+            }),
+            Event::Html(h) => Event::Html(rewrite_img_tags(&h, &mut rewrite).into()),
+            Event::InlineHtml(h) => Event::InlineHtml(rewrite_img_tags(&h, &mut rewrite).into()),
+            other => other,
+        };
+        mapped.push(event);
+    }
+    let mut out = String::new();
+    pulldown_cmark::html::push_html(&mut out, mapped.into_iter());
+    (out, headings)
+}
+
+/// 替换内联 HTML 中 `<img src>` 的地址 / Rewrites `<img src>` inside raw HTML
+pub fn rewrite_img_tags<F>(html: &str, rewrite: &mut F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    markdown::HTML_IMG_SRC
+        .replace_all(html, |caps: &regex::Captures| {
+            let url = &caps[3];
+            let new_url = rewrite(url).unwrap_or_else(|| url.to_string());
+            format!("{}{}{}{}", &caps[1], &caps[2], new_url, &caps[4])
+        })
+        .to_string()
+}
+
+/// toc_html
+/// 由标题生成嵌套目录 / Builds a nested table of contents
+pub fn toc_html(headings: &[Heading], max_level: u8) -> String {
+    let mut out = String::from("<nav class=\"toc\">");
+    let mut stack: Vec<u8> = Vec::new();
+    for h in headings.iter().filter(|h| h.level <= max_level) {
+        while let Some(&top) = stack.last() {
+            if top > h.level {
+                out.push_str("</li></ul>");
+                stack.pop();
+            } else {
+                break;
+            }
+        }
+        match stack.last() {
+            Some(&top) if top == h.level => out.push_str("</li>"),
+            _ => {
+                out.push_str("<ul>");
+                stack.push(h.level);
+            }
+        }
+        out.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a>",
+            markdown::escape_html(&h.id),
+            markdown::escape_html(&h.text)
+        ));
+    }
+    for _ in stack {
+        out.push_str("</li></ul>");
+    }
+    out.push_str("</nav>");
+    out
+}
+
+/// 将本地图片读取为 data URI；不在工作区内或非图片时保持原样
+fn image_data_uri(window: &str, base_dir: &Path, url: &str) -> Option<String> {
+    let p = markdown::resolve_local(base_dir, url)?;
+    if let Err(e) = crate::ensure_in_workspace(window, &p) {
+        log::warn!("Skip embedding image {:?}: {}", p, e);
+        return None;
+    }
+    let mime = mime_guess::from_path(&p).first_or_octet_stream();
+    if mime.type_() != mime_guess::mime::IMAGE {
+        return None;
+    }
+    let bytes = std::fs::read(&p).ok()?;
+    Some(format!(
+        "data:{};base64,{}",
+        mime.essence_str(),
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+/// render_document
+/// 渲染一个或多个文档为完整的 HTML 页面
+pub fn render_document(
+    sources: &[PathBuf],
+    options: &HtmlExportOptions,
+) -> Result<String, AppError> {
+    let mut slugs = SlugSet::default();
+    let mut body = String::new();
+    let mut headings = Vec::new();
+    let mut first_title = None;
+
+    for source in sources {
+        let text = read_source(source)?;
+        if first_title.is_none() {
+            first_title = Some(markdown::document_title(&text, source));
+        }
+        let base_dir = source.parent().unwrap_or(Path::new("."));
+        let (html, hs) = render_fragment(&text, &mut slugs, |url| {
+            if options.embed_images {
+                image_data_uri(&options.window, base_dir, url)
+            } else {
+                None
+            }
+        });
+        if sources.len() > 1 {
+            body.push_str("<section class=\"chapter\">\n");
+            body.push_str(&html);
+            body.push_str("</section>\n");
+        } else {
+            body.push_str(&html);
+        }
+        headings.extend(hs);
+    }
+
+    let mut css = match options.theme.as_deref().unwrap_or("light") {
+        "dark" => DARK_CSS.to_string(),
+        _ => LIGHT_CSS.to_string(),
+    };
+    if let Some(css_path) = &options.css_path {
+        let p = Path::new(css_path);
+        crate::ensure_in_workspace(&options.window, p).map_err(AppError::WorkspaceError)?;
+        css.push_str(&std::fs::read_to_string(p)?);
+    }
+
+    let title = options
+        .title
+        .clone()
+        .or(first_title)
+        .unwrap_or_else(|| "Document".to_string());
+    let toc = if options.toc && !headings.is_empty() {
+        toc_html(&headings, options.toc_depth)
+    } else {
+        String::new()
+    };
+
+    Ok(format!(
+        "<!doctype html>\n<html><head><meta charset=\"utf-8\"/>\
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\"/>\
+<title>{}</title><style>{}</style></head>\n<body class=\"markdown_body\">\n{}\n{}</body></html>\n",
+        markdown::escape_html(&title),
+        css,
+        toc,
+        body
+    ))
+}
+
+/// export
+/// 渲染并写出 HTML，返回输出路径
+pub fn export(path: &Path, options: &HtmlExportOptions) -> Result<PathBuf, AppError> {
+    let sources = collect_sources(path)?;
+    let html = render_document(&sources, options)?;
+    let output = options
+        .output
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_output(path, "html"));
+    std::fs::write(&output, html)?;
+    Ok(output)
+}
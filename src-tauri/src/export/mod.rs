@@ -0,0 +1,146 @@
+//! 文档导出 / Document export
+//!
+//! 各格式共用的源文件收集逻辑；具体格式见子模块。
+//! Source collection shared by all formats; see submodules for each format.
+
+pub mod docx;
+pub mod epub;
+pub mod html;
+pub mod pdf;
+
+use crate::error::AppError;
+use crate::markdown;
+use pulldown_cmark::{Event, Parser, Tag};
+use std::path::{Path, PathBuf};
+
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .map(|e| {
+            let e = e.to_string_lossy().to_lowercase();
+            e == "md" || e == "markdown"
+        })
+        .unwrap_or(false)
+}
+
+/// collect_sources
+/// 单个文档直接返回；文件夹则递归收集其中的 Markdown 文件并按路径排序
+pub fn collect_sources(path: &Path) -> Result<Vec<PathBuf>, AppError> {
+    if path.is_dir() {
+        let mut out = Vec::new();
+        walk(path, &mut out)?;
+        out.sort();
+        if out.is_empty() {
+            return Err(AppError::InvalidInput(
+                "文件夹中没有 Markdown 文件".to_string(),
+            ));
+        }
+        Ok(out)
+    } else if is_markdown(path) {
+        Ok(vec![path.to_path_buf()])
+    } else {
+        Err(AppError::InvalidInput(
+            "仅支持导出 Markdown 文件".to_string(),
+        ))
+    }
+}
+
+/// 递归收集文件夹中的 Markdown 文件，跳过隐藏项 / Markdown files under `dir`, skipping hidden entries
+fn walk(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), AppError> {
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let p = entry.path();
+        let hidden = p
+            .file_name()
+            .map(|n| n.to_string_lossy().starts_with('.'))
+            .unwrap_or(false);
+        if hidden {
+            continue;
+        }
+        if p.is_dir() {
+            walk(&p, out)?;
+        } else if is_markdown(&p) {
+            out.push(p);
+        }
+    }
+    Ok(())
+}
+
+/// ordered_chapters
+/// 电子书的章节顺序：优先使用 SUMMARY.md 中的链接顺序（SUMMARY.md 本身不作为章节），
+/// 否则按 front matter 的 `order` 字段排序，未设置的按路径排在后面。只用于 EPUB，其他格式按路径排序
+pub fn ordered_chapters(dir: &Path, window: &str) -> Result<Vec<PathBuf>, AppError> {
+    let summary = dir.join("SUMMARY.md");
+    if summary.is_file() {
+        let chapters = summary_links(&read_source(&summary)?, dir, window);
+        if !chapters.is_empty() {
+            return Ok(chapters);
+        }
+    }
+
+    let mut files = Vec::new();
+    walk(dir, &mut files)?;
+    let mut keyed: Vec<(Option<i64>, PathBuf)> = files
+        .into_iter()
+        .filter(|p| *p != summary)
+        .map(|p| {
+            let order = std::fs::read_to_string(&p).ok().and_then(|text| {
+                markdown::split_front_matter(&text)
+                    .0
+                    .and_then(|front| markdown::front_matter_value(front, "order"))
+                    .and_then(|v| v.parse::<i64>().ok())
+            });
+            (order, p)
+        })
+        .collect();
+    keyed.sort_by(|a, b| match (a.0, b.0) {
+        (Some(x), Some(y)) => x.cmp(&y).then_with(|| a.1.cmp(&b.1)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.1.cmp(&b.1),
+    });
+    if keyed.is_empty() {
+        return Err(AppError::InvalidInput(
+            "文件夹中没有 Markdown 文件".to_string(),
+        ));
+    }
+    Ok(keyed.into_iter().map(|(_, p)| p).collect())
+}
+
+/// 按出现顺序提取 SUMMARY.md 中指向本地 Markdown 文件的链接（去重），工作区之外的链接被忽略
+fn summary_links(text: &str, dir: &Path, window: &str) -> Vec<PathBuf> {
+    let mut out: Vec<PathBuf> = Vec::new();
+    for event in Parser::new_ext(text, markdown::parser_options()) {
+        if let Event::Start(Tag::Link { dest_url, .. }) = event {
+            if let Some(p) = markdown::resolve_local(dir, &dest_url) {
+                if !is_markdown(&p) || !p.is_file() || out.contains(&p) {
+                    continue;
+                }
+                match crate::ensure_in_workspace(window, &p) {
+                    Ok(()) => out.push(p),
+                    Err(e) => log::warn!("Skip SUMMARY.md link {:?}: {}", p, e),
+                }
+            }
+        }
+    }
+    out
+}
+
+/// 读取源文档（受 MAX_FILE_SIZE 限制） / Reads a source document (bounded by MAX_FILE_SIZE)
+pub fn read_source(path: &Path) -> Result<String, AppError> {
+    crate::check_file_size(path).map_err(AppError::InvalidInput)?;
+    Ok(std::fs::read_to_string(path)?)
+}
+
+/// 未指定输出路径时，在源文件（或文件夹）旁生成同名文件
+/// Default output path next to the source file (or folder)
+pub fn default_output(path: &Path, ext: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .or_else(|| path.file_name())
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "export".to_string());
+    if path.is_dir() {
+        path.join(format!("{}.{}", stem, ext))
+    } else {
+        path.with_file_name(format!("{}.{}", stem, ext))
+    }
+}
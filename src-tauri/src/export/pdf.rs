@@ -0,0 +1,1389 @@
+//! PDF 导出 / Native PDF export
+//!
+//! 不经过 webview 栅格化：在后端排版 Markdown，输出可选中文本的 PDF。
+//! 嵌入（子集化的）TrueType/OpenType 字体以支持中日韩文字，带页眉页脚、页码与标题书签。
+
+use super::{collect_sources, default_output, read_source};
+use crate::error::AppError;
+use crate::markdown::{self, SlugSet};
+use pdf_writer::types::{CidFontType, FontFlags, SystemInfo, UnicodeCmap};
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use pulldown_cmark::{Event, Tag, TagEnd};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// 常见系统字体（优先覆盖中日韩字符的 TrueType 字体）
+/// Common system fonts, CJK-capable TrueType fonts first
+const FONT_CANDIDATES: &[&str] = &[
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\simsun.ttc",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Light.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+];
+
+const BOLD_FONT_CANDIDATES: &[&str] = &[
+    "C:\\Windows\\Fonts\\msyhbd.ttc",
+    "C:\\Windows\\Fonts\\arialbd.ttf",
+    "/System/Library/Fonts/Supplemental/Arial Bold.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans-Bold.ttf",
+];
+
+const MONO_FONT_CANDIDATES: &[&str] = &[
+    "C:\\Windows\\Fonts\\consola.ttf",
+    "C:\\Windows\\Fonts\\cour.ttf",
+    "/System/Library/Fonts/Menlo.ttc",
+    "/System/Library/Fonts/Supplemental/Courier New.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+    "/usr/share/fonts/TTF/DejaVuSansMono.ttf",
+];
+
+const MARGIN: f32 = 56.7; // 20mm
+const HEADING_SIZES: [f32; 6] = [22.0, 18.0, 15.0, 13.0, 12.0, 11.0];
+const LINK_COLOR: (f32, f32, f32) = (0.04, 0.41, 0.85);
+
+/// PdfExportOptions
+/// PDF 导出选项
+#[derive(Debug, serde::Deserialize)]
+pub struct PdfExportOptions {
+    #[serde(default)]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 正文字体（TTF/OTF/TTC），缺省时自动查找系统字体
+    #[serde(default)]
+    pub font_path: Option<String>,
+    #[serde(default)]
+    pub bold_font_path: Option<String>,
+    #[serde(default)]
+    pub mono_font_path: Option<String>,
+    #[serde(default = "default_font_size")]
+    pub font_size: f32,
+    /// a4 / letter
+    #[serde(default = "default_page_size")]
+    pub page_size: String,
+    /// 页眉文本，缺省为文档标题；空字符串表示不显示
+    #[serde(default)]
+    pub header: Option<String>,
+    /// 页脚模板，支持 `{page}` 与 `{pages}`
+    #[serde(default = "default_footer")]
+    pub footer: String,
+    /// 发起导出的窗口（后端填写），图片须在其工作区内
+    #[serde(skip)]
+    pub window: String,
+}
+
+fn default_font_size() -> f32 {
+    11.0
+}
+
+fn default_page_size() -> String {
+    "a4".to_string()
+}
+
+fn default_footer() -> String {
+    "{page} / {pages}".to_string()
+}
+
+impl Default for PdfExportOptions {
+    fn default() -> Self {
+        Self {
+            output: None,
+            title: None,
+            font_path: None,
+            bold_font_path: None,
+            mono_font_path: None,
+            font_size: default_font_size(),
+            page_size: default_page_size(),
+            header: None,
+            footer: default_footer(),
+            window: String::new(),
+        }
+    }
+}
+
+/// 已加载的字体及其使用过的字形（用于子集化与 ToUnicode）
+struct FontFace {
+    data: Vec<u8>,
+    index: u32,
+    units_per_em: f32,
+    /// 字符 -> (字形 id, 千分之一 em 宽度)
+    glyphs: HashMap<char, Option<(u16, f32)>>,
+    used: BTreeMap<u16, (char, f32)>,
+}
+
+impl FontFace {
+    fn load(path: &Path) -> Result<Self, AppError> {
+        let data = std::fs::read(path)?;
+        let face = ttf_parser::Face::parse(&data, 0)
+            .map_err(|_| AppError::InvalidInput(format!("无法解析字体: {}", path.display())))?;
+        let units_per_em = face.units_per_em() as f32;
+        Ok(Self {
+            data,
+            index: 0,
+            units_per_em,
+            glyphs: HashMap::new(),
+            used: BTreeMap::new(),
+        })
+    }
+
+    fn face(&self) -> Option<ttf_parser::Face<'_>> {
+        ttf_parser::Face::parse(&self.data, self.index).ok()
+    }
+
+    fn lookup(&mut self, c: char) -> Option<(u16, f32)> {
+        if let Some(hit) = self.glyphs.get(&c) {
+            return *hit;
+        }
+        let result = self.face().and_then(|face| {
+            let gid = face.glyph_index(c)?;
+            let advance = face.glyph_hor_advance(gid).unwrap_or(0) as f32;
+            Some((gid.0, advance * 1000.0 / self.units_per_em))
+        });
+        self.glyphs.insert(c, result);
+        result
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+struct Style {
+    bold: bool,
+    italic: bool,
+    code: bool,
+    link: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FontKind {
+    Regular = 0,
+    Bold = 1,
+    Mono = 2,
+}
+
+/// 正文 / 粗体 / 等宽三种字体；缺字时回退到正文字体
+struct FontSet {
+    faces: Vec<Option<FontFace>>,
+}
+
+impl FontSet {
+    fn load(options: &PdfExportOptions) -> Result<Self, AppError> {
+        let find = |explicit: &Option<String>, candidates: &[&str]| -> Option<PathBuf> {
+            match explicit {
+                Some(p) => Some(PathBuf::from(p)),
+                None => candidates.iter().map(PathBuf::from).find(|p| p.is_file()),
+            }
+        };
+        let regular = find(&options.font_path, FONT_CANDIDATES).ok_or_else(|| {
+            AppError::InvalidInput("未找到可用字体，请在导出选项中指定 font_path".to_string())
+        })?;
+        let regular = FontFace::load(&regular)?;
+        let bold = find(&options.bold_font_path, BOLD_FONT_CANDIDATES)
+            .and_then(|p| FontFace::load(&p).ok());
+        let mono = find(&options.mono_font_path, MONO_FONT_CANDIDATES)
+            .and_then(|p| FontFace::load(&p).ok());
+        Ok(Self {
+            faces: vec![Some(regular), bold, mono],
+        })
+    }
+
+    fn kind_for(style: Style) -> FontKind {
+        if style.code {
+            FontKind::Mono
+        } else if style.bold {
+            FontKind::Bold
+        } else {
+            FontKind::Regular
+        }
+    }
+
+    /// 为字符选择字体，返回 (字体序号, 字形 id, 宽度)
+    fn pick(&mut self, kind: FontKind, c: char) -> (usize, u16, f32) {
+        let order = [kind as usize, FontKind::Regular as usize];
+        for idx in order {
+            if let Some(face) = self.faces[idx].as_mut() {
+                if let Some((gid, w)) = face.lookup(c) {
+                    return (idx, gid, w);
+                }
+            }
+        }
+        (FontKind::Regular as usize, 0, 500.0)
+    }
+
+    fn measure(&mut self, style: Style, text: &str, size: f32) -> f32 {
+        let kind = Self::kind_for(style);
+        text.chars().map(|c| self.pick(kind, c).2).sum::<f32>() * size / 1000.0
+    }
+}
+
+enum Op {
+    Text {
+        x: f32,
+        y: f32,
+        size: f32,
+        font: usize,
+        glyphs: Vec<u16>,
+        color: (f32, f32, f32),
+    },
+    Line {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        gray: f32,
+    },
+    Rect {
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        gray: f32,
+    },
+    Image {
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        index: usize,
+    },
+}
+
+struct OutlineEntry {
+    level: u8,
+    title: String,
+    page: usize,
+    y: f32,
+}
+
+struct RasterImage {
+    width: u32,
+    height: u32,
+    rgb: Vec<u8>,
+}
+
+/// 排版结果与状态 / Layout state
+struct Layout {
+    fonts: FontSet,
+    width: f32,
+    height: f32,
+    base_size: f32,
+    pages: Vec<Vec<Op>>,
+    /// 当前写入的页序号 / Page that receives new ops
+    current: usize,
+    y: f32,
+    outline: Vec<OutlineEntry>,
+    images: Vec<RasterImage>,
+}
+
+/// 一行中的片段 / A styled run within a line
+type Segment = (Style, String);
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x2E80..=0x2FFF | 0x3000..=0x303F | 0x3040..=0x30FF | 0x3100..=0x31FF |
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF |
+        0xFF00..=0xFFEF | 0x20000..=0x2FFFF)
+}
+
+/// 拆分为可断行单元：空白、单个中日韩字符、连续的其他字符
+fn tokenize(segments: &[Segment]) -> Vec<(Style, String, bool)> {
+    let mut tokens = Vec::new();
+    for (style, text) in segments {
+        let mut word = String::new();
+        for c in text.chars() {
+            if c.is_whitespace() || is_cjk(c) {
+                if !word.is_empty() {
+                    tokens.push((*style, std::mem::take(&mut word), false));
+                }
+                if c.is_whitespace() {
+                    tokens.push((*style, " ".to_string(), true));
+                } else {
+                    tokens.push((*style, c.to_string(), false));
+                }
+            } else {
+                word.push(c);
+            }
+        }
+        if !word.is_empty() {
+            tokens.push((*style, word, false));
+        }
+    }
+    tokens
+}
+
+impl Layout {
+    fn new(fonts: FontSet, options: &PdfExportOptions) -> Self {
+        let (width, height) = match options.page_size.to_lowercase().as_str() {
+            "letter" => (612.0, 792.0),
+            _ => (595.28, 841.89),
+        };
+        Self {
+            fonts,
+            width,
+            height,
+            base_size: options.font_size.clamp(6.0, 24.0),
+            pages: vec![Vec::new()],
+            current: 0,
+            y: height - MARGIN,
+            outline: Vec::new(),
+            images: Vec::new(),
+        }
+    }
+
+    fn content_width(&self) -> f32 {
+        self.width - 2.0 * MARGIN
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Vec::new());
+        self.current = self.pages.len() - 1;
+        self.y = self.height - MARGIN;
+    }
+
+    fn ensure_space(&mut self, h: f32) {
+        let at_top = self.y >= self.height - MARGIN - 0.01;
+        if self.y - h < MARGIN && !at_top {
+            self.new_page();
+        }
+    }
+
+    fn push(&mut self, op: Op) {
+        self.pages[self.current].push(op);
+    }
+
+    fn gap(&mut self, h: f32) {
+        self.y -= h;
+    }
+
+    /// 在 (x, baseline) 处绘制文本，按字形覆盖情况拆分字体
+    fn draw_text(
+        &mut self,
+        x: f32,
+        baseline: f32,
+        size: f32,
+        style: Style,
+        text: &str,
+        gray: bool,
+    ) {
+        let kind = FontSet::kind_for(style);
+        let color = if style.link {
+            LINK_COLOR
+        } else if gray {
+            (0.4, 0.4, 0.4)
+        } else {
+            (0.0, 0.0, 0.0)
+        };
+        let mut cursor = x;
+        let mut run: Option<(usize, f32, Vec<u16>)> = None;
+        let mut run_width = 0.0;
+        for c in text.chars() {
+            let (font, gid, w) = self.fonts.pick(kind, c);
+            if let Some(face) = self.fonts.faces[font].as_mut() {
+                face.used.insert(gid, (c, w));
+            }
+            match run.as_mut() {
+                Some((f, _, glyphs)) if *f == font => glyphs.push(gid),
+                _ => {
+                    if let Some((f, start, glyphs)) = run.take() {
+                        self.push(Op::Text {
+                            x: start,
+                            y: baseline,
+                            size,
+                            font: f,
+                            glyphs,
+                            color,
+                        });
+                    }
+                    cursor += run_width;
+                    run_width = 0.0;
+                    run = Some((font, cursor, vec![gid]));
+                }
+            }
+            run_width += w * size / 1000.0;
+        }
+        if let Some((f, start, glyphs)) = run {
+            self.push(Op::Text {
+                x: start,
+                y: baseline,
+                size,
+                font: f,
+                glyphs,
+                color,
+            });
+        }
+    }
+
+    /// 断行并输出一个段落；`first_prefix` 为列表符号等悬挂前缀
+    /// 按宽度折行，与段落排版一致 / Wraps segments to `max_width`, as paragraphs are laid out
+    fn wrap(&mut self, segments: &[Segment], max_width: f32, size: f32) -> Vec<Vec<Segment>> {
+        let mut lines: Vec<Vec<Segment>> = Vec::new();
+        let mut line: Vec<Segment> = Vec::new();
+        let mut width = 0.0;
+
+        fn append(line: &mut Vec<Segment>, style: Style, text: &str) {
+            match line.last_mut() {
+                Some((s, t)) if *s == style => t.push_str(text),
+                _ => line.push((style, text.to_string())),
+            }
+        }
+
+        for (style, text, is_space) in tokenize(segments) {
+            if is_space && line.is_empty() {
+                continue;
+            }
+            let w = self.fonts.measure(style, &text, size);
+            if width + w > max_width && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                width = 0.0;
+                if is_space {
+                    continue;
+                }
+            }
+            if w > max_width {
+                // 超长单词按字符断开 / Break overlong words by character
+                for c in text.chars() {
+                    let cw = self.fonts.measure(style, &c.to_string(), size);
+                    if width + cw > max_width && !line.is_empty() {
+                        lines.push(std::mem::take(&mut line));
+                        width = 0.0;
+                    }
+                    append(&mut line, style, &c.to_string());
+                    width += cw;
+                }
+                continue;
+            }
+            append(&mut line, style, &text);
+            width += w;
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// 在 (x0, baseline) 处绘制一行 / Draws one wrapped line
+    fn draw_line(&mut self, x0: f32, baseline: f32, size: f32, line: &[Segment], gray: bool) {
+        let mut x = x0;
+        for (j, (style, text)) in line.iter().enumerate() {
+            let text = if j + 1 == line.len() {
+                text.trim_end_matches(' ')
+            } else {
+                text.as_str()
+            };
+            self.draw_text(x, baseline, size, *style, text, gray);
+            x += self.fonts.measure(*style, text, size);
+        }
+    }
+
+    fn paragraph(
+        &mut self,
+        segments: &[Segment],
+        indent: f32,
+        size: f32,
+        first_prefix: Option<&str>,
+        gray: bool,
+    ) {
+        let line_height = size * 1.45;
+        let mut lines = self.wrap(segments, self.content_width() - indent, size);
+        if lines.is_empty() && first_prefix.is_some() {
+            lines.push(Vec::new());
+        }
+
+        for (i, line) in lines.iter().enumerate() {
+            self.ensure_space(line_height);
+            let baseline = self.y - size * 1.1;
+            let x0 = MARGIN + indent;
+            if i == 0 {
+                if let Some(prefix) = first_prefix {
+                    let pw = self.fonts.measure(Style::default(), prefix, size);
+                    self.draw_text(
+                        x0 - pw - 4.0,
+                        baseline,
+                        size,
+                        Style::default(),
+                        prefix,
+                        gray,
+                    );
+                }
+            }
+            self.draw_line(x0, baseline, size, line, gray);
+            self.y -= line_height;
+        }
+    }
+
+    fn heading(&mut self, level: u8, segments: &[Segment]) {
+        let size = HEADING_SIZES[(level as usize).clamp(1, 6) - 1] * self.base_size / 11.0;
+        self.gap(size * 0.6);
+        self.ensure_space(size * 3.0);
+        let title: String = segments.iter().map(|(_, t)| t.as_str()).collect();
+        self.outline.push(OutlineEntry {
+            level,
+            title: title.trim().to_string(),
+            page: self.current,
+            y: self.y,
+        });
+        let bold: Vec<Segment> = segments
+            .iter()
+            .map(|(s, t)| (Style { bold: true, ..*s }, t.clone()))
+            .collect();
+        self.paragraph(&bold, 0.0, size, None, false);
+        if level <= 2 {
+            let y = self.y + size * 0.2;
+            self.push(Op::Line {
+                x1: MARGIN,
+                y1: y,
+                x2: self.width - MARGIN,
+                y2: y,
+                gray: 0.8,
+            });
+        }
+        self.gap(size * 0.3);
+    }
+
+    fn code_block(&mut self, code: &str, indent: f32) {
+        let size = self.base_size * 0.85;
+        let line_height = size * 1.35;
+        let style = Style {
+            code: true,
+            ..Style::default()
+        };
+        let max_width = self.content_width() - indent - 12.0;
+        self.gap(size * 0.3);
+        for raw in code.trim_end_matches('\n').split('\n') {
+            let raw = raw.trim_end_matches('\r').replace('\t', "    ");
+            // 按字符折行 / Wrap by character
+            let mut pieces = Vec::new();
+            let mut current = String::new();
+            let mut width = 0.0;
+            for c in raw.chars() {
+                let cw = self.fonts.measure(style, &c.to_string(), size);
+                if width + cw > max_width && !current.is_empty() {
+                    pieces.push(std::mem::take(&mut current));
+                    width = 0.0;
+                }
+                current.push(c);
+                width += cw;
+            }
+            pieces.push(current);
+            for piece in pieces {
+                self.ensure_space(line_height);
+                self.push(Op::Rect {
+                    x: MARGIN + indent,
+                    y: self.y - line_height,
+                    w: self.content_width() - indent,
+                    h: line_height,
+                    gray: 0.95,
+                });
+                let baseline = self.y - size * 1.05;
+                self.draw_text(MARGIN + indent + 6.0, baseline, size, style, &piece, false);
+                self.y -= line_height;
+            }
+        }
+        self.gap(size * 0.6);
+    }
+
+    fn rule(&mut self) {
+        self.gap(self.base_size * 0.5);
+        self.ensure_space(2.0);
+        let y = self.y;
+        self.push(Op::Line {
+            x1: MARGIN,
+            y1: y,
+            x2: self.width - MARGIN,
+            y2: y,
+            gray: 0.7,
+        });
+        self.gap(self.base_size * 0.5);
+    }
+
+    fn table(&mut self, rows: &[(bool, Vec<Vec<Segment>>)]) {
+        let cols = rows.iter().map(|(_, r)| r.len()).max().unwrap_or(0);
+        if cols == 0 {
+            return;
+        }
+        let size = self.base_size * 0.9;
+        let padding = 4.0;
+        let col_w = self.content_width() / cols as f32;
+        self.gap(size * 0.4);
+        let line_height = size * 1.45;
+        for (header, cells) in rows {
+            // 按实际折行计算每个单元格的行数 / Wrap every cell exactly as it will be drawn
+            let wrapped: Vec<Vec<Vec<Segment>>> = cells
+                .iter()
+                .map(|cell| {
+                    let cell: Vec<Segment> = cell
+                        .iter()
+                        .map(|(s, t)| {
+                            (
+                                Style {
+                                    bold: s.bold || *header,
+                                    ..*s
+                                },
+                                t.clone(),
+                            )
+                        })
+                        .collect();
+                    self.wrap(&cell, col_w - 2.0 * padding, size)
+                })
+                .collect();
+            let total = wrapped.iter().map(Vec::len).max().unwrap_or(0).max(1);
+            // 放得下时整行留在同一页；比一页还高的行按行拆到后续页
+            // Keep a row on one page when it fits; a row taller than a page continues on the next
+            self.ensure_space(total as f32 * line_height + 2.0 * padding);
+            let mut start = 0;
+            loop {
+                let available = ((self.y - MARGIN - 2.0 * padding) / line_height).floor();
+                let count = (total - start).min(available.max(1.0) as usize);
+                let row_h = count as f32 * line_height + 2.0 * padding;
+                let top = self.y;
+                if *header {
+                    self.push(Op::Rect {
+                        x: MARGIN,
+                        y: top - row_h,
+                        w: col_w * cols as f32,
+                        h: row_h,
+                        gray: 0.93,
+                    });
+                }
+                for (i, lines) in wrapped.iter().enumerate() {
+                    let x0 = MARGIN + i as f32 * col_w + padding;
+                    let end = lines.len().min(start + count);
+                    for (k, line) in lines.get(start..end).unwrap_or_default().iter().enumerate() {
+                        let baseline = top - padding - k as f32 * line_height - size * 1.1;
+                        self.draw_line(x0, baseline, size, line, false);
+                    }
+                }
+                for i in 0..=cols {
+                    let x = MARGIN + i as f32 * col_w;
+                    self.push(Op::Line {
+                        x1: x,
+                        y1: top,
+                        x2: x,
+                        y2: top - row_h,
+                        gray: 0.6,
+                    });
+                }
+                for y in [top, top - row_h] {
+                    self.push(Op::Line {
+                        x1: MARGIN,
+                        y1: y,
+                        x2: MARGIN + col_w * cols as f32,
+                        y2: y,
+                        gray: 0.6,
+                    });
+                }
+                self.y = top - row_h;
+                start += count;
+                if start >= total {
+                    break;
+                }
+                self.new_page();
+            }
+        }
+        self.gap(size * 0.6);
+    }
+
+    fn image(&mut self, path: &Path, indent: f32) -> bool {
+        let img = match image::open(path) {
+            Ok(img) => img.to_rgba8(),
+            Err(e) => {
+                log::warn!("Failed to decode image {:?}: {}", path, e);
+                return false;
+            }
+        };
+        let (w, h) = img.dimensions();
+        // 透明区域合成到白底 / Composite transparency onto white
+        let mut rgb = Vec::with_capacity((w * h * 3) as usize);
+        for p in img.pixels() {
+            let a = p[3] as f32 / 255.0;
+            for c in 0..3 {
+                rgb.push((p[c] as f32 * a + 255.0 * (1.0 - a)).round() as u8);
+            }
+        }
+        // 按 96dpi 换算，并限制在版心内 / 96 dpi, clamped to the text block
+        let mut dw = w as f32 * 0.75;
+        let mut dh = h as f32 * 0.75;
+        let max_w = self.content_width() - indent;
+        let max_h = self.height - 2.0 * MARGIN;
+        let scale = (max_w / dw).min(max_h / dh).min(1.0);
+        dw *= scale;
+        dh *= scale;
+        self.ensure_space(dh);
+        self.images.push(RasterImage {
+            width: w,
+            height: h,
+            rgb,
+        });
+        let index = self.images.len() - 1;
+        self.push(Op::Image {
+            x: MARGIN + indent,
+            y: self.y - dh,
+            w: dw,
+            h: dh,
+            index,
+        });
+        self.y -= dh + self.base_size * 0.5;
+        true
+    }
+
+    /// 页眉页脚（需要总页数，最后统一添加）
+    fn decorate(&mut self, header: &str, footer: &str) {
+        let total = self.pages.len();
+        let size = self.base_size * 0.75;
+        let plain = Style::default();
+        for i in 0..total {
+            self.current = i;
+            if !header.is_empty() {
+                let y = self.height - MARGIN + size * 1.6;
+                self.draw_text(MARGIN, y, size, plain, header, true);
+                self.push(Op::Line {
+                    x1: MARGIN,
+                    y1: y - size * 0.5,
+                    x2: self.width - MARGIN,
+                    y2: y - size * 0.5,
+                    gray: 0.8,
+                });
+            }
+            if !footer.is_empty() {
+                let text = footer
+                    .replace("{page}", &(i + 1).to_string())
+                    .replace("{pages}", &total.to_string());
+                let w = self.fonts.measure(plain, &text, size);
+                self.draw_text(
+                    (self.width - w) / 2.0,
+                    MARGIN - size * 2.4,
+                    size,
+                    plain,
+                    &text,
+                    true,
+                );
+            }
+        }
+    }
+}
+
+/// 将 Markdown 事件排版到页面 / Lays out Markdown events onto pages
+struct Renderer<'a> {
+    layout: &'a mut Layout,
+    window: &'a str,
+    base_dir: PathBuf,
+    segments: Vec<Segment>,
+    style: Style,
+    lists: Vec<Option<u64>>,
+    item_prefix: Option<String>,
+    quote_depth: usize,
+    heading: Option<u8>,
+    code: Option<String>,
+    image: Option<(String, String)>,
+    table: Vec<(bool, Vec<Vec<Segment>>)>,
+    in_table: bool,
+    skip: bool,
+    footnotes: HashMap<String, usize>,
+}
+
+impl Renderer<'_> {
+    fn indent(&self) -> f32 {
+        self.lists.len() as f32 * 18.0 + self.quote_depth as f32 * 14.0
+    }
+
+    fn text(&mut self, t: &str) {
+        match self.segments.last_mut() {
+            Some((s, existing)) if *s == self.style => existing.push_str(t),
+            _ => self.segments.push((self.style, t.to_string())),
+        }
+    }
+
+    fn flush(&mut self) {
+        let prefix = self.item_prefix.take();
+        if self.segments.is_empty() && prefix.is_none() {
+            return;
+        }
+        let segments = std::mem::take(&mut self.segments);
+        let size = self.layout.base_size;
+        let indent = self.indent();
+        self.layout.paragraph(
+            &segments,
+            indent,
+            size,
+            prefix.as_deref(),
+            self.quote_depth > 0,
+        );
+        self.layout.gap(size * 0.4);
+    }
+
+    fn footnote_number(&mut self, label: &str) -> usize {
+        let next = self.footnotes.len() + 1;
+        *self.footnotes.entry(label.to_string()).or_insert(next)
+    }
+
+    fn handle(&mut self, event: Event) {
+        if let Some(code) = self.code.as_mut() {
+            match event {
+                Event::Text(t) => code.push_str(&t),
+                Event::End(TagEnd::CodeBlock) => {
+                    let code = self.code.take().unwrap_or_default();
+                    let indent = self.indent();
+                    self.layout.code_block(&code, indent);
+                }
+                _ => {}
+            }
+            return;
+        }
+        if let Some((_, alt)) = self.image.as_mut() {
+            match event {
+                Event::Text(t) | Event::Code(t) => alt.push_str(&t),
+                Event::End(TagEnd::Image) => {
+                    let (url, alt) = self.image.take().unwrap_or_default();
+                    let local = markdown::resolve_local(&self.base_dir, &url)
+                        .filter(|p| crate::ensure_in_workspace(self.window, p).is_ok());
+                    let drawn = match local {
+                        Some(p) if !self.in_table && self.heading.is_none() => {
+                            self.flush();
+                            let indent = self.indent();
+                            self.layout.image(&p, indent)
+                        }
+                        _ => false,
+                    };
+                    if !drawn {
+                        let saved = self.style;
+                        self.style.italic = true;
+                        self.text(&format!("[{}]", if alt.is_empty() { &url } else { &alt }));
+                        self.style = saved;
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(t) if !self.skip => self.text(&t),
+            Event::Code(t) => {
+                let saved = self.style;
+                self.style.code = true;
+                self.text(&t);
+                self.style = saved;
+            }
+            Event::SoftBreak => self.text(" "),
+            Event::HardBreak => self.flush(),
+            Event::Rule => {
+                self.flush();
+                self.layout.rule();
+            }
+            Event::FootnoteReference(label) => {
+                let n = self.footnote_number(&label);
+                self.text(&format!("[{}]", n));
+            }
+            Event::TaskListMarker(checked) => self.text(if checked { "[x] " } else { "[ ] " }),
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph if self.item_prefix.is_none() => self.flush(),
+            Tag::Heading { level, .. } => {
+                self.flush();
+                self.heading = Some(markdown::heading_level_number(level));
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.flush();
+                self.code = Some(String::new());
+            }
+            Tag::List(start) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let prefix = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        let p = format!("{}.", n);
+                        *n += 1;
+                        p
+                    }
+                    _ => "•".to_string(),
+                };
+                self.item_prefix = Some(prefix);
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.flush();
+                let n = self.footnote_number(&label);
+                self.text(&format!("[{}] ", n));
+            }
+            Tag::Table(_) => {
+                self.flush();
+                self.in_table = true;
+                self.table.clear();
+            }
+            Tag::TableHead => self.table.push((true, Vec::new())),
+            Tag::TableRow => self.table.push((false, Vec::new())),
+            Tag::TableCell => self.segments.clear(),
+            Tag::Emphasis => self.style.italic = true,
+            Tag::Strong => self.style.bold = true,
+            Tag::Link { .. } => self.style.link = true,
+            Tag::Image { dest_url, .. } => self.image = Some((dest_url.to_string(), String::new())),
+            Tag::MetadataBlock(_) | Tag::HtmlBlock => self.skip = true,
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Item | TagEnd::FootnoteDefinition => self.flush(),
+            TagEnd::Heading(_) => {
+                let segments = std::mem::take(&mut self.segments);
+                if let Some(level) = self.heading.take() {
+                    self.layout.heading(level, &segments);
+                }
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.layout.gap(self.layout.base_size * 0.3);
+                }
+            }
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.segments);
+                if let Some((_, cells)) = self.table.last_mut() {
+                    cells.push(cell);
+                }
+            }
+            TagEnd::Table => {
+                self.in_table = false;
+                let rows = std::mem::take(&mut self.table);
+                self.layout.table(&rows);
+            }
+            TagEnd::Emphasis => self.style.italic = false,
+            TagEnd::Strong => self.style.bold = false,
+            TagEnd::Link => self.style.link = false,
+            TagEnd::MetadataBlock(_) | TagEnd::HtmlBlock => self.skip = false,
+            _ => {}
+        }
+    }
+}
+
+fn compress(data: &[u8]) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(data, 6)
+}
+
+/// 子集字体名前缀（6 个大写字母） / Six-letter subset tag
+fn subset_tag(glyphs: &[u16]) -> String {
+    let mut h: u32 = 2166136261;
+    for g in glyphs {
+        h = (h ^ *g as u32).wrapping_mul(16777619);
+    }
+    (0..6)
+        .map(|i| (b'A' + ((h >> (i * 5)) % 26) as u8) as char)
+        .collect()
+}
+
+/// 构建层级书签：返回每项的 (父, 上一个, 下一个, 第一个子, 最后一个子, 子孙数)
+#[allow(clippy::type_complexity)]
+fn outline_tree(
+    entries: &[OutlineEntry],
+) -> Vec<(
+    Option<usize>,
+    Option<usize>,
+    Option<usize>,
+    Option<usize>,
+    Option<usize>,
+    i32,
+)> {
+    let n = entries.len();
+    let mut parent = vec![None; n];
+    let mut stack: Vec<usize> = Vec::new();
+    for i in 0..n {
+        while let Some(&top) = stack.last() {
+            if entries[top].level >= entries[i].level {
+                stack.pop();
+            } else {
+                break;
+            }
+        }
+        parent[i] = stack.last().copied();
+        stack.push(i);
+    }
+    let mut result = vec![(None, None, None, None, None, 0); n];
+    for i in 0..n {
+        result[i].0 = parent[i];
+        let siblings: Vec<usize> = (0..n).filter(|&j| parent[j] == parent[i]).collect();
+        let pos = siblings.iter().position(|&j| j == i).unwrap_or(0);
+        result[i].1 = if pos > 0 {
+            Some(siblings[pos - 1])
+        } else {
+            None
+        };
+        result[i].2 = siblings.get(pos + 1).copied();
+        let children: Vec<usize> = (0..n).filter(|&j| parent[j] == Some(i)).collect();
+        result[i].3 = children.first().copied();
+        result[i].4 = children.last().copied();
+    }
+    for i in (0..n).rev() {
+        if let Some(p) = parent[i] {
+            result[p].5 += 1 + result[i].5;
+        }
+    }
+    result
+}
+
+/// 写出 PDF 字节 / Serializes the laid-out pages
+fn write_pdf(layout: &mut Layout, title: &str) -> Vec<u8> {
+    let mut next_id = 1;
+    let mut alloc = || {
+        let r = Ref::new(next_id);
+        next_id += 1;
+        r
+    };
+    let catalog_id = alloc();
+    let pages_id = alloc();
+    let info_id = alloc();
+    let outline_id = alloc();
+    let page_ids: Vec<Ref> = layout.pages.iter().map(|_| alloc()).collect();
+    let content_ids: Vec<Ref> = layout.pages.iter().map(|_| alloc()).collect();
+    let image_ids: Vec<Ref> = layout.images.iter().map(|_| alloc()).collect();
+    let font_ids: Vec<Option<Ref>> = layout
+        .fonts
+        .faces
+        .iter()
+        .map(|f| match f {
+            Some(face) if !face.used.is_empty() => Some(alloc()),
+            _ => None,
+        })
+        .collect();
+    let outline_item_ids: Vec<Ref> = layout.outline.iter().map(|_| alloc()).collect();
+
+    let mut pdf = Pdf::new();
+    let mut catalog = pdf.catalog(catalog_id);
+    catalog.pages(pages_id);
+    if !layout.outline.is_empty() {
+        catalog.outlines(outline_id);
+        catalog.page_mode(pdf_writer::types::PageMode::UseOutlines);
+    }
+    catalog.finish();
+    pdf.document_info(info_id)
+        .title(TextStr(title))
+        .producer(TextStr("MarkdownMonkey"));
+    pdf.pages(pages_id)
+        .kids(page_ids.iter().copied())
+        .count(page_ids.len() as i32);
+
+    for (i, ops) in layout.pages.iter().enumerate() {
+        let mut content = Content::new();
+        for op in ops {
+            match op {
+                Op::Rect { x, y, w, h, gray } => {
+                    content
+                        .set_fill_gray(*gray)
+                        .rect(*x, *y, *w, *h)
+                        .fill_nonzero();
+                }
+                Op::Line {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    gray,
+                } => {
+                    content
+                        .set_stroke_gray(*gray)
+                        .set_line_width(0.6)
+                        .move_to(*x1, *y1)
+                        .line_to(*x2, *y2)
+                        .stroke();
+                }
+                Op::Image { x, y, w, h, index } => {
+                    let name = format!("Im{}", index);
+                    content
+                        .save_state()
+                        .transform([*w, 0.0, 0.0, *h, *x, *y])
+                        .x_object(Name(name.as_bytes()))
+                        .restore_state();
+                }
+                Op::Text {
+                    x,
+                    y,
+                    size,
+                    font,
+                    glyphs,
+                    color,
+                } => {
+                    let name = format!("F{}", font);
+                    let bytes: Vec<u8> = glyphs.iter().flat_map(|g| g.to_be_bytes()).collect();
+                    content
+                        .set_fill_rgb(color.0, color.1, color.2)
+                        .begin_text()
+                        .set_font(Name(name.as_bytes()), *size)
+                        .next_line(*x, *y)
+                        .show(Str(&bytes))
+                        .end_text();
+                }
+            }
+        }
+        let data = compress(&content.finish());
+        pdf.stream(content_ids[i], &data)
+            .filter(Filter::FlateDecode);
+
+        let mut page = pdf.page(page_ids[i]);
+        page.parent(pages_id)
+            .media_box(Rect::new(0.0, 0.0, layout.width, layout.height))
+            .contents(content_ids[i]);
+        let mut resources = page.resources();
+        let mut fonts = resources.fonts();
+        for (idx, id) in font_ids.iter().enumerate() {
+            if let Some(id) = id {
+                fonts.pair(Name(format!("F{}", idx).as_bytes()), *id);
+            }
+        }
+        fonts.finish();
+        if !image_ids.is_empty() {
+            let mut xobjects = resources.x_objects();
+            for (idx, id) in image_ids.iter().enumerate() {
+                xobjects.pair(Name(format!("Im{}", idx).as_bytes()), *id);
+            }
+        }
+    }
+
+    for (img, id) in layout.images.iter().zip(&image_ids) {
+        let data = compress(&img.rgb);
+        let mut xobject = pdf.image_xobject(*id, &data);
+        xobject.filter(Filter::FlateDecode);
+        xobject
+            .width(img.width as i32)
+            .height(img.height as i32)
+            .bits_per_component(8);
+        xobject.color_space().device_rgb();
+    }
+
+    for (idx, face) in layout.fonts.faces.iter().enumerate() {
+        let (Some(face), Some(type0_id)) = (face, font_ids[idx]) else {
+            continue;
+        };
+        write_font(&mut pdf, face, type0_id, &mut alloc);
+    }
+
+    if !layout.outline.is_empty() {
+        let tree = outline_tree(&layout.outline);
+        let roots: Vec<usize> = (0..tree.len()).filter(|&i| tree[i].0.is_none()).collect();
+        let mut outline = pdf.outline(outline_id);
+        if let (Some(first), Some(last)) = (roots.first(), roots.last()) {
+            outline
+                .first(outline_item_ids[*first])
+                .last(outline_item_ids[*last]);
+        }
+        outline.count(layout.outline.len() as i32);
+        outline.finish();
+        for (i, entry) in layout.outline.iter().enumerate() {
+            let (parent, prev, next, first, last, count) = tree[i];
+            let mut item = pdf.outline_item(outline_item_ids[i]);
+            item.title(TextStr(&entry.title))
+                .parent(parent.map(|p| outline_item_ids[p]).unwrap_or(outline_id));
+            if let Some(p) = prev {
+                item.prev(outline_item_ids[p]);
+            }
+            if let Some(n) = next {
+                item.next(outline_item_ids[n]);
+            }
+            if let (Some(f), Some(l)) = (first, last) {
+                item.first(outline_item_ids[f])
+                    .last(outline_item_ids[l])
+                    .count(count);
+            }
+            item.dest()
+                .page(page_ids[entry.page])
+                .xyz(MARGIN, entry.y, None);
+        }
+    }
+
+    pdf.finish()
+}
+
+/// 以 Identity-H 编码嵌入 CID 字体（子集化失败时嵌入完整字体）
+fn write_font(pdf: &mut Pdf, face: &FontFace, type0_id: Ref, alloc: &mut impl FnMut() -> Ref) {
+    let cid_id = alloc();
+    let descriptor_id = alloc();
+    let file_id = alloc();
+    let cmap_id = alloc();
+
+    let glyphs: Vec<u16> = face.used.keys().copied().collect();
+    let parsed = face.face();
+    let is_cff = parsed
+        .as_ref()
+        .map(|f| f.tables().cff.is_some())
+        .unwrap_or(false);
+    let ps_name = parsed
+        .as_ref()
+        .and_then(|f| {
+            f.names()
+                .into_iter()
+                .find(|n| n.name_id == ttf_parser::name_id::POST_SCRIPT_NAME)
+                .and_then(|n| n.to_string())
+        })
+        .unwrap_or_else(|| "MarkdownMonkeyFont".to_string());
+    let base_font = format!("{}+{}", subset_tag(&glyphs), ps_name.replace(' ', ""));
+    let scale = |v: f32| v * 1000.0 / face.units_per_em;
+    let (bbox, ascent, descent, cap_height) = match &parsed {
+        Some(f) => {
+            let b = f.global_bounding_box();
+            (
+                Rect::new(
+                    scale(b.x_min as f32),
+                    scale(b.y_min as f32),
+                    scale(b.x_max as f32),
+                    scale(b.y_max as f32),
+                ),
+                scale(f.ascender() as f32),
+                scale(f.descender() as f32),
+                scale(f.capital_height().unwrap_or(f.ascender()) as f32),
+            )
+        }
+        None => (Rect::new(0.0, -200.0, 1000.0, 900.0), 800.0, -200.0, 700.0),
+    };
+
+    let system_info = SystemInfo {
+        registry: Str(b"Adobe"),
+        ordering: Str(b"Identity"),
+        supplement: 0,
+    };
+
+    pdf.type0_font(type0_id)
+        .base_font(Name(base_font.as_bytes()))
+        .encoding_predefined(Name(b"Identity-H"))
+        .descendant_font(cid_id)
+        .to_unicode(cmap_id);
+
+    let mut cid = pdf.cid_font(cid_id);
+    cid.subtype(if is_cff {
+        CidFontType::Type0
+    } else {
+        CidFontType::Type2
+    })
+    .base_font(Name(base_font.as_bytes()))
+    .system_info(system_info)
+    .font_descriptor(descriptor_id)
+    .default_width(0.0);
+    if !is_cff {
+        cid.cid_to_gid_map_predefined(Name(b"Identity"));
+    }
+    let mut widths = cid.widths();
+    for (gid, (_, w)) in &face.used {
+        widths.consecutive(*gid, [*w]);
+    }
+    widths.finish();
+    cid.finish();
+
+    let mut descriptor = pdf.font_descriptor(descriptor_id);
+    descriptor
+        .name(Name(base_font.as_bytes()))
+        .flags(FontFlags::NON_SYMBOLIC)
+        .bbox(bbox)
+        .italic_angle(0.0)
+        .ascent(ascent)
+        .descent(descent)
+        .cap_height(cap_height)
+        .stem_v(80.0);
+    if is_cff {
+        descriptor.font_file3(file_id);
+    } else {
+        descriptor.font_file2(file_id);
+    }
+    descriptor.finish();
+
+    let program = subsetter::subset(&face.data, face.index, subsetter::Profile::pdf(&glyphs))
+        .unwrap_or_else(|e| {
+            log::warn!("Font subsetting failed, embedding full font: {}", e);
+            face.data.clone()
+        });
+    let compressed = compress(&program);
+    let mut stream = pdf.stream(file_id, &compressed);
+    stream.filter(Filter::FlateDecode);
+    if is_cff {
+        stream.pair(Name(b"Subtype"), Name(b"OpenType"));
+    } else {
+        stream.pair(Name(b"Length1"), program.len() as i32);
+    }
+    stream.finish();
+
+    let mut cmap = UnicodeCmap::new(Name(b"Custom"), system_info);
+    for (gid, (c, _)) in &face.used {
+        cmap.pair(*gid, *c);
+    }
+    pdf.stream(cmap_id, &cmap.finish());
+}
+
+/// export
+/// 排版并写出 PDF，返回输出路径
+pub fn export(path: &Path, options: &PdfExportOptions) -> Result<PathBuf, AppError> {
+    let sources = collect_sources(path)?;
+    let fonts = FontSet::load(options)?;
+    let mut layout = Layout::new(fonts, options);
+    let mut slugs = SlugSet::default();
+    let mut title = options.title.clone();
+
+    for (i, source) in sources.iter().enumerate() {
+        let text = read_source(source)?;
+        if title.is_none() {
+            title = Some(markdown::document_title(&text, source));
+        }
+        if i > 0 {
+            layout.new_page();
+        }
+        let (events, _) = markdown::parse_with_anchors(&text, &mut slugs);
+        let mut renderer = Renderer {
+            layout: &mut layout,
+            window: &options.window,
+            base_dir: source.parent().unwrap_or(Path::new(".")).to_path_buf(),
+            segments: Vec::new(),
+            style: Style::default(),
+            lists: Vec::new(),
+            item_prefix: None,
+            quote_depth: 0,
+            heading: None,
+            code: None,
+            image: None,
+            table: Vec::new(),
+            in_table: false,
+            skip: false,
+            footnotes: HashMap::new(),
+        };
+        for event in events {
+            renderer.handle(event);
+        }
+        renderer.flush();
+    }
+
+    let title = title.unwrap_or_else(|| "Document".to_string());
+    let header = options.header.clone().unwrap_or_else(|| title.clone());
+    layout.decorate(&header, &options.footer);
+    let bytes = write_pdf(&mut layout, &title);
+
+    let output = options
+        .output
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_output(path, "pdf"));
+    std::fs::write(&output, bytes)?;
+    Ok(output)
+}
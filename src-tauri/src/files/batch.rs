@@ -0,0 +1,658 @@
+//! 批量文件操作 / Batch file operations
+//!
+//! 一次提交多个新建、重命名、复制、删除操作：先按顺序整体校验（含工作区沙箱），再依次执行；
+//! 任一步失败时按相反顺序撤销已完成的步骤。删除与被覆盖的文件先移到同目录的暂存名下，全部成功后才真正删除。
+//! Validates every step up front, applies them in order and undoes applied steps if one fails.
+
+use super::{available_path_by, copy, is_symlink, occupied, Conflict, CopyOptions};
+use crate::assets::normalize_path;
+use crate::error::AppError;
+use crate::git::display;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 暂存名计数 / Counter for staging names
+static STAGE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// FsOp
+/// 单个文件操作（`op` 字段区分类型）
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum FsOp {
+    /// 新建文件（可带内容）或文件夹 / Create a file with optional content, or a folder
+    Create {
+        path: String,
+        #[serde(default)]
+        content: Option<String>,
+        #[serde(default)]
+        dir: bool,
+    },
+    /// 重命名或移动 / Rename or move
+    Rename { src: String, dst: String },
+    /// 复制（同 `copy_path`） / Copy, as `copy_path`
+    Copy {
+        src: String,
+        dst: String,
+        #[serde(default)]
+        options: CopyOptions,
+    },
+    /// 删除文件或文件夹 / Delete a file or folder
+    Delete { path: String },
+}
+
+impl FsOp {
+    fn name(&self) -> &'static str {
+        match self {
+            FsOp::Create { .. } => "create",
+            FsOp::Rename { .. } => "rename",
+            FsOp::Copy { .. } => "copy",
+            FsOp::Delete { .. } => "delete",
+        }
+    }
+}
+
+/// 操作状态 / Outcome of one operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpStatus {
+    /// 已执行 / Applied and kept
+    Applied,
+    /// 已执行但因后续失败被撤销 / Applied, then undone after a later failure
+    RolledBack,
+    /// 执行失败 / Failed while running
+    Failed,
+    /// 校验未通过 / Rejected by validation
+    Invalid,
+    /// 未执行 / Not run
+    Skipped,
+}
+
+/// OpReport
+/// 单个操作的结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OpReport {
+    pub index: usize,
+    pub op: &'static str,
+    pub status: OpStatus,
+    /// 操作结果路径（复制自动改名后为实际路径） / Resulting path, after any auto-rename
+    pub path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// BatchReport
+/// 批量操作结果；`ok` 为 false 时工作区已恢复原状（撤销失败的项见 `rollback_errors`）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BatchReport {
+    pub ok: bool,
+    pub ops: Vec<OpReport>,
+    pub rollback_errors: Vec<String>,
+}
+
+/// 撤销步骤 / One undo step
+enum Undo {
+    /// 删除新建的文件或文件夹 / Remove something the batch created
+    Remove(PathBuf),
+    /// 删除新建的空文件夹 / Remove a created folder if it is still empty
+    RemoveEmptyDir(PathBuf),
+    /// 把暂存的原文件移回 / Move a staged original back
+    Restore { staged: PathBuf, original: PathBuf },
+    /// 把移动过的路径移回 / Move a renamed path back
+    Move { from: PathBuf, to: PathBuf },
+}
+
+impl Undo {
+    fn run(&self) -> std::io::Result<()> {
+        let result = match self {
+            Undo::Remove(path) if path.is_dir() => std::fs::remove_dir_all(path),
+            Undo::Remove(path) => std::fs::remove_file(path),
+            Undo::RemoveEmptyDir(path) => match std::fs::remove_dir(path) {
+                // 非空说明里面有不属于本批次的内容，保留
+                // Not empty means it holds content this batch did not create
+                Err(_) if path.is_dir() => Ok(()),
+                other => other,
+            },
+            Undo::Restore { staged, original } => return std::fs::rename(staged, original),
+            Undo::Move { from, to } => return std::fs::rename(from, to),
+        };
+        // 失败的步骤可能只写了一部分，要删除的项不存在即可
+        // A failed step may have written only part of its files; absent is fine
+        match result {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            other => other,
+        }
+    }
+}
+
+/// 已执行的步骤：撤销动作与全部成功后需清理的暂存项
+/// Undo steps plus staged paths to purge once the whole batch succeeds
+#[derive(Default)]
+struct Journal {
+    undo: Vec<Undo>,
+    staged: Vec<PathBuf>,
+}
+
+impl Journal {
+    /// 新建 `dir` 缺失的各级目录，并记录撤销 / Creates missing folders up to `dir`
+    fn create_dirs(&mut self, dir: &Path) -> Result<(), AppError> {
+        let missing: Vec<PathBuf> = dir
+            .ancestors()
+            .take_while(|p| !p.as_os_str().is_empty() && !p.exists())
+            .map(Path::to_path_buf)
+            .collect();
+        std::fs::create_dir_all(dir)?;
+        // 外层先记录，撤销时先删内层 / Outer first so undo removes inner folders first
+        for path in missing.into_iter().rev() {
+            self.undo.push(Undo::RemoveEmptyDir(path));
+        }
+        Ok(())
+    }
+
+    /// 把已有路径移到同目录的暂存名下 / Moves an existing path aside under a hidden sibling name
+    fn stage(&mut self, path: &Path) -> Result<(), AppError> {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let n = STAGE_COUNTER.fetch_add(1, Ordering::SeqCst);
+        let staged = path.with_file_name(format!(".{}.batch-{}-{}", name, std::process::id(), n));
+        std::fs::rename(path, &staged)?;
+        self.undo.push(Undo::Restore {
+            staged: staged.clone(),
+            original: path.to_path_buf(),
+        });
+        self.staged.push(staged);
+        Ok(())
+    }
+}
+
+/// 校验阶段记录的路径变化 / A path change recorded during validation
+enum Change {
+    /// 被删除或移走 / Deleted or moved away
+    Gone,
+    /// 新建（其下尚无内容） / Newly created, with nothing inside yet
+    Created { dir: bool },
+    /// 由另一路径移动或复制而来 / Moved or copied from another path
+    From(PathBuf),
+}
+
+/// 按顺序推演前序操作的效果，供后续操作校验源与目标
+/// Replays earlier operations so later ones are validated against the resulting tree
+#[derive(Default)]
+struct Plan {
+    changes: Vec<(PathBuf, Change)>,
+}
+
+impl Plan {
+    fn exists(&self, path: &Path) -> bool {
+        Self::kind_in(&self.changes, path).is_some()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        Self::kind_in(&self.changes, path) == Some(true)
+    }
+
+    /// 推演后路径上的条目：不存在为 `None`，否则为是否文件夹
+    /// What is at `path` after the changes: `None` if absent, else whether it is a folder
+    fn kind_in(changes: &[(PathBuf, Change)], path: &Path) -> Option<bool> {
+        for (i, (changed, change)) in changes.iter().enumerate().rev() {
+            if let Ok(rest) = path.strip_prefix(changed) {
+                return match change {
+                    Change::Gone => None,
+                    Change::Created { dir } => rest.as_os_str().is_empty().then_some(*dir),
+                    // `join("")` 会追加斜杠，文件路径便不再存在 / `join("")` adds a trailing slash
+                    Change::From(origin) if rest.as_os_str().is_empty() => {
+                        Self::kind_in(&changes[..i], origin)
+                    }
+                    Change::From(origin) => Self::kind_in(&changes[..i], &origin.join(rest)),
+                };
+            }
+            // 其下有新内容的路径必然是文件夹 / An ancestor of something present is a folder
+            if changed.starts_with(path) && !matches!(change, Change::Gone) {
+                return Some(true);
+            }
+        }
+        // 与复制一致，不跟随符号链接 / Symlinks are not followed, as in `copy`
+        std::fs::symlink_metadata(path).ok().map(|m| m.is_dir())
+    }
+}
+
+/// 仅大小写不同且指向同一文件（不区分大小写的文件系统）时可直接重命名
+/// A case-only rename on a case-insensitive file system targets the source itself
+fn same_file(src: &Path, dst: &Path) -> bool {
+    src != dst
+        && display(src).to_lowercase() == display(dst).to_lowercase()
+        && !is_symlink(dst)
+        && matches!(
+            (std::fs::canonicalize(src), std::fs::canonicalize(dst)),
+            (Ok(a), Ok(b)) if a == b
+        )
+}
+
+/// 规范化并校验路径 / Normalizes a path and checks it against the sandbox
+fn checked(path: &str, sandbox: &dyn Fn(&Path) -> Result<(), String>) -> Result<PathBuf, String> {
+    let path = Path::new(path.trim());
+    if !path.is_absolute() {
+        return Err(format!("须为绝对路径: {}", display(path)));
+    }
+    let path = normalize_path(path);
+    if path.parent().is_none() {
+        return Err(format!("无效的路径: {}", display(&path)));
+    }
+    sandbox(&path)?;
+    Ok(path)
+}
+
+/// 校验单个操作，返回规范化后的路径（源, 目标） / Validates one operation against the plan
+fn validate(
+    op: &FsOp,
+    plan: &mut Plan,
+    sandbox: &dyn Fn(&Path) -> Result<(), String>,
+) -> Result<(PathBuf, Option<PathBuf>), String> {
+    let missing = |p: &Path| format!("源路径不存在: {}", display(p));
+    let taken = |p: &Path| format!("目标已存在: {}", display(p));
+    match op {
+        FsOp::Create { path, dir, .. } => {
+            let path = checked(path, sandbox)?;
+            if plan.exists(&path) {
+                return Err(taken(&path));
+            }
+            plan.changes
+                .push((path.clone(), Change::Created { dir: *dir }));
+            Ok((path, None))
+        }
+        FsOp::Rename { src, dst } => {
+            let src = checked(src, sandbox)?;
+            let dst = checked(dst, sandbox)?;
+            if !plan.exists(&src) {
+                return Err(missing(&src));
+            }
+            if dst.starts_with(&src) {
+                return Err("不能将文件夹移动到其自身内部".to_string());
+            }
+            if plan.exists(&dst) && !same_file(&src, &dst) {
+                return Err(taken(&dst));
+            }
+            plan.changes.push((dst.clone(), Change::From(src.clone())));
+            plan.changes.push((src.clone(), Change::Gone));
+            Ok((src, Some(dst)))
+        }
+        FsOp::Copy { src, dst, options } => {
+            let src = checked(src, sandbox)?;
+            let mut dst = checked(dst, sandbox)?;
+            if !plan.exists(&src) {
+                return Err(missing(&src));
+            }
+            if dst.starts_with(&src) && dst != src {
+                return Err("不能将文件夹复制到其自身内部".to_string());
+            }
+            // 按推演后的目录树确定自动改名的目标，后续操作才能引用它
+            // Pick the auto-renamed target against the planned tree so later steps see it
+            if options.conflict == Conflict::AutoRename {
+                dst = available_path_by(&dst, plan.is_dir(&src), &|p| plan.exists(p));
+                sandbox(&dst)?;
+            }
+            plan.changes.push((dst.clone(), Change::From(src.clone())));
+            Ok((src, Some(dst)))
+        }
+        FsOp::Delete { path } => {
+            let path = checked(path, sandbox)?;
+            if !plan.exists(&path) {
+                return Err(missing(&path));
+            }
+            plan.changes.push((path.clone(), Change::Gone));
+            Ok((path, None))
+        }
+    }
+}
+
+/// 复制会写到的文件夹与文件（外层在前） / Folders and files a copy writes to, outermost first
+fn copy_targets(src: &Path, dst: &Path, dirs: &mut Vec<PathBuf>, files: &mut Vec<PathBuf>) {
+    // 与复制一致，两端的符号链接都跳过 / Symlinks on either end are skipped, as in `copy`
+    if is_symlink(src) || is_symlink(dst) {
+        return;
+    }
+    if !src.is_dir() {
+        files.push(dst.to_path_buf());
+        return;
+    }
+    dirs.push(dst.to_path_buf());
+    for entry in std::fs::read_dir(src).into_iter().flatten().flatten() {
+        copy_targets(&entry.path(), &dst.join(entry.file_name()), dirs, files);
+    }
+}
+
+/// 执行单个已校验的操作，返回结果路径 / Applies one validated operation
+fn apply(
+    op: &FsOp,
+    src: &Path,
+    dst: Option<&Path>,
+    journal: &mut Journal,
+) -> Result<PathBuf, AppError> {
+    match op {
+        FsOp::Create { content, dir, .. } => {
+            if *dir {
+                journal.create_dirs(src)?;
+            } else {
+                if let Some(parent) = src.parent() {
+                    journal.create_dirs(parent)?;
+                }
+                crate::templates::write_new(src, content.as_deref().unwrap_or(""))?;
+                journal.undo.push(Undo::Remove(src.to_path_buf()));
+            }
+            Ok(src.to_path_buf())
+        }
+        FsOp::Rename { .. } => {
+            let dst = dst.unwrap_or(src);
+            if occupied(dst) && !same_file(src, dst) {
+                return Err(AppError::InvalidInput(format!(
+                    "目标已存在: {}",
+                    display(dst)
+                )));
+            }
+            if let Some(parent) = dst.parent() {
+                journal.create_dirs(parent)?;
+            }
+            std::fs::rename(src, dst)?;
+            journal.undo.push(Undo::Move {
+                from: dst.to_path_buf(),
+                to: src.to_path_buf(),
+            });
+            Ok(dst.to_path_buf())
+        }
+        FsOp::Copy { options, .. } => {
+            let dst = dst.unwrap_or(src);
+            // 自动改名的目标已在校验时确定，此后被占用则不再另选，撤销才知道删除哪里
+            // The auto-renamed target was fixed during validation; keep undo exact if it got taken since
+            if options.conflict == Conflict::AutoRename && occupied(dst) {
+                return Err(AppError::InvalidInput(format!(
+                    "目标已存在: {}",
+                    display(dst)
+                )));
+            }
+            if let Some(parent) = dst.parent() {
+                journal.create_dirs(parent)?;
+            }
+            // 复制中途失败也要能撤销，所以预先记录会新建的文件夹与文件
+            // Record what the copy will create up front so a partial copy can be undone too
+            let (mut dirs, mut files) = (Vec::new(), Vec::new());
+            copy_targets(src, dst, &mut dirs, &mut files);
+            for dir in dirs.into_iter().filter(|d| !occupied(d)) {
+                journal.undo.push(Undo::RemoveEmptyDir(dir));
+            }
+            if options.conflict == Conflict::Overwrite && dst != src {
+                for file in files.iter().filter(|f| f.is_file()) {
+                    journal.stage(file)?;
+                }
+            }
+            for file in files.into_iter().filter(|f| !occupied(f)) {
+                journal.undo.push(Undo::Remove(file));
+            }
+            let report = copy(src, dst, options)?;
+            Ok(PathBuf::from(report.path))
+        }
+        FsOp::Delete { .. } => {
+            journal.stage(src)?;
+            Ok(src.to_path_buf())
+        }
+    }
+}
+
+/// run
+/// 校验并执行一批操作；`sandbox` 检查路径（或其最近的已存在上级）是否在工作区内
+pub fn run(ops: &[FsOp], sandbox: &dyn Fn(&Path) -> Result<(), String>) -> BatchReport {
+    let mut reports: Vec<OpReport> = ops
+        .iter()
+        .enumerate()
+        .map(|(index, op)| OpReport {
+            index,
+            op: op.name(),
+            status: OpStatus::Skipped,
+            path: None,
+            error: None,
+        })
+        .collect();
+
+    let mut plan = Plan::default();
+    let mut resolved = Vec::with_capacity(ops.len());
+    for (op, report) in ops.iter().zip(reports.iter_mut()) {
+        match validate(op, &mut plan, sandbox) {
+            Ok(paths) => resolved.push(paths),
+            Err(e) => {
+                report.status = OpStatus::Invalid;
+                report.error = Some(e);
+            }
+        }
+    }
+    if resolved.len() != ops.len() {
+        return BatchReport {
+            ok: false,
+            ops: reports,
+            rollback_errors: Vec::new(),
+        };
+    }
+
+    let mut journal = Journal::default();
+    let mut failed = false;
+    for ((op, (src, dst)), report) in ops.iter().zip(&resolved).zip(reports.iter_mut()) {
+        match apply(op, src, dst.as_deref(), &mut journal) {
+            Ok(path) => {
+                report.status = OpStatus::Applied;
+                report.path = Some(display(&path));
+            }
+            Err(e) => {
+                log::warn!("Batch {} #{} failed: {:?}", op.name(), report.index, e);
+                report.status = OpStatus::Failed;
+                report.error = Some(e.to_string());
+                failed = true;
+                break;
+            }
+        }
+    }
+
+    let mut rollback_errors = Vec::new();
+    if failed {
+        for step in journal.undo.iter().rev() {
+            if let Err(e) = step.run() {
+                log::error!("Batch rollback step failed: {}", e);
+                rollback_errors.push(e.to_string());
+            }
+        }
+        for report in reports.iter_mut() {
+            if report.status == OpStatus::Applied {
+                report.status = OpStatus::RolledBack;
+            }
+        }
+    } else {
+        for staged in &journal.staged {
+            let result = if staged.is_dir() {
+                std::fs::remove_dir_all(staged)
+            } else {
+                std::fs::remove_file(staged)
+            };
+            if let Err(e) = result {
+                log::warn!("Failed to purge staged {:?}: {}", staged, e);
+            }
+        }
+    }
+
+    BatchReport {
+        ok: !failed,
+        ops: reports,
+        rollback_errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mm-batch-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn path(dir: &Path, rel: &str) -> String {
+        display(&dir.join(rel))
+    }
+
+    fn allow(_: &Path) -> Result<(), String> {
+        Ok(())
+    }
+
+    #[test]
+    fn plan_follows_earlier_operations() {
+        let dir = temp_dir("plan");
+        std::fs::write(dir.join("a.md"), "a").unwrap();
+        let mut plan = Plan::default();
+        let ops = [
+            FsOp::Create {
+                path: path(&dir, "docs"),
+                content: None,
+                dir: true,
+            },
+            FsOp::Rename {
+                src: path(&dir, "a.md"),
+                dst: path(&dir, "docs/b.md"),
+            },
+            FsOp::Create {
+                path: path(&dir, "a.md"),
+                content: None,
+                dir: false,
+            },
+        ];
+        for op in &ops {
+            validate(op, &mut plan, &allow).unwrap();
+        }
+        assert!(plan.is_dir(&dir.join("docs")));
+        assert!(plan.exists(&dir.join("docs/b.md")));
+        assert!(!plan.is_dir(&dir.join("docs/b.md")));
+        assert!(plan.exists(&dir.join("a.md")));
+
+        // 已移走的源与已占用的目标 / A moved-away source and a taken target
+        let moved = FsOp::Rename {
+            src: path(&dir, "docs/b.md"),
+            dst: path(&dir, "a.md"),
+        };
+        assert!(validate(&moved, &mut plan, &allow).is_err());
+        let delete = FsOp::Delete {
+            path: path(&dir, "missing.md"),
+        };
+        assert!(validate(&delete, &mut plan, &allow).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn plan_auto_renames_against_planned_copies() {
+        let dir = temp_dir("rename");
+        std::fs::write(dir.join("a.md"), "a").unwrap();
+        let mut plan = Plan::default();
+        let copy = FsOp::Copy {
+            src: path(&dir, "a.md"),
+            dst: path(&dir, "a.md"),
+            options: CopyOptions::default(),
+        };
+        let (_, first) = validate(&copy, &mut plan, &allow).unwrap();
+        let (_, second) = validate(&copy, &mut plan, &allow).unwrap();
+        assert_eq!(first, Some(dir.join("a-1.md")));
+        assert_eq!(second, Some(dir.join("a-2.md")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sandbox_rejects_the_whole_batch() {
+        let dir = temp_dir("sandbox");
+        let ops = [
+            FsOp::Create {
+                path: path(&dir, "ok.md"),
+                content: None,
+                dir: false,
+            },
+            FsOp::Create {
+                path: path(&dir, "outside.md"),
+                content: None,
+                dir: false,
+            },
+        ];
+        let report = run(&ops, &|p| {
+            if p.ends_with("outside.md") {
+                Err("outside".to_string())
+            } else {
+                Ok(())
+            }
+        });
+        assert!(!report.ok);
+        assert_eq!(report.ops[0].status, OpStatus::Skipped);
+        assert_eq!(report.ops[1].status, OpStatus::Invalid);
+        assert!(!dir.join("ok.md").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failure_rolls_back_applied_steps() {
+        let dir = temp_dir("rollback");
+        std::fs::write(dir.join("keep.md"), "keep").unwrap();
+        std::fs::write(dir.join("x.md"), "x").unwrap();
+        std::fs::write(dir.join("blocker"), "").unwrap();
+        let ops = [
+            FsOp::Create {
+                path: path(&dir, "new/note.md"),
+                content: Some("new".to_string()),
+                dir: false,
+            },
+            FsOp::Delete {
+                path: path(&dir, "keep.md"),
+            },
+            FsOp::Rename {
+                src: path(&dir, "x.md"),
+                dst: path(&dir, "y.md"),
+            },
+            // 上级是文件，通过校验但执行失败 / Passes validation, fails because the parent is a file
+            FsOp::Create {
+                path: path(&dir, "blocker/child.md"),
+                content: None,
+                dir: false,
+            },
+        ];
+        let report = run(&ops, &allow);
+        assert!(!report.ok);
+        let statuses: Vec<_> = report.ops.iter().map(|o| o.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                OpStatus::RolledBack,
+                OpStatus::RolledBack,
+                OpStatus::RolledBack,
+                OpStatus::Failed
+            ]
+        );
+        assert!(report.rollback_errors.is_empty());
+        assert!(!dir.join("new").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("keep.md")).unwrap(),
+            "keep"
+        );
+        assert!(dir.join("x.md").exists() && !dir.join("y.md").exists());
+        let names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names.len(), 3, "{:?}", names);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn success_purges_staged_files() {
+        let dir = temp_dir("purge");
+        std::fs::write(dir.join("old.md"), "old").unwrap();
+        let ops = [FsOp::Delete {
+            path: path(&dir, "old.md"),
+        }];
+        let report = run(&ops, &allow);
+        assert!(report.ok);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
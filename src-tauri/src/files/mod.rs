@@ -0,0 +1,261 @@
+//! 文件操作 / File operations
+//!
+//! 工作区内复制文件与文件夹：目标已存在时可跳过、覆盖或自动改名；可选地改写复制后文档中的相对链接，
+//! 使指向原目录之外的图片与文档在新位置仍然有效。
+//! Copies files and folders with conflict handling and optional relative link rewriting.
+
+pub mod batch;
+
+use crate::assets::{normalize_path, relative_link};
+use crate::error::AppError;
+use crate::git::display;
+use crate::markdown;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// 目标已存在时的处理方式 / What to do when the target already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Conflict {
+    /// 跳过已存在的文件 / Keep the existing file
+    Skip,
+    /// 覆盖已存在的文件 / Replace the existing file
+    Overwrite,
+    /// 换用不冲突的名称，如 `name-1.md` / Pick a free name such as `name-1.md`
+    #[default]
+    AutoRename,
+}
+
+/// CopyOptions
+/// 复制选项
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct CopyOptions {
+    /// 缺省为自动改名 / Defaults to auto-rename
+    #[serde(default)]
+    pub conflict: Conflict,
+    /// 改写复制后文档中的相对链接 / Rewrite relative links in copied documents
+    #[serde(default)]
+    pub rewrite_links: bool,
+}
+
+/// CopyReport
+/// 复制结果
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct CopyReport {
+    /// 实际的目标路径（自动改名后可能与请求不同） / Actual target, after any auto-rename
+    pub path: String,
+    /// 复制的文件 / Files written
+    pub copied: Vec<String>,
+    /// 跳过的目标：已存在，或源/目标为符号链接 / Targets skipped: existing, or a symlink on either end
+    pub skipped: Vec<String>,
+    /// 改写的链接数 / Links rewritten
+    pub links_rewritten: usize,
+}
+
+/// 路径上是否已有条目（不跟随符号链接，失效的链接也算） / Whether anything, even a dangling symlink, is there
+pub fn occupied(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok()
+}
+
+/// 是否为符号链接 / Whether the path itself is a symlink
+pub fn is_symlink(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+}
+
+/// 不冲突的路径：`name-1.ext`、`name-2.ext`…（文件夹不区分扩展名）
+/// A free sibling path; folders keep dots as part of the name
+pub fn available_path(path: &Path, is_dir: bool) -> PathBuf {
+    available_path_by(path, is_dir, &occupied)
+}
+
+/// 按给定的占用判断求不冲突的路径 / `available_path` with a custom occupancy check
+pub fn available_path_by(path: &Path, is_dir: bool, taken: &dyn Fn(&Path) -> bool) -> PathBuf {
+    if !taken(path) {
+        return path.to_path_buf();
+    }
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (stem, ext) = match (is_dir, name.rsplit_once('.')) {
+        (false, Some((stem, ext))) if !stem.is_empty() => (stem.to_string(), format!(".{}", ext)),
+        _ => (name, String::new()),
+    };
+    let mut n = 1;
+    loop {
+        let candidate = dir.join(format!("{}-{}{}", stem, n, ext));
+        if !taken(&candidate) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// copy
+/// 复制文件或文件夹（递归）。文件夹与已有文件夹合并，冲突按文件逐个处理；自动改名只作用于顶层目标
+pub fn copy(src: &Path, dst: &Path, options: &CopyOptions) -> Result<CopyReport, AppError> {
+    if !occupied(src) {
+        return Err(AppError::InvalidInput(format!(
+            "源路径不存在: {}",
+            display(src)
+        )));
+    }
+    // 只规范化上级目录，路径本身是符号链接时不解析 / Only parents are resolved, never the entry itself
+    let resolve = |path: &Path| -> Result<PathBuf, AppError> {
+        Ok(match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) if parent.exists() => {
+                std::fs::canonicalize(parent)?.join(name)
+            }
+            _ => normalize_path(path),
+        })
+    };
+    let src = resolve(src)?;
+    let dst = resolve(dst)?;
+    let is_dir = src.is_dir();
+    if is_dir && dst.starts_with(&src) && dst != src {
+        return Err(AppError::InvalidInput(
+            "不能将文件夹复制到其自身内部".to_string(),
+        ));
+    }
+
+    let dst = match options.conflict {
+        Conflict::AutoRename => available_path(&dst, is_dir),
+        _ if dst == src => {
+            return Err(AppError::InvalidInput("源路径与目标路径相同".to_string()));
+        }
+        _ => dst,
+    };
+    if dst.exists() && dst.is_dir() != is_dir && options.conflict == Conflict::Overwrite {
+        return Err(AppError::InvalidInput(format!(
+            "目标已存在且类型不同: {}",
+            display(&dst)
+        )));
+    }
+
+    let mut report = CopyReport {
+        path: display(&dst),
+        ..Default::default()
+    };
+    let mut documents = Vec::new();
+    copy_entry(&src, &dst, options.conflict, &mut report, &mut documents)?;
+
+    if options.rewrite_links {
+        for (from, to) in documents {
+            match rewrite_links(&src, &dst, &from, &to) {
+                Ok(n) => report.links_rewritten += n,
+                Err(e) => log::warn!("Failed to rewrite links in {:?}: {}", to, e),
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn copy_entry(
+    src: &Path,
+    dst: &Path,
+    conflict: Conflict,
+    report: &mut CopyReport,
+    documents: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<(), AppError> {
+    // 源中的符号链接可能指向工作区之外，目标中的符号链接会被写穿，两者都不处理
+    // A source link may point outside the workspace and a destination link would be
+    // written through, so neither is followed
+    if is_symlink(src) || is_symlink(dst) {
+        log::warn!("Skipping symlink while copying {:?} -> {:?}", src, dst);
+        report.skipped.push(display(dst));
+        return Ok(());
+    }
+
+    if src.is_dir() {
+        if dst.is_file() {
+            // 跳过模式下保留同名文件；覆盖模式已在顶层检查类型
+            // Only reachable when skipping; overwrite checks types up front
+            report.skipped.push(display(dst));
+            return Ok(());
+        }
+        std::fs::create_dir_all(dst)?;
+        for entry in std::fs::read_dir(src)?.flatten() {
+            copy_entry(
+                &entry.path(),
+                &dst.join(entry.file_name()),
+                conflict,
+                report,
+                documents,
+            )?;
+        }
+        return Ok(());
+    }
+
+    if dst.exists() && (conflict == Conflict::Skip || dst.is_dir()) {
+        report.skipped.push(display(dst));
+        return Ok(());
+    }
+    std::fs::copy(src, dst)?;
+    report.copied.push(display(dst));
+    if crate::export::is_markdown(src) {
+        documents.push((src.to_path_buf(), dst.to_path_buf()));
+    }
+    Ok(())
+}
+
+/// 改写复制后文档中的相对链接：指向复制范围内的保持相对结构，指向范围外的重新计算
+/// Rewrites links in a copied document: targets inside the copied tree follow it, others are re-based
+fn rewrite_links(
+    src_root: &Path,
+    dst_root: &Path,
+    from: &Path,
+    to: &Path,
+) -> Result<usize, AppError> {
+    crate::check_file_size(to).map_err(AppError::InvalidInput)?;
+    let text = std::fs::read_to_string(to)?;
+    let (Some(from_dir), Some(to_dir)) = (from.parent(), to.parent()) else {
+        return Ok(0);
+    };
+
+    let mut urls: Vec<(String, Range<usize>)> = markdown::references(&text)
+        .into_iter()
+        .map(|r| (r.url, r.span))
+        .collect();
+    urls.extend(markdown::reference_definitions(&text));
+
+    let mut links: HashMap<&str, String> = HashMap::new();
+    let mut spans = Vec::new();
+    for (url, span) in &urls {
+        let trimmed = url.trim();
+        if trimmed.starts_with('/') || Path::new(trimmed).is_absolute() {
+            continue;
+        }
+        let Some(target) = markdown::resolve_local(from_dir, trimmed) else {
+            continue;
+        };
+        let target = normalize_path(&target);
+        let moved = match target.strip_prefix(src_root) {
+            Ok(rel) if rel.as_os_str().is_empty() => dst_root.to_path_buf(),
+            Ok(rel) => dst_root.join(rel),
+            Err(_) => target,
+        };
+        // 在新位置仍指向同一文件则无需改写 / Unchanged when it already resolves correctly
+        if markdown::resolve_local(to_dir, trimmed).map(|p| normalize_path(&p))
+            == Some(moved.clone())
+        {
+            continue;
+        }
+        let suffix = trimmed.find(['?', '#']).map_or("", |i| &trimmed[i..]);
+        let link = relative_link(to_dir, &moved);
+        links.insert(trimmed, format!("{}{}", link, suffix));
+        spans.push(span.clone());
+    }
+    if links.is_empty() {
+        return Ok(0);
+    }
+
+    spans.sort_by_key(|s| s.start);
+    spans.dedup();
+    let (output, rewritten) = markdown::rewrite_urls(&text, &spans, &links);
+    if rewritten > 0 {
+        std::fs::write(to, output)?;
+    }
+    Ok(rewritten)
+}
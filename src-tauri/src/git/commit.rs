@@ -0,0 +1,132 @@
+//! 暂存与提交 / Staging and committing
+//!
+//! 通过 libgit2 直接操作索引，无需调用 git 命令行。
+//! Operates on the index through libgit2; no git executable required.
+
+use super::{relative_path, require};
+use crate::error::AppError;
+use git2::{IndexAddOption, Repository};
+use std::path::Path;
+
+/// 提交结果 / Created commit
+#[derive(Debug, serde::Serialize)]
+pub struct CommitInfo {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+}
+
+fn same_repo(paths: &[&Path]) -> Result<Repository, AppError> {
+    let first = paths
+        .first()
+        .ok_or_else(|| AppError::InvalidInput("未指定文件".to_string()))?;
+    require(first)
+}
+
+/// stage
+/// 将文件加入暂存区；已删除的文件记录为删除
+pub fn stage(paths: &[&Path]) -> Result<(), AppError> {
+    let repo = same_repo(paths)?;
+    let mut index = repo.index()?;
+    for path in paths {
+        let rel = relative_path(&repo, path)?;
+        if path.is_dir() {
+            index.add_all([&rel], IndexAddOption::DEFAULT, None)?;
+            index.update_all([&rel], None)?;
+        } else if path.exists() {
+            index.add_path(&rel)?;
+        } else {
+            index.remove_path(&rel)?;
+        }
+    }
+    index.write()?;
+    Ok(())
+}
+
+/// unstage
+/// 将文件从暂存区恢复为 HEAD 中的状态，不改动工作区
+pub fn unstage(paths: &[&Path]) -> Result<(), AppError> {
+    let repo = same_repo(paths)?;
+    let rels = paths
+        .iter()
+        .map(|p| relative_path(&repo, p))
+        .collect::<Result<Vec<_>, _>>()?;
+    match repo.head().ok().and_then(|h| h.peel_to_commit().ok()) {
+        Some(head) => repo.reset_default(Some(head.as_object()), &rels)?,
+        // 首次提交前 HEAD 不存在，直接从索引移除 / No HEAD yet: drop the entries
+        None => {
+            let mut index = repo.index()?;
+            for rel in &rels {
+                if index.get_path(rel, 0).is_some() {
+                    index.remove_path(rel)?;
+                } else {
+                    index.remove_dir(rel, 0)?;
+                }
+            }
+            index.write()?;
+        }
+    }
+    Ok(())
+}
+
+/// commit
+/// 以暂存区内容创建提交；没有已暂存的更改、存在冲突或暂存了工作区之外的路径时报错
+pub fn commit(dir: &Path, message: &str) -> Result<CommitInfo, AppError> {
+    let message = message.trim();
+    if message.is_empty() {
+        return Err(AppError::InvalidInput("提交说明不能为空".to_string()));
+    }
+    let repo = require(dir)?;
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        return Err(AppError::Git("存在未解决的冲突".to_string()));
+    }
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    // 只提交工作区内的更改：暂存区包含工作区之外的路径时拒绝，避免一并提交
+    // Commits stay inside the workspace; refuse when paths outside it are staged
+    let prefix = relative_path(&repo, dir)?;
+    let head_tree = parent.as_ref().map(|p| p.tree()).transpose()?;
+    let staged = repo.diff_tree_to_index(head_tree.as_ref(), Some(&index), None)?;
+    let outside = staged
+        .deltas()
+        .flat_map(|d| [d.old_file().path(), d.new_file().path()])
+        .flatten()
+        .find(|p| !p.starts_with(&prefix));
+    if let Some(path) = outside {
+        return Err(AppError::Git(format!(
+            "暂存区包含工作区之外的更改: {}，请先取消暂存",
+            path.to_string_lossy()
+        )));
+    }
+    let tree_id = index.write_tree()?;
+    let tree = repo.find_tree(tree_id)?;
+    if parent.as_ref().is_some_and(|p| p.tree_id() == tree_id)
+        || parent.is_none() && tree.is_empty()
+    {
+        return Err(AppError::Git("没有已暂存的更改".to_string()));
+    }
+    let signature = repo
+        .signature()
+        .map_err(|_| AppError::Git("请先配置 user.name 与 user.email".to_string()))?;
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    let id = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &format!("{}\n", message),
+        &tree,
+        &parents,
+    )?;
+    let commit = repo.find_commit(id)?;
+    let short_id = commit
+        .as_object()
+        .short_id()
+        .ok()
+        .and_then(|b| b.as_str().map(str::to_string))
+        .unwrap_or_else(|| id.to_string()[..7].to_string());
+    Ok(CommitInfo {
+        id: id.to_string(),
+        short_id,
+        summary: commit.summary().unwrap_or_default().to_string(),
+    })
+}
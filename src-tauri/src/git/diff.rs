@@ -0,0 +1,281 @@
+//! 差异对比 / Diff
+//!
+//! 工作区文件与 HEAD 中版本的差异，输出统一格式文本或左右对照的行列表。
+//! Working copy versus HEAD, as unified text or side-by-side rows.
+
+use super::{relative_path, require};
+use crate::error::AppError;
+use git2::{DiffOptions, Patch, Repository};
+use std::path::Path;
+
+/// 默认上下文行数 / Default number of context lines
+const DEFAULT_CONTEXT: u32 = 3;
+
+/// DiffRequest
+/// 差异选项
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct DiffRequest {
+    /// `unified`（缺省）或 `split`（左右对照）
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// 上下文行数，缺省为 3
+    #[serde(default)]
+    pub context: Option<u32>,
+}
+
+/// 对照视图中的一侧 / One side of a side-by-side row
+#[derive(Debug, serde::Serialize)]
+pub struct DiffCell {
+    /// 行号（从 1 开始） / 1-based line number
+    pub line: u32,
+    pub text: String,
+}
+
+/// 对照视图中的一行 / A side-by-side row
+#[derive(Debug, serde::Serialize)]
+pub struct DiffRow {
+    /// `hunk` / `context` / `added` / `removed` / `changed`
+    pub kind: &'static str,
+    pub left: Option<DiffCell>,
+    pub right: Option<DiffCell>,
+    /// 仅 `hunk` 行：区块标题 / Hunk header, `hunk` rows only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+}
+
+/// FileDiff
+/// 单个文件的差异
+#[derive(Debug, serde::Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    pub additions: usize,
+    pub deletions: usize,
+    /// 任一版本为二进制时不输出内容 / No content when either side is binary
+    pub binary: bool,
+    /// `unified` 模式：统一格式文本
+    pub unified: Option<String>,
+    /// `split` 模式：左右对照行
+    pub rows: Option<Vec<DiffRow>>,
+}
+
+/// HEAD 中的文件内容；新文件或尚无提交时为空 / File contents at HEAD, if any
+fn head_contents(repo: &Repository, rel: &Path) -> Result<Vec<u8>, AppError> {
+    let Some(tree) = repo.head().ok().and_then(|h| h.peel_to_tree().ok()) else {
+        return Ok(Vec::new());
+    };
+    let entry = match tree.get_path(rel) {
+        Ok(entry) => entry,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let blob = entry.to_object(repo)?.peel_to_blob()?;
+    Ok(blob.content().to_vec())
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(8000)].contains(&0)
+}
+
+fn line_text(content: &[u8]) -> String {
+    String::from_utf8_lossy(content)
+        .trim_end_matches(['\n', '\r'])
+        .to_string()
+}
+
+/// 把连续的删除与新增配对成左右对照的行 / Pairs runs of removals and additions
+fn flush(removed: &mut Vec<DiffCell>, added: &mut Vec<DiffCell>, rows: &mut Vec<DiffRow>) {
+    let mut left = removed.drain(..);
+    let mut right = added.drain(..);
+    loop {
+        let (l, r) = (left.next(), right.next());
+        let kind = match (&l, &r) {
+            (None, None) => break,
+            (Some(_), Some(_)) => "changed",
+            (Some(_), None) => "removed",
+            (None, Some(_)) => "added",
+        };
+        rows.push(DiffRow {
+            kind,
+            left: l,
+            right: r,
+            header: None,
+        });
+    }
+}
+
+fn split_rows(patch: &Patch) -> Result<Vec<DiffRow>, AppError> {
+    let mut rows = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    for h in 0..patch.num_hunks() {
+        let (hunk, count) = patch.hunk(h)?;
+        rows.push(DiffRow {
+            kind: "hunk",
+            left: None,
+            right: None,
+            header: Some(line_text(hunk.header())),
+        });
+        for l in 0..count {
+            let line = patch.line_in_hunk(h, l)?;
+            let text = line_text(line.content());
+            match line.origin() {
+                '-' => removed.push(DiffCell {
+                    line: line.old_lineno().unwrap_or(0),
+                    text,
+                }),
+                '+' => added.push(DiffCell {
+                    line: line.new_lineno().unwrap_or(0),
+                    text,
+                }),
+                ' ' => {
+                    flush(&mut removed, &mut added, &mut rows);
+                    rows.push(DiffRow {
+                        kind: "context",
+                        left: Some(DiffCell {
+                            line: line.old_lineno().unwrap_or(0),
+                            text: text.clone(),
+                        }),
+                        right: Some(DiffCell {
+                            line: line.new_lineno().unwrap_or(0),
+                            text,
+                        }),
+                        header: None,
+                    });
+                }
+                // 行尾换行符提示等 / "\ No newline at end of file" markers
+                _ => {}
+            }
+        }
+        flush(&mut removed, &mut added, &mut rows);
+    }
+    Ok(rows)
+}
+
+/// diff_file
+/// 对比工作区文件与 HEAD 中的版本
+pub fn diff_file(path: &Path, request: &DiffRequest) -> Result<FileDiff, AppError> {
+    let repo = require(path)?;
+    let rel = relative_path(&repo, path)?;
+    let old = head_contents(&repo, &rel)?;
+    let new = if path.is_file() {
+        crate::check_file_size(path).map_err(AppError::InvalidInput)?;
+        std::fs::read(path)?
+    } else {
+        Vec::new()
+    };
+    let display = super::display(path);
+    if is_binary(&old) || is_binary(&new) {
+        return Ok(FileDiff {
+            path: display,
+            additions: 0,
+            deletions: 0,
+            binary: true,
+            unified: None,
+            rows: None,
+        });
+    }
+
+    let mut options = DiffOptions::new();
+    options.context_lines(request.context.unwrap_or(DEFAULT_CONTEXT));
+    let name = super::display(&rel);
+    let mut patch = Patch::from_buffers(
+        &old,
+        Some(Path::new(&name)),
+        &new,
+        Some(Path::new(&name)),
+        Some(&mut options),
+    )?;
+    let (_, additions, deletions) = patch.line_stats()?;
+    let split = request.mode.as_deref() == Some("split");
+    let (unified, rows) = if split {
+        (None, Some(split_rows(&patch)?))
+    } else {
+        let buf = patch.to_buf()?;
+        (Some(String::from_utf8_lossy(&buf).to_string()), None)
+    };
+    Ok(FileDiff {
+        path: display,
+        additions,
+        deletions,
+        binary: false,
+        unified,
+        rows,
+    })
+}
+
+/// 已暂存的单个文件补丁，按区块拆分 / A staged patch split into hunks
+#[derive(Debug)]
+pub struct StagedPatch {
+    /// 相对仓库的路径 / Path relative to the repository
+    pub path: String,
+    /// `added` / `modified` / `deleted` / `renamed`
+    pub change: &'static str,
+    pub additions: usize,
+    pub deletions: usize,
+    /// 每个区块的文本（含 `@@` 标题） / Hunk texts including their `@@` header
+    pub hunks: Vec<String>,
+}
+
+/// staged_markdown_patches
+/// 暂存区相对 HEAD 的 Markdown 文件差异，限于工作区内
+pub fn staged_markdown_patches(dir: &Path, context: u32) -> Result<Vec<StagedPatch>, AppError> {
+    let repo = require(dir)?;
+    let head = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+    let mut options = DiffOptions::new();
+    options.context_lines(context);
+    // 只取工作区内的更改，工作区可能是仓库的子目录
+    // Limited to the workspace, which may be a subfolder of the repository
+    let scope = relative_path(&repo, dir)?;
+    if !scope.as_os_str().is_empty() {
+        options.pathspec(super::display(&scope));
+    }
+    let mut diff = repo.diff_tree_to_index(head.as_ref(), None, Some(&mut options))?;
+    diff.find_similar(None)?;
+
+    let mut out = Vec::new();
+    for i in 0..diff.deltas().len() {
+        let Some(delta) = diff.get_delta(i) else {
+            continue;
+        };
+        let Some(file) = delta.new_file().path().or(delta.old_file().path()) else {
+            continue;
+        };
+        if !crate::export::is_markdown(file) || delta.flags().is_binary() {
+            continue;
+        }
+        let change = match delta.status() {
+            git2::Delta::Added => "added",
+            git2::Delta::Deleted => "deleted",
+            git2::Delta::Renamed => "renamed",
+            _ => "modified",
+        };
+        let path = super::display(file);
+        let Some(patch) = Patch::from_diff(&diff, i)? else {
+            continue;
+        };
+        let (_, additions, deletions) = patch.line_stats()?;
+        let mut hunks = Vec::with_capacity(patch.num_hunks());
+        for h in 0..patch.num_hunks() {
+            let (hunk, count) = patch.hunk(h)?;
+            let mut text = line_text(hunk.header());
+            text.push('\n');
+            for l in 0..count {
+                let line = patch.line_in_hunk(h, l)?;
+                if matches!(line.origin(), ' ' | '+' | '-') {
+                    text.push(line.origin());
+                    text.push_str(&line_text(line.content()));
+                    text.push('\n');
+                }
+            }
+            hunks.push(text);
+        }
+        out.push(StagedPatch {
+            path,
+            change,
+            additions,
+            deletions,
+            hunks,
+        });
+    }
+    Ok(out)
+}
@@ -0,0 +1,202 @@
+//! 文件历史与逐行追溯 / File history and blame
+//!
+//! 列出修改过文档的提交（跟随重命名）、读取历史版本内容，以及逐行显示作者。
+//! Lists commits touching a document (following renames), reads old versions and blames lines.
+
+use super::{relative_path, require};
+use crate::error::AppError;
+use git2::{BlameOptions, Commit, DiffFindOptions, Oid, Repository, Sort};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 默认最多返回的提交数 / Default maximum number of commits
+const DEFAULT_LIMIT: usize = 200;
+
+/// 提交信息 / Commit metadata
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CommitEntry {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub message: String,
+    pub author: String,
+    pub email: String,
+    /// 提交时间（Unix 秒） / Commit time in Unix seconds
+    pub time: i64,
+    /// 该提交中文件的相对路径（重命名前可能不同） / Path in this commit, may differ before a rename
+    pub path: String,
+}
+
+/// 逐行追溯的区块 / A run of lines attributed to the same commit
+#[derive(Debug, serde::Serialize)]
+pub struct BlameHunk {
+    /// 起始行（从 1 开始） / First line, 1-based
+    pub start_line: usize,
+    pub lines: usize,
+    /// 尚未提交的行为 `None` / `None` for uncommitted lines
+    pub commit: Option<CommitEntry>,
+}
+
+fn entry(commit: &Commit, path: &Path) -> CommitEntry {
+    let author = commit.author();
+    let id = commit.id().to_string();
+    CommitEntry {
+        short_id: id[..7.min(id.len())].to_string(),
+        id,
+        summary: commit.summary().unwrap_or_default().to_string(),
+        message: commit.message().unwrap_or_default().trim_end().to_string(),
+        author: author.name().unwrap_or_default().to_string(),
+        email: author.email().unwrap_or_default().to_string(),
+        time: commit.time().seconds(),
+        path: super::display(path),
+    }
+}
+
+fn blob_id(commit: &Commit, path: &Path) -> Option<Oid> {
+    commit.tree().ok()?.get_path(path).ok().map(|e| e.id())
+}
+
+/// 文件在父提交中不存在时，查找是否由其他路径重命名而来
+/// When the file is new relative to `parent`, finds the path it was renamed from
+fn renamed_from(
+    repo: &Repository,
+    parent: &Commit,
+    commit: &Commit,
+    path: &Path,
+) -> Result<Option<PathBuf>, AppError> {
+    let mut diff = repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&commit.tree()?), None)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    Ok(diff
+        .deltas()
+        .find(|d| d.status() == git2::Delta::Renamed && d.new_file().path() == Some(path))
+        .and_then(|d| d.old_file().path().map(Path::to_path_buf)))
+}
+
+/// 沿历史逐个访问修改过该文件的提交（跟随重命名），`visit` 返回 `false` 时停止
+/// Visits commits touching the file newest first, following renames, until `visit` returns `false`
+fn walk_history(
+    repo: &Repository,
+    mut current: PathBuf,
+    mut visit: impl FnMut(&Commit, &Path) -> Result<bool, AppError>,
+) -> Result<(), AppError> {
+    let mut walk = repo.revwalk()?;
+    if walk.push_head().is_err() {
+        // 尚无提交 / No commits yet
+        return Ok(());
+    }
+    walk.set_sorting(Sort::TIME | Sort::TOPOLOGICAL)?;
+
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        let Some(id) = blob_id(&commit, &current) else {
+            continue;
+        };
+        let parents: Vec<Commit> = commit.parents().collect();
+        // 与任一父提交相同则视为未修改（同 git log 的简化规则）
+        // Unchanged relative to any parent counts as untouched, like `git log`
+        if parents.iter().any(|p| blob_id(p, &current) == Some(id)) {
+            continue;
+        }
+        if !visit(&commit, &current)? {
+            break;
+        }
+        if let [parent] = parents.as_slice() {
+            if blob_id(parent, &current).is_none() {
+                if let Some(old) = renamed_from(repo, parent, &commit, &current)? {
+                    current = old;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// file_history
+/// 按时间倒序列出修改过该文件的提交
+pub fn file_history(path: &Path, limit: Option<usize>) -> Result<Vec<CommitEntry>, AppError> {
+    let repo = require(path)?;
+    let current = relative_path(&repo, path)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(1);
+    let mut out = Vec::new();
+    walk_history(&repo, current, |commit, path| {
+        out.push(entry(commit, path));
+        Ok(out.len() < limit)
+    })?;
+    Ok(out)
+}
+
+/// read_at_revision
+/// 读取文件在指定版本（提交 ID、分支名、`HEAD~2` 等）中的内容
+pub fn read_at_revision(path: &Path, rev: &str) -> Result<String, AppError> {
+    let repo = require(path)?;
+    let rel = relative_path(&repo, path)?;
+    let commit = repo
+        .revparse_single(rev.trim())
+        .and_then(|o| o.peel_to_commit())
+        .map_err(|_| AppError::Git(format!("找不到版本 {}", rev)))?;
+    let tree = commit.tree()?;
+    let entry = match tree.get_path(&rel) {
+        Ok(entry) => entry,
+        // 文件后来被重命名：取该版本及之前最近一次修改时的路径
+        // Renamed later: use the path from the nearest history entry at or before `rev`
+        Err(_) => {
+            // 不限条数，一直回溯到该版本为止 / No limit: walk back as far as `rev`
+            let mut old_path = None;
+            walk_history(&repo, rel, |e, p| {
+                let reached =
+                    e.id() == commit.id() || repo.graph_descendant_of(commit.id(), e.id())?;
+                if reached {
+                    old_path = Some(p.to_path_buf());
+                }
+                Ok(!reached)
+            })?;
+            old_path
+                .and_then(|p| tree.get_path(&p).ok())
+                .ok_or_else(|| AppError::Git("该版本中不存在此文件".to_string()))?
+        }
+    };
+    let blob = entry.to_object(&repo)?.peel_to_blob()?;
+    if blob.size() as u64 > crate::MAX_FILE_SIZE {
+        return Err(AppError::InvalidInput("文件过大".to_string()));
+    }
+    Ok(String::from_utf8_lossy(blob.content()).to_string())
+}
+
+/// blame
+/// 逐行追溯当前内容（含未提交的修改）的作者
+pub fn blame(path: &Path) -> Result<Vec<BlameHunk>, AppError> {
+    let repo = require(path)?;
+    let rel = relative_path(&repo, path)?;
+    if repo.head().is_err() {
+        return Err(AppError::Git("仓库尚无提交".to_string()));
+    }
+    let mut options = BlameOptions::new();
+    options.track_copies_same_file(true);
+    let committed = repo.blame_file(&rel, Some(&mut options))?;
+    // 基于工作区内容追溯，未提交的行归为零 ID / Blame the working copy; new lines get a zero id
+    crate::check_file_size(path).map_err(AppError::InvalidInput)?;
+    let content = std::fs::read(path)?;
+    let blame = committed.blame_buffer(&content)?;
+
+    let mut commits: HashMap<Oid, Option<CommitEntry>> = HashMap::new();
+    let mut out = Vec::with_capacity(blame.len());
+    for hunk in blame.iter() {
+        let id = hunk.final_commit_id();
+        let commit = commits
+            .entry(id)
+            .or_insert_with(|| {
+                if id.is_zero() {
+                    return None;
+                }
+                let hunk_path = hunk.path().unwrap_or(&rel);
+                repo.find_commit(id).ok().map(|c| entry(&c, hunk_path))
+            })
+            .clone();
+        out.push(BlameHunk {
+            start_line: hunk.final_start_line(),
+            lines: hunk.lines_in_hunk(),
+            commit,
+        });
+    }
+    Ok(out)
+}
@@ -0,0 +1,167 @@
+//! Git 集成 / Git integration
+//!
+//! 工作区通常是 Git 仓库：查询文件状态（修改、暂存、未跟踪、冲突），供侧边栏显示标记。
+//! Workspaces are usually git repositories; reports per-file status for sidebar badges.
+
+pub mod commit;
+pub mod diff;
+pub mod history;
+pub mod summary;
+
+use crate::error::AppError;
+use git2::{Repository, Status, StatusOptions};
+use std::path::{Path, PathBuf};
+
+/// 单个文件的状态 / Status of one file
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FileStatus {
+    /// 绝对路径（`/` 分隔） / Absolute path with `/` separators
+    pub path: String,
+    /// 暂存区与 HEAD 不同 / Index differs from HEAD
+    pub staged: bool,
+    /// 工作区与暂存区不同 / Working tree differs from the index
+    pub modified: bool,
+    pub untracked: bool,
+    pub conflicted: bool,
+    pub deleted: bool,
+}
+
+/// RepoStatus
+/// 仓库状态
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RepoStatus {
+    /// 仓库工作目录 / Repository working directory
+    pub root: String,
+    /// 当前分支，分离 HEAD 时为空 / Current branch; empty when HEAD is detached
+    pub branch: Option<String>,
+    pub files: Vec<FileStatus>,
+}
+
+pub(crate) fn display(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// 查找包含该路径的仓库；不在仓库内时返回 `None`
+/// Finds the repository containing `path`; `None` when it is not inside one
+pub fn open(path: &Path) -> Result<Option<Repository>, AppError> {
+    // 文件（包括已删除的文件）从其所在目录开始查找 / Files start from their folder
+    let start = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(path)
+    };
+    match Repository::discover(start) {
+        Ok(repo) if repo.is_bare() => Ok(None),
+        Ok(repo) => Ok(Some(repo)),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 仓库工作目录 / Working directory of a non-bare repository
+pub fn workdir(repo: &Repository) -> Result<PathBuf, AppError> {
+    // 去掉末尾的分隔符 / Drops the trailing separator
+    repo.workdir()
+        .map(|p| p.components().collect::<PathBuf>())
+        .ok_or_else(|| AppError::Git("仓库没有工作目录".to_string()))
+}
+
+/// 文件相对仓库工作目录的路径；文件已删除时按父目录解析
+/// Path relative to the working directory; deleted files resolve via their parent
+pub fn relative_path(repo: &Repository, path: &Path) -> Result<PathBuf, AppError> {
+    let root = workdir(repo)?.canonicalize()?;
+    let absolute = match path.canonicalize() {
+        Ok(p) => p,
+        Err(_) => {
+            let name = path
+                .file_name()
+                .ok_or_else(|| AppError::InvalidInput("无效的文件路径".to_string()))?;
+            path.parent()
+                .unwrap_or(Path::new("."))
+                .canonicalize()?
+                .join(name)
+        }
+    };
+    absolute
+        .strip_prefix(&root)
+        .map(Path::to_path_buf)
+        .map_err(|_| AppError::Git("文件不在仓库内".to_string()))
+}
+
+/// 打开路径所在仓库，不在仓库内时报错 / Opens the repository, failing when there is none
+pub fn require(path: &Path) -> Result<Repository, AppError> {
+    open(path)?.ok_or_else(|| AppError::Git("不是 Git 仓库".to_string()))
+}
+
+fn branch_name(repo: &Repository) -> Option<String> {
+    match repo.head() {
+        Ok(head) if head.is_branch() => head.shorthand().map(str::to_string),
+        Ok(_) => None,
+        // 尚无提交时 HEAD 指向未诞生的分支 / Unborn branch before the first commit
+        Err(_) => repo
+            .find_reference("HEAD")
+            .ok()
+            .and_then(|r| r.symbolic_target().map(str::to_string))
+            .map(|t| t.trim_start_matches("refs/heads/").to_string()),
+    }
+}
+
+/// status
+/// 查询目录所在仓库中有变化的文件（只包含该目录下的文件）；不在仓库内时返回 `None`
+pub fn status(dir: &Path) -> Result<Option<RepoStatus>, AppError> {
+    let Some(repo) = open(dir)? else {
+        return Ok(None);
+    };
+    let root = workdir(&repo)?;
+    // 工作区可能是仓库的子目录 / The workspace may be a subfolder of the repository
+    let scope = dir
+        .canonicalize()?
+        .strip_prefix(root.canonicalize()?)
+        .map(Path::to_path_buf)
+        .map_err(|_| AppError::Git("目录不在仓库工作区内".to_string()))?;
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .exclude_submodules(true)
+        .renames_head_to_index(true);
+    if !scope.as_os_str().is_empty() {
+        // 按字面路径匹配，避免目录名中的 `*`、`[` 被当作通配符 / Match literally so `*` or `[` in names aren't globs
+        options.pathspec(display(&scope)).disable_pathspec_match(true);
+    }
+
+    let mut files = Vec::new();
+    for entry in repo.statuses(Some(&mut options))?.iter() {
+        let Some(rel) = entry.path() else {
+            continue;
+        };
+        let s = entry.status();
+        files.push(FileStatus {
+            path: display(&root.join(rel)),
+            staged: s.intersects(
+                Status::INDEX_NEW
+                    | Status::INDEX_MODIFIED
+                    | Status::INDEX_DELETED
+                    | Status::INDEX_RENAMED
+                    | Status::INDEX_TYPECHANGE,
+            ),
+            modified: s.intersects(
+                Status::WT_MODIFIED
+                    | Status::WT_DELETED
+                    | Status::WT_RENAMED
+                    | Status::WT_TYPECHANGE,
+            ),
+            untracked: s.contains(Status::WT_NEW),
+            conflicted: s.is_conflicted(),
+            deleted: s.intersects(Status::INDEX_DELETED | Status::WT_DELETED),
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(Some(RepoStatus {
+        root: display(&root),
+        branch: branch_name(&repo),
+        files,
+    }))
+}
@@ -0,0 +1,156 @@
+//! 提交说明生成 / Commit message generation
+//!
+//! 将已暂存的 Markdown 差异整理为提示词交给 AI，解析回复中的提交说明与更新日志。
+//! 差异过大时按文件公平分配字符预算：先保证每个文件都出现在概览中，再按区块截断。
+//! Builds the prompt from staged Markdown diffs, truncating fairly per file, and parses the reply.
+
+use super::diff::StagedPatch;
+
+/// 默认差异字符预算 / Default character budget for the diff
+pub const DEFAULT_BUDGET: usize = 12_000;
+
+/// 预算下限，保证每个文件至少能展示少量内容 / Lower bound so every file shows something
+const MIN_BUDGET: usize = 2_000;
+
+/// 生成提示词时使用的上下文行数 / Context lines used when rendering the diff
+pub const PROMPT_CONTEXT: u32 = 1;
+
+pub const SYSTEM_PROMPT: &str = "You write git commit messages and changelog entries for changes to Markdown documents. Be concise and factual; describe what changed for the reader, not how the diff looks.";
+
+const INSTRUCTIONS: &str = "Below is the staged diff of Markdown files. Write:
+1. A commit message: a summary line of at most 72 characters in the imperative mood, optionally followed by a blank line and a short body.
+2. A changelog entry: a few bullet points describing the changes for readers.
+Use the same language as the document content. Reply exactly in this format:
+COMMIT:
+<commit message>
+CHANGELOG:
+<changelog entry>";
+
+/// ChangeSummary
+/// AI 生成的提交说明与更新日志
+#[derive(Debug, serde::Serialize)]
+pub struct ChangeSummary {
+    pub commit_message: String,
+    pub changelog: String,
+    /// 参与总结的文件 / Files included in the summary
+    pub files: Vec<String>,
+    /// 差异是否因过长被截断 / Whether the diff had to be truncated
+    pub truncated: bool,
+}
+
+/// 按预算截取单个文件的区块：整块保留，首个区块放不下时按行截断
+/// Keeps whole hunks within `limit`; the first hunk is cut by lines if it alone is too big
+fn take_hunks(patch: &StagedPatch, limit: usize) -> (String, bool) {
+    let mut out = String::new();
+    for (i, hunk) in patch.hunks.iter().enumerate() {
+        if out.len() + hunk.len() <= limit {
+            out.push_str(hunk);
+            continue;
+        }
+        let mut omitted = patch.hunks.len() - i;
+        if i == 0 {
+            let lines: Vec<&str> = hunk.lines().collect();
+            let mut shown = 0;
+            for line in &lines {
+                if out.len() + line.len() + 1 > limit {
+                    break;
+                }
+                out.push_str(line);
+                out.push('\n');
+                shown += 1;
+            }
+            if shown > 0 {
+                out.push_str(&format!(
+                    "... ({} more line(s) omitted)\n",
+                    lines.len() - shown
+                ));
+                omitted -= 1;
+            }
+        }
+        if omitted > 0 {
+            out.push_str(&format!("... ({} more hunk(s) omitted)\n", omitted));
+        }
+        return (out, true);
+    }
+    (out, false)
+}
+
+/// render_diff
+/// 生成提示词中的差异部分，返回 (文本, 是否截断)
+pub fn render_diff(patches: &[StagedPatch], budget: usize) -> (String, bool) {
+    let mut overview = String::from("Files:\n");
+    for p in patches {
+        overview.push_str(&format!(
+            "- {} ({}, +{} -{})\n",
+            p.path, p.change, p.additions, p.deletions
+        ));
+    }
+
+    // 从小到大分配：小文件用不完的份额留给大文件
+    // Water-filling: small patches take what they need, the rest goes to larger ones
+    let sizes: Vec<usize> = patches
+        .iter()
+        .map(|p| p.hunks.iter().map(String::len).sum())
+        .collect();
+    let mut order: Vec<usize> = (0..patches.len()).collect();
+    order.sort_by_key(|&i| sizes[i]);
+    let mut remaining = budget.max(MIN_BUDGET).saturating_sub(overview.len());
+    let mut allowance = vec![0; patches.len()];
+    for (n, &i) in order.iter().enumerate() {
+        let share = remaining / (patches.len() - n);
+        allowance[i] = sizes[i].min(share);
+        remaining -= allowance[i];
+    }
+
+    let mut out = overview;
+    let mut truncated = false;
+    for (i, p) in patches.iter().enumerate() {
+        out.push_str(&format!("\n--- {}\n", p.path));
+        let (text, cut) = take_hunks(p, allowance[i]);
+        truncated |= cut;
+        out.push_str(&text);
+    }
+    (out, truncated)
+}
+
+/// build_prompt
+/// 组合指令与差异
+pub fn build_prompt(diff: &str) -> String {
+    format!("{}\n\n{}", INSTRUCTIONS, diff)
+}
+
+/// 去掉代码围栏 / Strips surrounding code fences
+fn unfence(text: &str) -> String {
+    text.lines()
+        .filter(|l| !l.trim_start().starts_with("```"))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// parse_response
+/// 解析 `COMMIT:` 与 `CHANGELOG:` 两段；格式不符时整段作为提交说明
+pub fn parse_response(text: &str) -> (String, String) {
+    let text = unfence(text);
+    // ASCII 大写不改变字节位置 / ASCII uppercasing keeps byte offsets valid
+    let upper = text.to_ascii_uppercase();
+    let (Some(commit), Some(changelog)) = (upper.find("COMMIT:"), upper.find("CHANGELOG:")) else {
+        return (text, String::new());
+    };
+    let (commit_message, changelog) = if commit < changelog {
+        (
+            &text[commit + "COMMIT:".len()..changelog],
+            &text[changelog + "CHANGELOG:".len()..],
+        )
+    } else {
+        (
+            &text[commit + "COMMIT:".len()..],
+            &text[changelog + "CHANGELOG:".len()..commit],
+        )
+    };
+    (
+        commit_message.trim().to_string(),
+        changelog.trim().to_string(),
+    )
+}
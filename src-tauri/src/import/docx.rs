@@ -0,0 +1,638 @@
+//! DOCX → Markdown
+//!
+//! 读取 word/document.xml 及其样式、编号、关系与脚注；标题、列表、代码与引用由段落样式推断，
+//! 内嵌图片提取到 `assets/`。
+//! Headings, lists, code and quotes are inferred from paragraph styles; images are extracted.
+
+use super::{escape_markdown, inline_code, link_destination, wrap_emphasis, AssetWriter};
+use crate::assets::detect_extension;
+use crate::error::AppError;
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+const W: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const R: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+/// 视为代码的等宽字体 / Monospace fonts treated as inline code
+const MONO_FONTS: &[&str] = &[
+    "courier new",
+    "courier",
+    "consolas",
+    "menlo",
+    "monaco",
+    "source code pro",
+    "lucida console",
+];
+
+#[derive(Default, Clone)]
+struct StyleInfo {
+    heading: Option<u8>,
+    code: bool,
+    quote: bool,
+    based_on: Option<String>,
+}
+
+/// 行内片段 / Inline run
+enum Seg {
+    Text {
+        text: String,
+        bold: bool,
+        italic: bool,
+        strike: bool,
+        code: bool,
+    },
+    Raw(String),
+}
+
+fn element_children<'a, 'i>(node: Node<'a, 'i>) -> impl Iterator<Item = Node<'a, 'i>> {
+    node.children().filter(|n| n.is_element())
+}
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Option<Node<'a, 'i>> {
+    element_children(node).find(|n| n.tag_name().name() == name)
+}
+
+fn val<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    node.attribute((W, "val"))
+}
+
+/// `<w:b/>` 与 `<w:b w:val="0"/>` 等开关属性 / On/off properties
+fn toggle(props: Option<Node>, name: &str) -> bool {
+    props
+        .and_then(|p| child(p, name))
+        .map(|n| {
+            !matches!(
+                val(n),
+                Some("0") | Some("false") | Some("off") | Some("none")
+            )
+        })
+        .unwrap_or(false)
+}
+
+fn read_entry(
+    archive: &mut zip::ZipArchive<std::fs::File>,
+    name: &str,
+) -> Result<Option<Vec<u8>>, AppError> {
+    let mut entry = match archive.by_name(name) {
+        Ok(e) => e,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    // 声明的大小不可信，按实际读取的字节数限制 / The declared size is untrusted; cap what is read
+    let mut buf = Vec::new();
+    (&mut entry)
+        .take(crate::MAX_FILE_SIZE + 1)
+        .read_to_end(&mut buf)?;
+    if buf.len() as u64 > crate::MAX_FILE_SIZE {
+        return Err(AppError::InvalidInput(format!("文档内容过大: {}", name)));
+    }
+    Ok(Some(buf))
+}
+
+fn read_xml(
+    archive: &mut zip::ZipArchive<std::fs::File>,
+    name: &str,
+) -> Result<Option<String>, AppError> {
+    Ok(read_entry(archive, name)?.map(|b| String::from_utf8_lossy(&b).into_owned()))
+}
+
+fn parse(xml: &str) -> Result<Document<'_>, AppError> {
+    Document::parse(xml).map_err(|_| AppError::InvalidInput("无效的 DOCX 文档".to_string()))
+}
+
+fn parse_styles(xml: &str) -> Result<HashMap<String, StyleInfo>, AppError> {
+    let doc = parse(xml)?;
+    let mut styles = HashMap::new();
+    for style in doc.descendants().filter(|n| n.has_tag_name((W, "style"))) {
+        let Some(id) = style.attribute((W, "styleId")) else {
+            continue;
+        };
+        let name = child(style, "name")
+            .and_then(val)
+            .unwrap_or(id)
+            .to_lowercase();
+        let outline = child(style, "pPr")
+            .and_then(|p| child(p, "outlineLvl"))
+            .and_then(val)
+            .and_then(|v| v.parse::<u8>().ok());
+        let heading = if name == "title" {
+            Some(1)
+        } else if let Some(n) = name.strip_prefix("heading ") {
+            n.trim().parse::<u8>().ok()
+        } else {
+            outline.filter(|l| *l < 6).map(|l| l + 1)
+        };
+        styles.insert(
+            id.to_string(),
+            StyleInfo {
+                heading: heading.map(|h| h.clamp(1, 6)),
+                code: name.contains("code")
+                    || name.contains("source")
+                    || name.contains("preformatted"),
+                quote: name.contains("quote"),
+                based_on: child(style, "basedOn").and_then(val).map(str::to_string),
+            },
+        );
+    }
+    Ok(styles)
+}
+
+/// numId + ilvl -> 是否为有序列表 / Whether a list level is ordered
+fn parse_numbering(xml: &str) -> Result<HashMap<(String, u8), bool>, AppError> {
+    let doc = parse(xml)?;
+    let mut abstract_levels: HashMap<String, HashMap<u8, bool>> = HashMap::new();
+    for abs in doc
+        .descendants()
+        .filter(|n| n.has_tag_name((W, "abstractNum")))
+    {
+        let Some(id) = abs.attribute((W, "abstractNumId")) else {
+            continue;
+        };
+        let mut levels = HashMap::new();
+        for lvl in element_children(abs).filter(|n| n.tag_name().name() == "lvl") {
+            let ilvl = lvl
+                .attribute((W, "ilvl"))
+                .and_then(|v| v.parse::<u8>().ok())
+                .unwrap_or(0);
+            let fmt = child(lvl, "numFmt").and_then(val).unwrap_or("bullet");
+            levels.insert(ilvl, !matches!(fmt, "bullet" | "none"));
+        }
+        abstract_levels.insert(id.to_string(), levels);
+    }
+    let mut out = HashMap::new();
+    for num in doc.descendants().filter(|n| n.has_tag_name((W, "num"))) {
+        let (Some(num_id), Some(abs_id)) = (
+            num.attribute((W, "numId")),
+            child(num, "abstractNumId").and_then(val),
+        ) else {
+            continue;
+        };
+        if let Some(levels) = abstract_levels.get(abs_id) {
+            for (ilvl, ordered) in levels {
+                out.insert((num_id.to_string(), *ilvl), *ordered);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// 关系 id -> (目标, 是否为外部链接) / Relationship id -> (target, external)
+fn parse_rels(xml: &str) -> Result<HashMap<String, (String, bool)>, AppError> {
+    let doc = parse(xml)?;
+    Ok(doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "Relationship")
+        .filter_map(|n| {
+            Some((
+                n.attribute("Id")?.to_string(),
+                (
+                    n.attribute("Target")?.to_string(),
+                    n.attribute("TargetMode") == Some("External"),
+                ),
+            ))
+        })
+        .collect())
+}
+
+struct Converter<'a> {
+    archive: zip::ZipArchive<std::fs::File>,
+    assets: &'a mut AssetWriter,
+    styles: HashMap<String, StyleInfo>,
+    numbering: HashMap<(String, u8), bool>,
+    rels: HashMap<String, (String, bool)>,
+    images: HashMap<String, String>,
+    footnotes_used: Vec<String>,
+    code_lines: Vec<String>,
+    last_list: bool,
+}
+
+impl Converter<'_> {
+    /// 沿 basedOn 链合并样式信息 / Resolves style info through the basedOn chain
+    fn style(&self, id: Option<&str>) -> StyleInfo {
+        let mut info = StyleInfo::default();
+        let mut current = id.map(str::to_string);
+        for _ in 0..8 {
+            let Some(s) = current.as_ref().and_then(|id| self.styles.get(id)) else {
+                break;
+            };
+            info.heading = info.heading.or(s.heading);
+            info.code |= s.code;
+            info.quote |= s.quote;
+            current = s.based_on.clone();
+        }
+        info
+    }
+
+    fn image(&mut self, rel_id: &str) -> Result<Option<String>, AppError> {
+        if let Some(link) = self.images.get(rel_id) {
+            return Ok(Some(link.clone()));
+        }
+        let Some((target, external)) = self.rels.get(rel_id).cloned() else {
+            return Ok(None);
+        };
+        let link = if external {
+            target
+        } else {
+            let name = match target.strip_prefix('/') {
+                Some(abs) => abs.to_string(),
+                None => format!("word/{}", target),
+            };
+            let Some(bytes) = read_entry(&mut self.archive, &name)? else {
+                return Ok(None);
+            };
+            // 按内容判断类型，不信任条目名的扩展名 / Trust the content, not the entry name
+            let Some(ext) = detect_extension(&bytes) else {
+                log::warn!("Skip non-image DOCX entry {}", name);
+                return Ok(None);
+            };
+            self.assets.save(&bytes, ext)?
+        };
+        self.images.insert(rel_id.to_string(), link.clone());
+        Ok(Some(link))
+    }
+
+    fn run(&mut self, run: Node, segs: &mut Vec<Seg>) -> Result<(), AppError> {
+        let props = child(run, "rPr");
+        let code = props
+            .and_then(|p| child(p, "rStyle"))
+            .and_then(val)
+            .map(|s| {
+                let s = s.to_lowercase();
+                s.contains("code") || s.contains("verbatim")
+            })
+            .unwrap_or(false)
+            || props
+                .and_then(|p| child(p, "rFonts"))
+                .and_then(|f| f.attribute((W, "ascii")))
+                .map(|f| MONO_FONTS.contains(&f.to_lowercase().as_str()))
+                .unwrap_or(false);
+        let bold = toggle(props, "b");
+        let italic = toggle(props, "i");
+        let strike = toggle(props, "strike") || toggle(props, "dstrike");
+        let push = |segs: &mut Vec<Seg>, text: &str| {
+            segs.push(Seg::Text {
+                text: text.to_string(),
+                bold,
+                italic,
+                strike,
+                code,
+            })
+        };
+        for node in element_children(run) {
+            match node.tag_name().name() {
+                "t" => push(segs, node.text().unwrap_or("")),
+                "tab" => push(segs, "\t"),
+                "noBreakHyphen" => push(segs, "-"),
+                "br" if node.attribute((W, "type")).is_none() => push(segs, "\n"),
+                "cr" => push(segs, "\n"),
+                "drawing" | "pict" | "object" => {
+                    let rel = node.descendants().find_map(|n| {
+                        n.attribute((R, "embed"))
+                            .or_else(|| n.attribute((R, "id")))
+                            .or_else(|| n.attribute((R, "link")))
+                    });
+                    let alt = node
+                        .descendants()
+                        .find(|n| n.tag_name().name() == "docPr")
+                        .and_then(|n| n.attribute("descr").or_else(|| n.attribute("title")))
+                        .unwrap_or("");
+                    if let Some(link) = rel.map(|r| self.image(r)).transpose()?.flatten() {
+                        segs.push(Seg::Raw(format!(
+                            "![{}]({})",
+                            escape_markdown(alt),
+                            link_destination(&link)
+                        )));
+                    }
+                }
+                "footnoteReference" => {
+                    if let Some(id) = node.attribute((W, "id")) {
+                        if !self.footnotes_used.iter().any(|f| f == id) {
+                            self.footnotes_used.push(id.to_string());
+                        }
+                        segs.push(Seg::Raw(format!("[^{}]", id)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn inline(&mut self, node: Node, segs: &mut Vec<Seg>) -> Result<(), AppError> {
+        for n in element_children(node) {
+            match n.tag_name().name() {
+                "r" => self.run(n, segs)?,
+                "hyperlink" => {
+                    let mut inner = Vec::new();
+                    self.inline(n, &mut inner)?;
+                    let label = render(&inner).0;
+                    let url = n
+                        .attribute((R, "id"))
+                        .and_then(|id| self.rels.get(id))
+                        .map(|(target, _)| target.clone())
+                        .or_else(|| {
+                            // 导出时书签名为 `_<id>` / Exported bookmarks are named `_<id>`
+                            n.attribute((W, "anchor"))
+                                .map(|a| format!("#{}", a.trim_start_matches('_')))
+                        });
+                    match url {
+                        Some(url) if !label.trim().is_empty() => segs.push(Seg::Raw(format!(
+                            "[{}]({})",
+                            label.trim(),
+                            link_destination(&url)
+                        ))),
+                        _ => segs.push(Seg::Raw(label)),
+                    }
+                }
+                "ins" | "smartTag" | "fldSimple" | "customXml" => self.inline(n, segs)?,
+                "sdt" => {
+                    if let Some(content) = child(n, "sdtContent") {
+                        self.inline(content, segs)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// 段落的 (Markdown, 纯文本) / A paragraph's Markdown and plain text
+    fn paragraph_text(&mut self, p: Node) -> Result<(String, String), AppError> {
+        let mut segs = Vec::new();
+        self.inline(p, &mut segs)?;
+        Ok(render(&segs))
+    }
+
+    fn flush_code(&mut self, out: &mut String) {
+        if self.code_lines.is_empty() {
+            return;
+        }
+        let code = std::mem::take(&mut self.code_lines).join("\n");
+        let fence = if code.contains("```") { "~~~~" } else { "```" };
+        separate(out, false);
+        out.push_str(&format!("{}\n{}\n{}", fence, code, fence));
+    }
+
+    fn paragraph(&mut self, p: Node, out: &mut String) -> Result<(), AppError> {
+        let props = child(p, "pPr");
+        let info = self.style(props.and_then(|pp| child(pp, "pStyle")).and_then(val));
+        let (markdown, plain) = self.paragraph_text(p)?;
+
+        if info.code {
+            self.code_lines.push(plain.replace('\t', "    "));
+            return Ok(());
+        }
+        self.flush_code(out);
+        if markdown.trim().is_empty() {
+            return Ok(());
+        }
+
+        let num = props.and_then(|pp| child(pp, "numPr"));
+        let num_id = num.and_then(|n| child(n, "numId")).and_then(val);
+        let ilvl = num
+            .and_then(|n| child(n, "ilvl"))
+            .and_then(val)
+            .and_then(|v| v.parse::<u8>().ok())
+            .unwrap_or(0);
+        let text = markdown.trim();
+
+        if let Some(level) = info.heading {
+            separate(out, false);
+            out.push_str(&"#".repeat(level as usize));
+            out.push(' ');
+            out.push_str(&text.replace("  \n", " "));
+            self.last_list = false;
+        } else if let Some(num_id) = num_id.filter(|id| *id != "0") {
+            let ordered = self
+                .numbering
+                .get(&(num_id.to_string(), ilvl))
+                .copied()
+                .unwrap_or(false);
+            separate(out, self.last_list);
+            out.push_str(&"    ".repeat(ilvl as usize));
+            out.push_str(if ordered { "1. " } else { "- " });
+            out.push_str(&text.replace("  \n", "  \n    "));
+            self.last_list = true;
+        } else if info.quote {
+            separate(out, false);
+            for line in text.lines() {
+                out.push_str("> ");
+                out.push_str(line);
+                out.push('\n');
+            }
+            self.last_list = false;
+        } else {
+            separate(out, false);
+            out.push_str(text);
+            self.last_list = false;
+        }
+        Ok(())
+    }
+
+    fn table(&mut self, tbl: Node, out: &mut String) -> Result<(), AppError> {
+        self.flush_code(out);
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for tr in element_children(tbl).filter(|n| n.tag_name().name() == "tr") {
+            let mut cells = Vec::new();
+            for tc in element_children(tr).filter(|n| n.tag_name().name() == "tc") {
+                let mut parts = Vec::new();
+                for p in tc.descendants().filter(|n| n.has_tag_name((W, "p"))) {
+                    let (markdown, _) = self.paragraph_text(p)?;
+                    let markdown = markdown.trim().replace("  \n", "<br>");
+                    if !markdown.is_empty() {
+                        parts.push(markdown);
+                    }
+                }
+                cells.push(parts.join("<br>").replace('|', "\\|"));
+            }
+            rows.push(cells);
+        }
+        let cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if cols == 0 {
+            return Ok(());
+        }
+        separate(out, false);
+        for (i, row) in rows.iter().enumerate() {
+            out.push('|');
+            for c in 0..cols {
+                out.push(' ');
+                out.push_str(row.get(c).map(String::as_str).unwrap_or(""));
+                out.push_str(" |");
+            }
+            out.push('\n');
+            if i == 0 {
+                out.push('|');
+                out.push_str(&" --- |".repeat(cols));
+                out.push('\n');
+            }
+        }
+        self.last_list = false;
+        Ok(())
+    }
+
+    fn body(&mut self, node: Node, out: &mut String) -> Result<(), AppError> {
+        for n in element_children(node) {
+            match n.tag_name().name() {
+                "p" => self.paragraph(n, out)?,
+                "tbl" => self.table(n, out)?,
+                "sdt" => {
+                    if let Some(content) = child(n, "sdtContent") {
+                        self.body(content, out)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.flush_code(out);
+        Ok(())
+    }
+
+    fn footnotes(&mut self, out: &mut String) -> Result<(), AppError> {
+        if self.footnotes_used.is_empty() {
+            return Ok(());
+        }
+        let Some(xml) = read_xml(&mut self.archive, "word/footnotes.xml")? else {
+            return Ok(());
+        };
+        let doc = parse(&xml)?;
+        let mut texts: HashMap<String, String> = HashMap::new();
+        for note in doc
+            .descendants()
+            .filter(|n| n.has_tag_name((W, "footnote")))
+        {
+            let Some(id) = note.attribute((W, "id")) else {
+                continue;
+            };
+            let mut parts = Vec::new();
+            for p in note.descendants().filter(|n| n.has_tag_name((W, "p"))) {
+                let (markdown, _) = self.paragraph_text(p)?;
+                if !markdown.trim().is_empty() {
+                    parts.push(markdown.trim().to_string());
+                }
+            }
+            texts.insert(id.to_string(), parts.join(" "));
+        }
+        separate(out, false);
+        for id in self.footnotes_used.clone() {
+            if let Some(text) = texts.get(&id) {
+                out.push_str(&format!("[^{}]: {}\n", id, text));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 段落之间的分隔：连续列表项只换行，其他块之间空一行
+fn separate(out: &mut String, tight: bool) {
+    if out.is_empty() {
+        return;
+    }
+    while out.ends_with('\n') {
+        out.pop();
+    }
+    out.push_str(if tight { "\n" } else { "\n\n" });
+}
+
+/// 合并相同格式的片段并输出 (Markdown, 纯文本)
+/// Merges runs with identical formatting; returns (markdown, plain text)
+fn render(segs: &[Seg]) -> (String, String) {
+    let mut merged: Vec<(String, bool, bool, bool, bool)> = Vec::new();
+    let mut raw: Vec<Option<String>> = Vec::new();
+    for seg in segs {
+        match seg {
+            Seg::Text {
+                text,
+                bold,
+                italic,
+                strike,
+                code,
+            } => {
+                let key = (*bold, *italic, *strike, *code);
+                match (merged.last_mut(), raw.last()) {
+                    (Some(last), Some(None)) if (last.1, last.2, last.3, last.4) == key => {
+                        last.0.push_str(text)
+                    }
+                    _ => {
+                        merged.push((text.clone(), *bold, *italic, *strike, *code));
+                        raw.push(None);
+                    }
+                }
+            }
+            Seg::Raw(r) => raw.push(Some(r.clone())),
+        }
+    }
+
+    let mut markdown = String::new();
+    let mut plain = String::new();
+    let mut texts = merged.into_iter();
+    for item in raw {
+        if let Some(r) = item {
+            markdown.push_str(&r);
+            plain.push_str(&r);
+            continue;
+        }
+        let Some((text, bold, italic, strike, code)) = texts.next() else {
+            continue;
+        };
+        plain.push_str(&text);
+        let mut piece = if code {
+            inline_code(&text.replace('\n', " "))
+        } else {
+            escape_markdown(&text).replace('\n', "  \n")
+        };
+        if strike {
+            piece = wrap_emphasis(&piece, "~~");
+        }
+        if italic {
+            piece = wrap_emphasis(&piece, "*");
+        }
+        if bold {
+            piece = wrap_emphasis(&piece, "**");
+        }
+        markdown.push_str(&piece);
+    }
+    (markdown, plain)
+}
+
+/// convert
+/// 将 DOCX 文件转换为 Markdown
+pub fn convert(path: &Path, assets: &mut AssetWriter) -> Result<String, AppError> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+    let document = read_xml(&mut archive, "word/document.xml")?
+        .ok_or_else(|| AppError::InvalidInput("无效的 DOCX 文档".to_string()))?;
+    let styles = match read_xml(&mut archive, "word/styles.xml")? {
+        Some(xml) => parse_styles(&xml)?,
+        None => HashMap::new(),
+    };
+    let numbering = match read_xml(&mut archive, "word/numbering.xml")? {
+        Some(xml) => parse_numbering(&xml)?,
+        None => HashMap::new(),
+    };
+    let rels = match read_xml(&mut archive, "word/_rels/document.xml.rels")? {
+        Some(xml) => parse_rels(&xml)?,
+        None => HashMap::new(),
+    };
+
+    let mut converter = Converter {
+        archive,
+        assets,
+        styles,
+        numbering,
+        rels,
+        images: HashMap::new(),
+        footnotes_used: Vec::new(),
+        code_lines: Vec::new(),
+        last_list: false,
+    };
+    let doc = parse(&document)?;
+    let body = doc
+        .descendants()
+        .find(|n| n.has_tag_name((W, "body")))
+        .ok_or_else(|| AppError::InvalidInput("无效的 DOCX 文档".to_string()))?;
+    let mut out = String::new();
+    converter.body(body, &mut out)?;
+    converter.footnotes(&mut out)?;
+    Ok(out)
+}
@@ -0,0 +1,444 @@
+//! HTML → Markdown
+//!
+//! 网页剪藏优先取 `<article>` / `<main>` 正文；内联（data URI）与本地图片提取到 `assets/`，
+//! 远程图片保留原链接。
+//! Web clippings prefer `<article>` / `<main>`; data-URI and local images are extracted,
+//! remote images keep their URL.
+
+use super::{escape_markdown, inline_code, link_destination, wrap_emphasis, AssetWriter};
+use crate::assets::detect_extension;
+use crate::error::AppError;
+use crate::markdown;
+use base64::Engine;
+use scraper::{ElementRef, Html, Node, Selector};
+use std::path::Path;
+
+/// 不输出内容的标签 / Elements whose content is dropped
+const SKIPPED: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "iframe", "svg", "canvas", "button", "form",
+    "select", "textarea", "nav", "title", "meta", "link",
+];
+
+fn select_first<'a>(doc: &'a Html, selector: &str) -> Option<ElementRef<'a>> {
+    let selector = Selector::parse(selector).ok()?;
+    doc.select(&selector).next()
+}
+
+fn text_of(element: ElementRef) -> String {
+    element.text().collect::<String>()
+}
+
+struct Converter<'a> {
+    base_dir: &'a Path,
+    assets: &'a mut AssetWriter,
+    in_table: bool,
+    list_depth: usize,
+}
+
+impl Converter<'_> {
+    /// 保存图片并返回新链接；无法处理时保留原地址
+    fn image(&mut self, src: &str) -> Result<String, AppError> {
+        let src = src.trim();
+        if let Some(data) = src.strip_prefix("data:") {
+            let Some((meta, payload)) = data.split_once(',') else {
+                return Ok(String::new());
+            };
+            if !meta.ends_with(";base64") {
+                return Ok(String::new());
+            }
+            let Ok(bytes) = base64::engine::general_purpose::STANDARD
+                .decode(payload.split_whitespace().collect::<String>())
+            else {
+                log::warn!("Skip undecodable data URI image");
+                return Ok(String::new());
+            };
+            // 扩展名按内容判断，不信任声明的 MIME 类型 / The declared MIME type is not trusted
+            return match detect_extension(&bytes) {
+                Some(ext) => self.assets.save(&bytes, ext),
+                None => {
+                    log::warn!("Skip data URI that is not an image");
+                    Ok(String::new())
+                }
+            };
+        }
+        if markdown::is_external(src) {
+            return Ok(src.to_string());
+        }
+        // 只复制源文件所在目录之内的图片，剪藏的网页不能借此读取其他文件
+        // Only images under the source file's folder are copied, so a clipped page cannot pull in other files
+        let Some(path) = markdown::resolve_local(self.base_dir, src) else {
+            return Ok(src.to_string());
+        };
+        let inside = match (
+            std::fs::canonicalize(&path),
+            std::fs::canonicalize(self.base_dir),
+        ) {
+            (Ok(p), Ok(base)) => p.starts_with(&base) && p.is_file(),
+            _ => false,
+        };
+        if !inside {
+            log::warn!("Skip image outside the source folder: {}", src);
+            return Ok(src.to_string());
+        }
+        let bytes = std::fs::read(&path)?;
+        match detect_extension(&bytes) {
+            Some(ext) => self.assets.save(&bytes, ext),
+            None => {
+                log::warn!("Skip non-image file {:?}", path);
+                Ok(src.to_string())
+            }
+        }
+    }
+
+    fn children(&mut self, element: ElementRef, out: &mut String) -> Result<(), AppError> {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => push_text(out, text),
+                Node::Element(_) => {
+                    if let Some(el) = ElementRef::wrap(child) {
+                        self.element(el, out)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, element: ElementRef) -> Result<String, AppError> {
+        let mut out = String::new();
+        self.children(element, &mut out)?;
+        Ok(out)
+    }
+
+    fn element(&mut self, el: ElementRef, out: &mut String) -> Result<(), AppError> {
+        let name = el.value().name();
+        if SKIPPED.contains(&name) {
+            return Ok(());
+        }
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                let text = self.render(el)?;
+                let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !text.is_empty() {
+                    block(out);
+                    out.push_str(&"#".repeat(level));
+                    out.push(' ');
+                    out.push_str(&text);
+                    block(out);
+                }
+            }
+            "p" | "div" | "section" | "article" | "main" | "header" | "footer" | "figure"
+            | "figcaption" | "aside" | "details" | "summary" | "center" | "dl" | "dd"
+            | "address" => {
+                if self.in_table {
+                    self.children(el, out)?;
+                    out.push('\n');
+                } else {
+                    block(out);
+                    self.children(el, out)?;
+                    block(out);
+                }
+            }
+            "dt" => {
+                block(out);
+                let text = self.render(el)?;
+                out.push_str(&wrap_emphasis(text.trim(), "**"));
+                block(out);
+            }
+            "br" => {
+                if self.in_table {
+                    out.push('\n');
+                } else {
+                    out.push_str("  \n");
+                }
+            }
+            "hr" => {
+                block(out);
+                out.push_str("---");
+                block(out);
+            }
+            "pre" => {
+                let code = text_of(el);
+                let lang = el
+                    .descendants()
+                    .filter_map(ElementRef::wrap)
+                    .chain(std::iter::once(el))
+                    .filter_map(|e| e.value().attr("class"))
+                    .flat_map(|c| c.split_whitespace())
+                    .find_map(|c| {
+                        c.strip_prefix("language-")
+                            .or_else(|| c.strip_prefix("lang-"))
+                    })
+                    .unwrap_or("")
+                    .to_string();
+                let fence = if code.contains("```") { "~~~~" } else { "```" };
+                block(out);
+                out.push_str(fence);
+                out.push_str(&lang);
+                out.push('\n');
+                out.push_str(code.trim_end_matches('\n'));
+                out.push('\n');
+                out.push_str(fence);
+                block(out);
+            }
+            "code" | "kbd" | "samp" | "tt" => {
+                let code = text_of(el);
+                if !code.is_empty() {
+                    out.push_str(&inline_code(&code.replace('\n', " ")));
+                }
+            }
+            "strong" | "b" => {
+                let text = self.render(el)?;
+                out.push_str(&wrap_emphasis(&text, "**"));
+            }
+            "em" | "i" | "cite" => {
+                let text = self.render(el)?;
+                out.push_str(&wrap_emphasis(&text, "*"));
+            }
+            "del" | "s" | "strike" => {
+                let text = self.render(el)?;
+                out.push_str(&wrap_emphasis(&text, "~~"));
+            }
+            "a" => {
+                let text = self.render(el)?;
+                match el.value().attr("href").map(str::trim) {
+                    Some(href) if !href.is_empty() && !href.starts_with("javascript:") => {
+                        let label = if text.trim().is_empty() {
+                            href
+                        } else {
+                            text.trim()
+                        };
+                        out.push_str(&format!("[{}]({})", label, link_destination(href)));
+                    }
+                    _ => out.push_str(&text),
+                }
+            }
+            "img" => {
+                let Some(src) = el.value().attr("src") else {
+                    return Ok(());
+                };
+                let link = self.image(src)?;
+                if !link.is_empty() {
+                    let alt = escape_markdown(el.value().attr("alt").unwrap_or(""));
+                    out.push_str(&format!("![{}]({})", alt, link_destination(&link)));
+                }
+            }
+            "input" => {
+                if el.value().attr("type") == Some("checkbox") {
+                    let checked = el.value().attr("checked").is_some();
+                    out.push_str(if checked { "[x] " } else { "[ ] " });
+                }
+            }
+            "ul" | "ol" => self.list(el, name == "ol", out)?,
+            "blockquote" => {
+                let inner = self.render(el)?;
+                block(out);
+                for line in super::tidy(&inner).trim_end().lines() {
+                    if line.is_empty() {
+                        out.push_str(">\n");
+                    } else {
+                        out.push_str("> ");
+                        out.push_str(line);
+                        out.push('\n');
+                    }
+                }
+                block(out);
+            }
+            "table" if !self.in_table => self.table(el, out)?,
+            _ => self.children(el, out)?,
+        }
+        Ok(())
+    }
+
+    fn list(&mut self, el: ElementRef, ordered: bool, out: &mut String) -> Result<(), AppError> {
+        let mut n = el
+            .value()
+            .attr("start")
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(1);
+        // 嵌套列表紧跟父列表项 / Nested lists follow their parent item directly
+        let nested = self.list_depth > 0;
+        if !nested {
+            block(out);
+        } else if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        self.list_depth += 1;
+        for item in el.children().filter_map(ElementRef::wrap) {
+            if item.value().name() != "li" {
+                continue;
+            }
+            let marker = if ordered {
+                let m = format!("{}. ", n);
+                n += 1;
+                m
+            } else {
+                "- ".to_string()
+            };
+            let content = super::tidy(&self.render(item)?);
+            let indent = " ".repeat(marker.len());
+            for (i, line) in content.trim_end().lines().enumerate() {
+                if i == 0 {
+                    out.push_str(&marker);
+                } else if !line.is_empty() {
+                    out.push_str(&indent);
+                }
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        self.list_depth -= 1;
+        if !nested {
+            block(out);
+        }
+        Ok(())
+    }
+
+    fn table(&mut self, el: ElementRef, out: &mut String) -> Result<(), AppError> {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut row_elements = Vec::new();
+        for child in el.children().filter_map(ElementRef::wrap) {
+            match child.value().name() {
+                "tr" => row_elements.push(child),
+                "thead" | "tbody" | "tfoot" => row_elements.extend(
+                    child
+                        .children()
+                        .filter_map(ElementRef::wrap)
+                        .filter(|r| r.value().name() == "tr"),
+                ),
+                _ => {}
+            }
+        }
+        self.in_table = true;
+        for row in row_elements {
+            let mut cells = Vec::new();
+            for cell in row.children().filter_map(ElementRef::wrap) {
+                if !matches!(cell.value().name(), "td" | "th") {
+                    continue;
+                }
+                let text = self.render(cell)?;
+                let text = text
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("<br>")
+                    .replace('|', "\\|");
+                cells.push(text);
+            }
+            rows.push(cells);
+        }
+        self.in_table = false;
+
+        let cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if cols == 0 {
+            return Ok(());
+        }
+        block(out);
+        for (i, row) in rows.iter().enumerate() {
+            out.push('|');
+            for c in 0..cols {
+                out.push(' ');
+                out.push_str(row.get(c).map(String::as_str).unwrap_or(""));
+                out.push_str(" |");
+            }
+            out.push('\n');
+            if i == 0 {
+                out.push('|');
+                out.push_str(&" --- |".repeat(cols));
+                out.push('\n');
+            }
+        }
+        block(out);
+        Ok(())
+    }
+}
+
+/// 开始新的块（确保前面有空行） / Starts a new block separated by a blank line
+fn block(out: &mut String) {
+    if out.is_empty() {
+        return;
+    }
+    while out.ends_with(' ') && !out.ends_with("  \n") {
+        out.pop();
+    }
+    if !out.ends_with("\n\n") {
+        out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+    }
+}
+
+/// 追加折叠空白后的文本 / Appends text with collapsed whitespace
+fn push_text(out: &mut String, text: &str) {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            space = true;
+        } else {
+            if space && !collapsed.is_empty() {
+                collapsed.push(' ');
+            }
+            space = false;
+            collapsed.push(c);
+        }
+    }
+    let at_line_start = out.is_empty() || out.ends_with('\n') || out.ends_with(' ');
+    let leading = text.starts_with(char::is_whitespace) && !at_line_start;
+    if collapsed.is_empty() {
+        if leading {
+            out.push(' ');
+        }
+        return;
+    }
+    if leading {
+        out.push(' ');
+    }
+    out.push_str(&escape_markdown(&collapsed));
+    if text.ends_with(char::is_whitespace) {
+        out.push(' ');
+    }
+}
+
+/// convert
+/// 将 HTML 文本转换为 Markdown；有标题或来源链接时写入 front matter
+pub fn convert(html: &str, base_dir: &Path, assets: &mut AssetWriter) -> Result<String, AppError> {
+    let doc = Html::parse_document(html);
+    let title = select_first(&doc, "title")
+        .map(text_of)
+        .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty());
+    let source = select_first(&doc, "link[rel=canonical]")
+        .and_then(|e| e.value().attr("href"))
+        .or_else(|| {
+            select_first(&doc, "meta[property=\"og:url\"]").and_then(|e| e.value().attr("content"))
+        })
+        .map(str::to_string);
+    let root = ["article", "main", "[role=main]", "body"]
+        .iter()
+        .find_map(|s| select_first(&doc, s))
+        .unwrap_or_else(|| doc.root_element());
+
+    let mut converter = Converter {
+        base_dir,
+        assets,
+        in_table: false,
+        list_depth: 0,
+    };
+    let body = converter.render(root)?;
+
+    let mut out = String::new();
+    if title.is_some() || source.is_some() {
+        out.push_str("---\n");
+        if let Some(title) = &title {
+            out.push_str(&format!("title: \"{}\"\n", title.replace('"', "\\\"")));
+        }
+        if let Some(source) = &source {
+            out.push_str(&format!("source: {}\n", source));
+        }
+        out.push_str("---\n\n");
+    }
+    out.push_str(body.trim());
+    Ok(out)
+}
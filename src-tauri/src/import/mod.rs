@@ -0,0 +1,197 @@
+//! 文档导入 / Document import
+//!
+//! 将 HTML（含网页剪藏）与 DOCX 转换为 Markdown，内嵌图片提取到新文件旁的 `assets/` 目录。
+//! Converts HTML (including web clippings) and DOCX into Markdown; images go to `assets/`.
+
+pub mod docx;
+pub mod html;
+
+use crate::assets::sanitize_file_name;
+use crate::error::AppError;
+use std::path::{Path, PathBuf};
+
+/// 图片目录名 / Image folder created next to the imported document
+pub const ASSETS_DIR: &str = "assets";
+
+/// 将导入过程中提取的图片写入 `assets/`，文件名为 `<文档名>-<序号>.<扩展名>`
+/// Writes extracted images into `assets/` as `<stem>-<n>.<ext>`
+pub struct AssetWriter {
+    dir: PathBuf,
+    prefix: String,
+    count: usize,
+}
+
+impl AssetWriter {
+    pub fn new(markdown_path: &Path) -> Self {
+        let dir = markdown_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(ASSETS_DIR);
+        let prefix = markdown_path
+            .file_stem()
+            .map(|s| sanitize_file_name(&s.to_string_lossy()))
+            .unwrap_or_else(|| "image".to_string());
+        Self {
+            dir,
+            prefix,
+            count: 0,
+        }
+    }
+
+    /// 保存图片并返回相对 Markdown 文件的链接 / Saves bytes and returns the relative link
+    pub fn save(&mut self, bytes: &[u8], ext: &str) -> Result<String, AppError> {
+        std::fs::create_dir_all(&self.dir)?;
+        let ext = ext.trim_start_matches('.').to_ascii_lowercase();
+        let ext = if ext.is_empty() {
+            "png".to_string()
+        } else {
+            ext
+        };
+        let name = loop {
+            self.count += 1;
+            let name = format!("{}-{}.{}", self.prefix, self.count, ext);
+            if !self.dir.join(&name).exists() {
+                break name;
+            }
+        };
+        std::fs::write(self.dir.join(&name), bytes)?;
+        Ok(format!("{}/{}", ASSETS_DIR, name))
+    }
+}
+
+/// 转义会被误解析为 Markdown 语法的字符 / Escapes characters that Markdown would interpret
+pub fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// 以合适长度的反引号包裹行内代码 / Wraps inline code with enough backticks
+pub fn inline_code(code: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in code.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    let fence = "`".repeat(longest + 1);
+    if code.starts_with('`') || code.ends_with('`') {
+        format!("{} {} {}", fence, code, fence)
+    } else {
+        format!("{}{}{}", fence, code, fence)
+    }
+}
+
+/// 用强调标记包裹文本，首尾空白移到标记外 / Wraps text with emphasis markers, keeping
+/// leading/trailing whitespace outside so the markers stay valid
+pub fn wrap_emphasis(text: &str, marker: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_string();
+    }
+    let start = text.len() - text.trim_start().len();
+    let end = text.trim_end().len();
+    format!(
+        "{}{}{}{}{}",
+        &text[..start],
+        marker,
+        trimmed,
+        marker,
+        &text[end..]
+    )
+}
+
+/// 图片链接中的空格等字符需要尖括号包裹 / Link destinations with spaces need angle brackets
+pub fn link_destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url)
+    } else {
+        url.to_string()
+    }
+}
+
+/// 整理输出：合并多余空行、去掉行尾空白（保留硬换行）
+/// Tidies the output: collapses blank lines and strips stray trailing whitespace
+pub fn tidy(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut blank = 0;
+    let mut in_fence = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        let line = if in_fence || line.ends_with("  ") && !line.trim().is_empty() {
+            line
+        } else {
+            line.trim_end()
+        };
+        if line.trim().is_empty() && !in_fence {
+            blank += 1;
+            if blank > 1 || out.is_empty() {
+                continue;
+            }
+            out.push('\n');
+            continue;
+        }
+        blank = 0;
+        out.push_str(line);
+        out.push('\n');
+    }
+    let trimmed = out.trim_end();
+    format!("{}\n", trimmed)
+}
+
+/// 目标 Markdown 路径：与源文件同名，已存在时追加序号
+/// Target Markdown path: same stem as the source, numbered if it already exists
+pub fn target_path(source: &Path, target_dir: &Path) -> PathBuf {
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "imported".to_string());
+    let mut candidate = target_dir.join(format!("{}.md", stem));
+    let mut n = 1;
+    while candidate.exists() {
+        candidate = target_dir.join(format!("{}-{}.md", stem, n));
+        n += 1;
+    }
+    candidate
+}
+
+/// import_document
+/// 按扩展名选择转换器，写出 Markdown 文件并返回其路径
+pub fn import_document(source: &Path, target_dir: &Path) -> Result<PathBuf, AppError> {
+    let ext = source
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let output = target_path(source, target_dir);
+    let mut assets = AssetWriter::new(&output);
+    let markdown = match ext.as_str() {
+        "html" | "htm" | "xhtml" => {
+            crate::check_file_size(source).map_err(AppError::InvalidInput)?;
+            let text = std::fs::read(source)?;
+            html::convert(
+                &String::from_utf8_lossy(&text),
+                source.parent().unwrap_or(Path::new(".")),
+                &mut assets,
+            )?
+        }
+        "docx" => docx::convert(source, &mut assets)?,
+        _ => {
+            return Err(AppError::InvalidInput(
+                "仅支持导入 HTML 与 DOCX 文件".to_string(),
+            ))
+        }
+    };
+    std::fs::write(&output, tidy(&markdown))?;
+    Ok(output)
+}
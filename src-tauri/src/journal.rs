@@ -0,0 +1,417 @@
+//! 日记 / Daily notes
+//!
+//! 按工作区配置中的路径模式（如 `journal/{yyyy}/{mm}-{dd}.md`）定位每天的日记，不存在时按模板创建，
+//! 并链接前后最近的已有日记，同时更新这两篇中的导航行；反向匹配该模式即可得到有日记的日期，供日历显示。
+//! Resolves dated notes from a path pattern, creates them from a template and lists dates that have notes.
+
+use crate::config::JournalSettings;
+use crate::error::AppError;
+use chrono::{Datelike, Local, NaiveDate};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+use std::path::{Component, Path, PathBuf};
+
+/// 默认路径模式 / Default path pattern
+pub const DEFAULT_PATTERN: &str = "journal/{yyyy}/{yyyy}-{mm}-{dd}.md";
+
+/// 未配置模板时的日记内容 / Note content when no template is configured
+const DEFAULT_CONTENT: &str = "# {{title}}\n\n{{nav}}\n\n";
+
+/// 没有已有日记可链接时的默认内容 / Default content when there is nothing to link to
+const DEFAULT_CONTENT_ALONE: &str = "# {{title}}\n\n";
+
+/// 两位年份超过今年这么多年时视为上个世纪 / Two-digit years this far ahead belong to the last century
+const YY_PIVOT_AHEAD: i32 = 20;
+
+/// 日期格式 / Date format used in commands and links
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// 路径模式中的日期占位符 / Date tokens in a path pattern
+static TOKEN: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{(yyyy|yy|mm|dd)\}").unwrap());
+
+/// 解析后的路径模式 / A parsed path pattern
+pub struct Pattern {
+    text: String,
+    regex: Regex,
+    /// 各捕获组对应的占位符 / Token of each capture group
+    groups: Vec<String>,
+    /// 不含占位符的前缀目录 / Leading directories without tokens
+    base: PathBuf,
+    /// 模式的层数 / Number of path components
+    depth: usize,
+}
+
+impl Pattern {
+    /// parse
+    /// 校验并解析路径模式：须为工作区内的相对路径，含年、月、日占位符，且指向 Markdown 文件
+    pub fn parse(text: &str) -> Result<Self, AppError> {
+        let text = text.trim().trim_start_matches("./").replace('\\', "/");
+        let invalid =
+            |why: &str| AppError::InvalidInput(format!("无效的日记路径 {}: {}", text, why));
+        let path = Path::new(&text);
+        if text.is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(invalid("须为工作区内的相对路径"));
+        }
+        if !crate::export::is_markdown(path) {
+            return Err(invalid("须为 .md 文件"));
+        }
+        let has = |token: &str| text.contains(token);
+        if !(has("{yyyy}") || has("{yy}")) || !has("{mm}") || !has("{dd}") {
+            return Err(invalid("须包含 {yyyy}、{mm}、{dd}"));
+        }
+
+        let mut source = String::from("^");
+        let mut groups = Vec::new();
+        let mut last = 0;
+        for caps in TOKEN.captures_iter(&text) {
+            let m = caps.get(0).unwrap();
+            source.push_str(&regex::escape(&text[last..m.start()]));
+            source.push_str(if &caps[1] == "yyyy" {
+                r"(\d{4})"
+            } else {
+                r"(\d{2})"
+            });
+            groups.push(caps[1].to_string());
+            last = m.end();
+        }
+        source.push_str(&regex::escape(&text[last..]));
+        source.push('$');
+
+        let base = path
+            .components()
+            .take_while(|c| !c.as_os_str().to_string_lossy().contains('{'))
+            .collect();
+        Ok(Pattern {
+            regex: Regex::new(&source).map_err(|e| invalid(&e.to_string()))?,
+            groups,
+            base,
+            depth: path.components().count(),
+            text,
+        })
+    }
+
+    /// 某天日记的相对路径（`/` 分隔） / Relative path of the note for `date`
+    pub fn path_for(&self, date: NaiveDate) -> String {
+        TOKEN
+            .replace_all(&self.text, |caps: &regex::Captures| match &caps[1] {
+                "yyyy" => format!("{:04}", date.year()),
+                "yy" => format!("{:02}", date.year().rem_euclid(100)),
+                "mm" => format!("{:02}", date.month()),
+                _ => format!("{:02}", date.day()),
+            })
+            .into_owned()
+    }
+
+    /// 由相对路径反推日期；各处占位符须一致 / Date of a relative path, if it matches the pattern
+    fn date_of(&self, rel: &str) -> Option<NaiveDate> {
+        let caps = self.regex.captures(rel)?;
+        let (mut year, mut month, mut day) = (None, None, None);
+        for (token, value) in self.groups.iter().zip(caps.iter().skip(1)) {
+            let n: u32 = value?.as_str().parse().ok()?;
+            match token.as_str() {
+                "yyyy" => year = year.or(Some(n as i32)),
+                "yy" => year = year.or(Some(expand_yy(n as i32))),
+                "mm" => month = month.or(Some(n)),
+                _ => day = day.or(Some(n)),
+            }
+        }
+        let date = NaiveDate::from_ymd_opt(year?, month?, day?)?;
+        // 重复的占位符（如目录与文件名中的年份）须相同
+        // Repeated tokens (e.g. the year in folder and file name) must agree
+        (self.path_for(date) == rel).then_some(date)
+    }
+}
+
+/// 两位年份：默认为本世纪，超过今年 20 年以上时取上个世纪（`99` 为 1999）
+/// Two-digit year in this century unless that is over 20 years ahead, then the last one
+fn expand_yy(yy: i32) -> i32 {
+    let year = 2000 + yy;
+    if year > Local::now().year() + YY_PIVOT_AHEAD {
+        year - 100
+    } else {
+        year
+    }
+}
+
+/// 工作区的日记路径模式 / The workspace's pattern, or the default
+pub fn pattern(settings: &JournalSettings) -> Result<Pattern, AppError> {
+    Pattern::parse(settings.path.as_deref().unwrap_or(DEFAULT_PATTERN))
+}
+
+/// parse_date
+/// 解析 `YYYY-MM-DD`；缺省为今天
+pub fn parse_date(date: Option<&str>) -> Result<NaiveDate, AppError> {
+    match date.map(str::trim).filter(|d| !d.is_empty()) {
+        Some(d) => NaiveDate::parse_from_str(d, DATE_FORMAT)
+            .map_err(|_| AppError::InvalidInput(format!("无效的日期: {}", d))),
+        None => Ok(Local::now().date_naive()),
+    }
+}
+
+/// 工作区中已有日记的日期 / Dates that have a note in the workspace
+pub fn dates(root: &Path, pattern: &Pattern) -> BTreeSet<NaiveDate> {
+    fn walk(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                if depth > 1 {
+                    walk(&path, depth - 1, files);
+                }
+            } else if depth == 1 {
+                files.push(path);
+            }
+        }
+    }
+    let mut files = Vec::new();
+    let depth = pattern.depth - pattern.base.components().count();
+    walk(&root.join(&pattern.base), depth, &mut files);
+    files
+        .iter()
+        .filter_map(|f| f.strip_prefix(root).ok())
+        .filter_map(|rel| pattern.date_of(&crate::git::display(rel)))
+        .collect()
+}
+
+/// 日记链接 / A dated note
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct JournalLink {
+    pub date: String,
+    pub path: String,
+}
+
+/// DailyNote
+/// 打开的日记及前后两篇已有的日记
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DailyNote {
+    pub date: String,
+    pub path: String,
+    /// 本次是否新建 / Whether the note was just created
+    pub created: bool,
+    pub previous: Option<JournalLink>,
+    pub next: Option<JournalLink>,
+}
+
+/// 前后最近的已有日期 / Nearest existing dates before and after `date`
+fn neighbours(
+    dates: &BTreeSet<NaiveDate>,
+    date: NaiveDate,
+) -> (Option<NaiveDate>, Option<NaiveDate>) {
+    (
+        dates.range(..date).next_back().copied(),
+        dates
+            .range((Bound::Excluded(date), Bound::Unbounded))
+            .next()
+            .copied(),
+    )
+}
+
+/// 导航行，如 `[← 2024-01-01](01-01.md) · [2024-01-05 →](01-05.md)`；缺少的一侧省略
+/// Navigation line linking the given neighbours; a missing side is left out
+fn nav_line(
+    root: &Path,
+    pattern: &Pattern,
+    date: NaiveDate,
+    prev: Option<NaiveDate>,
+    next: Option<NaiveDate>,
+) -> String {
+    let path = root.join(pattern.path_for(date));
+    let dir = path.parent().unwrap_or(root);
+    let link = |d: NaiveDate| crate::assets::relative_link(dir, &root.join(pattern.path_for(d)));
+    let mut parts = Vec::new();
+    if let Some(d) = prev {
+        parts.push(format!("[← {}]({})", d.format(DATE_FORMAT), link(d)));
+    }
+    if let Some(d) = next {
+        parts.push(format!("[{} →]({})", d.format(DATE_FORMAT), link(d)));
+    }
+    parts.join(" · ")
+}
+
+/// 重写已有日记中的导航行（只含指向日记的链接且带箭头的行）；没有导航行时只在默认标题后补上，其他内容不改动
+/// Regenerates the navigation line of an existing note, or adds one under the default title
+fn update_nav(root: &Path, pattern: &Pattern, date: NaiveDate, dates: &BTreeSet<NaiveDate>) {
+    let path = root.join(pattern.path_for(date));
+    let dir = path.parent().unwrap_or(root);
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let is_nav = |line: &str| {
+        let references = crate::markdown::references(line);
+        (line.contains('←') || line.contains('→'))
+            && !references.is_empty()
+            && references.iter().all(|r| {
+                crate::markdown::resolve_local(dir, &r.url)
+                    .map(|p| crate::assets::normalize_path(&p))
+                    .and_then(|p| p.strip_prefix(root).ok().map(crate::git::display))
+                    .is_some_and(|rel| pattern.date_of(&rel).is_some())
+            })
+    };
+    let (prev, next) = neighbours(dates, date);
+    let new = nav_line(root, pattern, date, prev, next);
+    let title = format!("# {}", date.format(DATE_FORMAT));
+    let updated = match text.lines().find(|line| is_nav(line)) {
+        Some(old) if old.trim() == new => return,
+        Some(old) => text.replacen(old, &new, 1),
+        // 默认内容在没有可链接的日记时不含导航行 / Default content omits the line when alone
+        None if text.lines().next() == Some(title.as_str()) => {
+            text.replacen(&title, &format!("{}\n\n{}", title, new), 1)
+        }
+        None => return,
+    };
+    if let Err(e) = std::fs::write(&path, updated) {
+        log::warn!("Failed to update journal links in {:?}: {}", path, e);
+    }
+}
+
+/// open
+/// 定位某天的日记，不存在时按模板（或默认内容）创建，并更新前后两篇的导航行
+pub fn open(
+    root: &Path,
+    settings: &JournalSettings,
+    date: NaiveDate,
+) -> Result<DailyNote, AppError> {
+    let pattern = pattern(settings)?;
+    let note_path = |date: NaiveDate| root.join(pattern.path_for(date));
+    let path = note_path(date);
+
+    let mut dates = dates(root, &pattern);
+    let created = !path.exists();
+    if created {
+        // 链接写入前已有的、最近的日记，而不是日历上的前后一天
+        // Link the nearest notes that exist before writing, not the calendar neighbours
+        let (prev, next) = neighbours(&dates, date);
+        let dir = path.parent().unwrap_or(root);
+        let date_of = |d: Option<NaiveDate>| {
+            d.map(|d| d.format(DATE_FORMAT).to_string())
+                .unwrap_or_default()
+        };
+        let link_of = |d: Option<NaiveDate>| {
+            d.map(|d| crate::assets::relative_link(dir, &note_path(d)))
+                .unwrap_or_default()
+        };
+        let nav = nav_line(root, &pattern, date, prev, next);
+        let mut vars = HashMap::new();
+        vars.insert("title".to_string(), date.format(DATE_FORMAT).to_string());
+        vars.insert("prev_date".to_string(), date_of(prev));
+        vars.insert("prev_link".to_string(), link_of(prev));
+        vars.insert("next_date".to_string(), date_of(next));
+        vars.insert("next_link".to_string(), link_of(next));
+        let text = if nav.is_empty() {
+            DEFAULT_CONTENT_ALONE
+        } else {
+            DEFAULT_CONTENT
+        };
+        vars.insert("nav".to_string(), nav);
+
+        let content = match settings.template.as_deref() {
+            Some(template) => crate::templates::render(root, &path, template, &vars, Some(date))?.1,
+            None => {
+                let time = date.and_time(Local::now().time());
+                let mut all = crate::templates::builtin_vars(dir, date);
+                all.extend(vars);
+                crate::templates::expand(text, &time, &all)?
+            }
+        };
+        crate::templates::write_new(&path, &content)?;
+
+        dates.insert(date);
+        for neighbour in [prev, next].into_iter().flatten() {
+            update_nav(root, &pattern, neighbour, &dates);
+        }
+    }
+
+    let (previous, next) = neighbours(&dates, date);
+    let to_link = |d: NaiveDate| JournalLink {
+        date: d.format(DATE_FORMAT).to_string(),
+        path: crate::git::display(&note_path(d)),
+    };
+    Ok(DailyNote {
+        date: date.format(DATE_FORMAT).to_string(),
+        path: crate::git::display(&path),
+        created,
+        previous: previous.map(to_link),
+        next: next.map(to_link),
+    })
+}
+
+/// calendar
+/// 某年（`YYYY`）或某月（`YYYY-MM`）中有日记的日期
+pub fn calendar(
+    root: &Path,
+    settings: &JournalSettings,
+    period: &str,
+) -> Result<Vec<String>, AppError> {
+    let invalid = || AppError::InvalidInput(format!("无效的日期范围: {}", period));
+    let (year, month) = match period.trim().split_once('-') {
+        Some((y, m)) => (y, Some(m)),
+        None => (period.trim(), None),
+    };
+    let year: i32 = year.parse().map_err(|_| invalid())?;
+    let month: Option<u32> = match month {
+        Some(m) => Some(
+            m.parse()
+                .ok()
+                .filter(|m| (1..=12).contains(m))
+                .ok_or_else(invalid)?,
+        ),
+        None => None,
+    };
+
+    let pattern = pattern(settings)?;
+    Ok(dates(root, &pattern)
+        .into_iter()
+        .filter(|d| d.year() == year && month.unwrap_or(d.month()) == d.month())
+        .map(|d| d.format(DATE_FORMAT).to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn parse_rejects_invalid_patterns() {
+        for text in [
+            "",
+            "/abs/{yyyy}-{mm}-{dd}.md",
+            "../{yyyy}-{mm}-{dd}.md",
+            "journal/{yyyy}-{mm}-{dd}.txt",
+            "journal/{yyyy}-{mm}.md",
+            "journal/{mm}-{dd}.md",
+        ] {
+            assert!(Pattern::parse(text).is_err(), "{}", text);
+        }
+        let pattern = Pattern::parse("./journal\\{yyyy}\\{mm}-{dd}.md").unwrap();
+        assert_eq!(pattern.base, PathBuf::from("journal"));
+        assert_eq!(pattern.depth, 3);
+    }
+
+    #[test]
+    fn date_of_inverts_path_for() {
+        let pattern = Pattern::parse(DEFAULT_PATTERN).unwrap();
+        let day = date(2024, 2, 29);
+        assert_eq!(pattern.path_for(day), "journal/2024/2024-02-29.md");
+        assert_eq!(pattern.date_of(&pattern.path_for(day)), Some(day));
+        // 重复的年份不一致、日期无效、不匹配 / Disagreeing years, invalid dates, no match
+        assert_eq!(pattern.date_of("journal/2023/2024-02-29.md"), None);
+        assert_eq!(pattern.date_of("journal/2023/2023-02-29.md"), None);
+        assert_eq!(pattern.date_of("journal/2024/notes.md"), None);
+    }
+
+    #[test]
+    fn date_of_pivots_two_digit_years() {
+        let pattern = Pattern::parse("j/{yy}{mm}{dd}.md").unwrap();
+        assert_eq!(pattern.date_of("j/240105.md"), Some(date(2024, 1, 5)));
+        assert_eq!(pattern.date_of("j/991231.md"), Some(date(1999, 12, 31)));
+        assert_eq!(pattern.path_for(date(1999, 12, 31)), "j/991231.md");
+    }
+}
@@ -1,24 +1,43 @@
 use futures_util::StreamExt;
 use once_cell::sync::Lazy;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 use tauri::{Emitter, Manager};
 
+mod assets;
+mod cli;
+mod config;
 mod error;
-// 注意：error::AppError 已定义，将在后续版本中使用 / Note: error::AppError is defined and will be used in future versions
-// use error::AppError;
+mod export;
+mod files;
+mod git;
+mod import;
+mod journal;
+mod markdown;
+mod recent;
+mod templates;
 
-// 全局工作区根路径（线程安全） / Global workspace root path (thread-safe)
-static WORKSPACE_ROOT: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+/// 窗口打开的工作区及其配置 / A window's workspace root and its loaded configuration
+struct Workspace {
+    root: String,
+    config: Arc<config::WorkspaceConfig>,
+    /// 配置文件读取或解析失败的原因 / Why the config file could not be loaded
+    config_error: Option<String>,
+}
+
+// 各窗口的工作区（按窗口标签，线程安全） / Workspace per window label (thread-safe)
+static WORKSPACES: Lazy<RwLock<HashMap<String, Workspace>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 主窗口标签 / Label of the main window
+const MAIN_WINDOW: &str = "main";
 
 // 文件大小限制：10MB / File size limit: 10MB
-// 注意：此常量将在文件操作中使用 / Note: This constant will be used in file operations
-#[allow(dead_code)]
 const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
 
 // 检查文件大小 / Check file size
-// 注意：此函数将在文件打开时使用 / Note: This function will be used when opening files
-#[allow(dead_code)]
 fn check_file_size(path: &Path) -> Result<(), String> {
     if let Ok(metadata) = std::fs::metadata(path) {
         let size = metadata.len();
@@ -37,36 +56,47 @@ pub fn run() {
     // 获取命令行参数 / Get command line arguments
     let args: Vec<String> = std::env::args().collect();
 
+    // 子命令以无窗口模式运行，不启动 Tauri / Subcommands run headless without starting Tauri
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+        .plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
             // 当尝试打开第二个实例时，会调用这个回调 / This callback is called when trying to open a second instance
-            // args 是新实例的命令行参数 / args are the command line arguments of the new instance
-            if args.len() > 1 {
-                let file_path = args[1].clone();
-                if file_path.ends_with(".md") || file_path.ends_with(".markdown") {
-                    // 转换为绝对路径并规范化路径分隔符 / Convert to absolute path and normalize path separators
-                    let abs_path = if std::path::Path::new(&file_path).is_absolute() {
-                        file_path.replace('\\', "/")
-                    } else {
-                        std::env::current_dir()
-                            .ok()
-                            .and_then(|cwd| cwd.join(&file_path).canonicalize().ok())
-                            .and_then(|p| p.to_str().map(|s| s.replace('\\', "/")))
-                            .unwrap_or_else(|| file_path.replace('\\', "/"))
-                    };
-                    // 发送事件到前端打开文件 / Send event to frontend to open file
-                    app.emit("open-file", abs_path).ok();
-                }
+            // args 与 cwd 是新实例的命令行参数和工作目录 / args and cwd belong to the new instance
+            let request = cli::open::parse(&args, Path::new(&cwd));
+            if request.new_window && !request.is_empty() {
+                // 在异步任务中建窗，避免阻塞事件循环 / Build the window off the event-loop callback
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = create_document_window(&app, request) {
+                        log::warn!("Failed to open new window: {}", e);
+                    }
+                });
+                return;
+            }
+            if !request.is_empty() {
+                // 发送事件到前端打开文件（前端未就绪时排队） / Send to frontend, queued until it is ready
+                dispatch_open(app, MAIN_WINDOW, request);
             }
             // 聚焦到已有窗口 / Focus to existing window
-            if let Some(window) = app.get_webview_window("main") {
+            if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
                 window.show().ok();
                 window.set_focus().ok();
             }
         }))
+        .on_window_event(|window, event| {
+            // 窗口关闭后释放其工作区、监听器等状态 / Drop per-window state once a window is gone
+            if let tauri::WindowEvent::Destroyed = event {
+                forget_window(window.label());
+            }
+        })
+        .register_asynchronous_uri_scheme_protocol(THUMBNAIL_SCHEME, thumbnail_protocol)
+        .register_asynchronous_uri_scheme_protocol(assets::protocol::SCHEME, asset_protocol)
         .setup(move |app| {
             if cfg!(debug_assertions) {
                 app.handle().plugin(
@@ -76,33 +106,12 @@ pub fn run() {
                 )?;
             }
 
-            // 处理初始命令行参数（如果有传入文件路径） / Handle initial command line arguments (if file path is passed)
-            if args.len() > 1 {
-                let file_path = args[1].clone();
-                // 验证是否为 .md 文件 / Verify if it's a .md file
-                if file_path.ends_with(".md") || file_path.ends_with(".markdown") {
-                    // 转换为绝对路径并规范化路径分隔符 / Convert to absolute path and normalize path separators
-                    let abs_path = if std::path::Path::new(&file_path).is_absolute() {
-                        file_path.replace('\\', "/")
-                    } else {
-                        std::env::current_dir()
-                            .ok()
-                            .and_then(|cwd| cwd.join(&file_path).canonicalize().ok())
-                            .and_then(|p| p.to_str().map(|s| s.replace('\\', "/")))
-                            .unwrap_or_else(|| file_path.replace('\\', "/"))
-                    };
-
-                    // 发送事件到前端，让前端打开这个文件 / Send event to frontend to let it open this file
-                    let app_handle = app.handle().clone();
-                    let path_to_open = abs_path.clone();
-
-                    // 延迟发送事件，确保前端已准备好 / Delay sending event to ensure frontend is ready
-                    tauri::async_runtime::spawn(async move {
-                        // 使用异步延迟，确保前端已准备好 / Use async delay to ensure frontend is ready
-                        tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
-                        app_handle.emit("open-file", path_to_open).ok();
-                    });
-                }
+            // 处理初始命令行参数（文件、文件夹与选项），待前端就绪后发送
+            // Handle initial command line arguments; delivered once the frontend is ready
+            let cwd = std::env::current_dir().unwrap_or_default();
+            let request = cli::open::parse(&args, &cwd);
+            if !request.is_empty() {
+                dispatch_open(app.handle(), MAIN_WINDOW, request);
             }
 
             Ok(())
@@ -120,21 +129,68 @@ pub fn run() {
             watch_stop,
             secret_set,
             secret_get,
-            secret_delete
+            secret_delete,
+            export_html,
+            export_docx,
+            export_epub,
+            export_pdf,
+            import_document,
+            save_image,
+            audit_assets,
+            cleanup_orphans,
+            get_thumbnail,
+            localize_remote_images,
+            git_status,
+            git_stage,
+            git_unstage,
+            git_diff,
+            git_commit,
+            summarize_changes,
+            file_history,
+            read_at_revision,
+            git_blame,
+            frontend_ready,
+            open_in_new_window,
+            recent_list,
+            recent_add,
+            recent_pin,
+            recent_remove,
+            recent_clear,
+            workspace_config,
+            list_templates,
+            create_from_template,
+            open_daily_note,
+            journal_dates,
+            copy_path,
+            batch_fs
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
 #[tauri::command]
-async fn list_md_files(dir: String) -> Result<Vec<String>, String> {
-    set_workspace_root(&dir);
-    fn walk_collect(p: PathBuf, out: &mut Vec<String>) {
+async fn list_md_files(window: tauri::WebviewWindow, dir: String) -> Result<Vec<String>, String> {
+    set_workspace_root(window.label(), &dir);
+    let (_, config) = window_workspace(window.label())?;
+    let root = PathBuf::from(&dir);
+    fn walk_collect(
+        p: PathBuf,
+        root: &Path,
+        config: &config::WorkspaceConfig,
+        out: &mut Vec<String>,
+    ) {
         if let Ok(rd) = std::fs::read_dir(&p) {
             for e in rd.flatten() {
                 let path = e.path();
+                // 跳过工作区配置中忽略的路径 / Skip paths matched by the workspace ignore globs
+                if path
+                    .strip_prefix(root)
+                    .is_ok_and(|rel| config.is_ignored(rel))
+                {
+                    continue;
+                }
                 if path.is_dir() {
-                    walk_collect(path, out);
+                    walk_collect(path, root, config, out);
                 } else if let Some(ext) = path.extension() {
                     let e = ext.to_string_lossy().to_lowercase();
                     if e == "md" || e == "markdown" {
@@ -147,37 +203,72 @@ async fn list_md_files(dir: String) -> Result<Vec<String>, String> {
         }
     }
     let mut out = Vec::new();
-    walk_collect(PathBuf::from(dir), &mut out);
+    walk_collect(root.clone(), &root, &config, &mut out);
     Ok(out)
 }
 
-fn set_workspace_root(dir: &str) {
-    if let Ok(mut guard) = WORKSPACE_ROOT.write() {
-        *guard = Some(dir.to_string());
+/// 设置窗口的工作区并读取其 `.markdownmonkey/config.toml`
+/// Sets a window's workspace and (re)loads its config file
+fn set_workspace_root(label: &str, dir: &str) {
+    let (config, config_error) = match config::load(Path::new(dir)) {
+        Ok(config) => (config, None),
+        Err(e) => {
+            log::warn!("Failed to load workspace config in {}: {}", dir, e);
+            (config::WorkspaceConfig::default(), Some(e.to_string()))
+        }
+    };
+    if let Ok(mut guard) = WORKSPACES.write() {
+        guard.insert(
+            label.to_string(),
+            Workspace {
+                root: dir.to_string(),
+                config: Arc::new(config),
+                config_error,
+            },
+        );
     }
 }
 
-fn ensure_in_workspace(path: &Path) -> Result<(), String> {
+/// 工作区配置及加载错误 / Workspace config together with its load error
+#[derive(serde::Serialize)]
+struct WorkspaceSettings {
+    root: String,
+    config: config::WorkspaceConfig,
+    error: Option<String>,
+}
+
+/// workspace_config
+/// 当前窗口工作区的配置（覆盖全局设置的部分）；尚未打开工作区时返回 null
+#[tauri::command]
+async fn workspace_config(
+    window: tauri::WebviewWindow,
+) -> Result<Option<WorkspaceSettings>, String> {
+    let guard = WORKSPACES
+        .read()
+        .map_err(|_| "工作区锁定失败".to_string())?;
+    Ok(guard.get(window.label()).map(|w| WorkspaceSettings {
+        root: w.root.clone(),
+        config: (*w.config).clone(),
+        error: w.config_error.clone(),
+    }))
+}
+
+/// 检查路径是否在调用窗口的工作区内（不接受其他窗口的工作区）
+/// The path must lie inside the calling window's own workspace
+fn ensure_in_workspace(label: &str, path: &Path) -> Result<(), String> {
     // 先规范化路径，这会解析符号链接
     let p = std::fs::canonicalize(path).map_err(|e| {
         log::warn!("Failed to canonicalize path: {:?}", e);
         "无法访问指定路径".to_string()
     })?;
 
-    let root = WORKSPACE_ROOT
-        .read()
-        .map_err(|_| "工作区锁定失败".to_string())?
-        .clone()
-        .ok_or_else(|| "未设置工作区".to_string())?;
-
+    let (root, _) = window_workspace(label)?;
     let root_c = std::fs::canonicalize(&root).map_err(|e| {
-        log::warn!("Failed to canonicalize workspace root: {:?}", e);
-        "工作区路径无效".to_string()
+        log::warn!("Failed to canonicalize workspace root {:?}: {:?}", root, e);
+        "无法访问工作区".to_string()
     })?;
-
-    // 检查规范化后的路径是否在工作区内
     if !p.starts_with(&root_c) {
-        log::warn!("Path {:?} is outside workspace {:?}", p, root_c);
+        log::warn!("Path {:?} is outside workspace {:?} of {}", p, root, label);
         return Err("路径不在工作区内".to_string());
     }
 
@@ -194,32 +285,113 @@ fn ensure_in_workspace(path: &Path) -> Result<(), String> {
 }
 
 #[tauri::command]
-async fn create_empty_file(path: String) -> Result<(), String> {
+async fn create_empty_file(window: tauri::WebviewWindow, path: String) -> Result<(), String> {
     use std::io::Write;
     if let Some(parent) = std::path::Path::new(&path).parent() {
-        ensure_in_workspace(parent)?;
+        ensure_in_workspace(window.label(), parent)?;
         std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
-    ensure_in_workspace(Path::new(&path))?;
+    ensure_in_workspace(window.label(), Path::new(&path))?;
     let mut f = std::fs::File::create(&path).map_err(|e| e.to_string())?;
     f.write_all(b"").map_err(|e| e.to_string())?;
     Ok(())
 }
 
+/// list_templates
+/// 工作区 `.markdownmonkey/templates/` 中的模板名
+#[tauri::command]
+async fn list_templates(window: tauri::WebviewWindow, dir: String) -> Result<Vec<String>, String> {
+    let root = Path::new(&dir);
+    ensure_in_workspace(window.label(), root)?;
+    Ok(templates::list(root)?)
+}
+
+/// create_from_template
+/// 用工作区模板创建新文件，展开日期、标题、作者、编号等变量（文件名中也可使用），返回实际路径
+#[tauri::command]
+async fn create_from_template(
+    window: tauri::WebviewWindow,
+    path: String,
+    template: String,
+    vars: Option<HashMap<String, String>>,
+) -> Result<String, String> {
+    let target = assets::normalize_path(Path::new(&path));
+    let parent = target
+        .parent()
+        .ok_or_else(|| "无效的文件路径".to_string())?;
+    // 目录可能尚不存在，检查最近的已有上级；缺失的目录由写入时创建
+    // The folder may not exist yet: check its nearest existing ancestor, writing creates the rest
+    ensure_target_in_workspace(window.label(), parent)?;
+    let (root, _) = window_workspace(window.label())?;
+    let vars = vars.unwrap_or_default();
+    let label = window.label().to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        // 文件名展开后再校验一次最终路径 / Check the final path again once the file name is expanded
+        templates::create(&root, &target, &template, &vars, &|p| {
+            ensure_target_in_workspace(&label, p)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map(|p| p.to_string_lossy().replace('\\', "/"))
+    .map_err(String::from)
+}
+
+/// 窗口的工作区根目录与配置 / Root and config of a window's workspace
+fn window_workspace(label: &str) -> Result<(PathBuf, Arc<config::WorkspaceConfig>), String> {
+    let guard = WORKSPACES
+        .read()
+        .map_err(|_| "工作区锁定失败".to_string())?;
+    guard
+        .get(label)
+        .map(|w| (PathBuf::from(&w.root), w.config.clone()))
+        .ok_or_else(|| "未设置工作区".to_string())
+}
+
+/// open_daily_note
+/// 打开某天（`YYYY-MM-DD`，缺省为今天）的日记，不存在时按配置的路径模式与模板创建
 #[tauri::command]
-async fn rename_path(src: String, dst: String) -> Result<(), String> {
-    ensure_in_workspace(Path::new(&src))?;
+async fn open_daily_note(
+    window: tauri::WebviewWindow,
+    date: Option<String>,
+) -> Result<journal::DailyNote, String> {
+    let (root, config) = window_workspace(window.label())?;
+    ensure_in_workspace(window.label(), &root)?;
+    let date = journal::parse_date(date.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || journal::open(&root, &config.journal, date))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(String::from)
+}
+
+/// journal_dates
+/// 某年（`YYYY`）或某月（`YYYY-MM`）中有日记的日期，供日历标记
+#[tauri::command]
+async fn journal_dates(
+    window: tauri::WebviewWindow,
+    period: String,
+) -> Result<Vec<String>, String> {
+    let (root, config) = window_workspace(window.label())?;
+    tauri::async_runtime::spawn_blocking(move || journal::calendar(&root, &config.journal, &period))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(String::from)
+}
+
+#[tauri::command]
+async fn rename_path(window: tauri::WebviewWindow, src: String, dst: String) -> Result<(), String> {
+    ensure_in_workspace(window.label(), Path::new(&src))?;
     if let Some(parent) = Path::new(&dst).parent() {
-        ensure_in_workspace(parent)?;
+        ensure_in_workspace(window.label(), parent)?;
     }
     std::fs::rename(src, dst).map_err(|e| e.to_string())?;
     Ok(())
 }
 
 #[tauri::command]
-async fn delete_path(target: String) -> Result<(), String> {
+async fn delete_path(window: tauri::WebviewWindow, target: String) -> Result<(), String> {
     let p = std::path::Path::new(&target);
-    ensure_in_workspace(p)?;
+    ensure_in_workspace(window.label(), p)?;
     if p.is_dir() {
         std::fs::remove_dir_all(p).map_err(|e| e.to_string())?
     } else {
@@ -228,8 +400,536 @@ async fn delete_path(target: String) -> Result<(), String> {
     Ok(())
 }
 
-static WATCHER: Lazy<std::sync::Mutex<Option<notify::RecommendedWatcher>>> =
-    Lazy::new(|| std::sync::Mutex::new(None));
+/// copy_path
+/// 复制文件或文件夹（递归）到工作区内的目标路径；可指定冲突处理方式（跳过、覆盖、自动改名）与是否改写相对链接
+#[tauri::command]
+async fn copy_path(
+    window: tauri::WebviewWindow,
+    src: String,
+    dst: String,
+    options: Option<files::CopyOptions>,
+) -> Result<files::CopyReport, String> {
+    ensure_in_workspace(window.label(), Path::new(&src))?;
+    let parent = Path::new(&dst)
+        .parent()
+        .ok_or_else(|| "无效的目标路径".to_string())?;
+    ensure_in_workspace(window.label(), parent)?;
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        files::copy(Path::new(&src), Path::new(&dst), &options)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(String::from)
+}
+
+/// 检查可能尚不存在的路径：取其最近的已存在上级目录做沙箱检查
+/// Sandbox check for a path that may not exist yet, via its nearest existing ancestor
+fn ensure_target_in_workspace(label: &str, path: &Path) -> Result<(), String> {
+    let path = assets::normalize_path(path);
+    let existing = path
+        .ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| "无法访问指定路径".to_string())?;
+    ensure_in_workspace(label, existing)
+}
+
+/// batch_fs
+/// 批量执行新建、重命名、复制、删除：先整体校验，任一步失败时撤销已完成的步骤，返回每个操作的结果
+#[tauri::command]
+async fn batch_fs(
+    window: tauri::WebviewWindow,
+    ops: Vec<files::batch::FsOp>,
+) -> Result<files::batch::BatchReport, String> {
+    let label = window.label().to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        files::batch::run(&ops, &|p| ensure_target_in_workspace(&label, p))
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// export_html
+/// 将文档或文件夹导出为单个自包含 HTML 文件（内联本地图片、主题 CSS 与目录）
+#[tauri::command]
+async fn export_html(
+    window: tauri::WebviewWindow,
+    path: String,
+    mut options: export::html::HtmlExportOptions,
+) -> Result<String, String> {
+    let src = Path::new(&path);
+    ensure_in_workspace(window.label(), src)?;
+    options.window = window.label().to_string();
+    apply_export_config(window.label(), &mut options);
+    let output = export::html::export(src, &options)?;
+    Ok(output.to_string_lossy().replace('\\', "/"))
+}
+
+/// 未指定的主题与 CSS 取自工作区配置 / Fills theme and CSS from the workspace config when unset
+fn apply_export_config(label: &str, options: &mut export::html::HtmlExportOptions) {
+    let Ok((root, config)) = window_workspace(label) else {
+        return;
+    };
+    if options.theme.is_none() {
+        options.theme = config.export.theme.clone();
+    }
+    if options.css_path.is_none() {
+        options.css_path = config
+            .export
+            .css
+            .as_ref()
+            .map(|css| root.join(css).to_string_lossy().to_string());
+    }
+}
+
+/// export_docx
+/// 将 Markdown 转换为 Word 文档（可选参考 .docx 提供样式）
+#[tauri::command]
+async fn export_docx(
+    window: tauri::WebviewWindow,
+    path: String,
+    mut options: export::docx::DocxExportOptions,
+) -> Result<String, String> {
+    let src = Path::new(&path);
+    ensure_in_workspace(window.label(), src)?;
+    options.window = window.label().to_string();
+    let output = export::docx::export(src, &options)?;
+    Ok(output.to_string_lossy().replace('\\', "/"))
+}
+
+/// export_epub
+/// 将文件夹中的章节打包为 EPUB 3 电子书（顺序来自 SUMMARY.md 或 front matter）
+#[tauri::command]
+async fn export_epub(
+    window: tauri::WebviewWindow,
+    dir: String,
+    mut manifest: export::epub::EpubManifest,
+) -> Result<String, String> {
+    let src = Path::new(&dir);
+    ensure_in_workspace(window.label(), src)?;
+    manifest.window = window.label().to_string();
+    if !src.is_dir() {
+        return Err("请选择包含章节的文件夹".to_string());
+    }
+    let output = export::epub::export(src, &manifest)?;
+    Ok(output.to_string_lossy().replace('\\', "/"))
+}
+
+/// export_pdf
+/// 在后端排版并导出可选中文本的 PDF（嵌入字体子集、页眉页脚、页码与书签）
+#[tauri::command]
+async fn export_pdf(
+    window: tauri::WebviewWindow,
+    path: String,
+    mut options: export::pdf::PdfExportOptions,
+) -> Result<String, String> {
+    let src = Path::new(&path);
+    ensure_in_workspace(window.label(), src)?;
+    options.window = window.label().to_string();
+    let output = export::pdf::export(src, &options)?;
+    Ok(output.to_string_lossy().replace('\\', "/"))
+}
+
+/// import_document
+/// 将 HTML / DOCX 转换为 Markdown；新文件写在 target_dir（缺省为源文件所在目录），
+/// 图片提取到新文件旁的 assets/ 目录
+#[tauri::command]
+async fn import_document(
+    window: tauri::WebviewWindow,
+    path: String,
+    target_dir: Option<String>,
+) -> Result<String, String> {
+    let src = Path::new(&path);
+    if !src.is_file() {
+        return Err("文件不存在".to_string());
+    }
+    let dir = match &target_dir {
+        Some(d) => PathBuf::from(d),
+        None => src.parent().map(Path::to_path_buf).unwrap_or_default(),
+    };
+    ensure_in_workspace(window.label(), &dir)?;
+    let output = import::import_document(src, &dir)?;
+    Ok(output.to_string_lossy().replace('\\', "/"))
+}
+
+/// save_image
+/// 保存粘贴/拖入的图片到文档旁的资源目录（按内容去重），返回插入 Markdown 的相对路径
+#[tauri::command]
+async fn save_image(
+    window: tauri::WebviewWindow,
+    bytes: Vec<u8>,
+    doc_path: String,
+    mut options: assets::SaveImageOptions,
+) -> Result<String, String> {
+    let doc = Path::new(&doc_path);
+    let doc_dir = doc.parent().ok_or_else(|| "无效的文档路径".to_string())?;
+    ensure_in_workspace(window.label(), doc_dir)?;
+    options.window = window.label().to_string();
+    if options.assets_dir.is_none() {
+        options.assets_dir = window_workspace(window.label())?.1.assets_dir.clone();
+    }
+    Ok(assets::save_image(&bytes, doc, &options)?)
+}
+
+/// audit_assets
+/// 比对工作区内的图片与所有 Markdown 引用，列出孤立图片与缺失引用
+#[tauri::command]
+async fn audit_assets(
+    window: tauri::WebviewWindow,
+    dir: String,
+) -> Result<assets::audit::AssetReport, String> {
+    let dir = Path::new(&dir);
+    ensure_in_workspace(window.label(), dir)?;
+    let (root, _) = window_workspace(window.label())?;
+    Ok(assets::audit::audit(&root, dir)?)
+}
+
+/// cleanup_orphans
+/// 将孤立图片移入回收站（可只处理指定文件），返回已移除的路径
+#[tauri::command]
+async fn cleanup_orphans(
+    window: tauri::WebviewWindow,
+    dir: String,
+    paths: Option<Vec<String>>,
+) -> Result<Vec<String>, String> {
+    let dir = Path::new(&dir);
+    ensure_in_workspace(window.label(), dir)?;
+    let (root, _) = window_workspace(window.label())?;
+    Ok(assets::audit::cleanup_orphans(
+        &root,
+        dir,
+        paths.as_deref(),
+    )?)
+}
+
+/// localize_remote_images
+/// 下载文档中的远程图片到资源目录并改写链接，逐个报告成功或失败原因
+#[tauri::command]
+async fn localize_remote_images(
+    window: tauri::WebviewWindow,
+    path: String,
+    options: Option<assets::remote::LocalizeOptions>,
+) -> Result<assets::remote::LocalizeReport, String> {
+    let doc = Path::new(&path);
+    ensure_in_workspace(window.label(), doc)?;
+    let mut options = options.unwrap_or_default();
+    options.window = window.label().to_string();
+    if options.assets_dir.is_none() {
+        options.assets_dir = window_workspace(window.label())?.1.assets_dir.clone();
+    }
+    Ok(assets::remote::localize(doc, &options).await?)
+}
+
+/// 缩略图协议名；Windows/Android 上以 `http://mm-thumb.localhost/` 访问
+/// Thumbnail URI scheme; served as `http://mm-thumb.localhost/` on Windows/Android
+const THUMBNAIL_SCHEME: &str = "mm-thumb";
+
+fn thumbnail_url(key: &str) -> String {
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://{}.localhost/{}", THUMBNAIL_SCHEME, key)
+    } else {
+        format!("{}://localhost/{}", THUMBNAIL_SCHEME, key)
+    }
+}
+
+/// mm-thumb 协议：只提供缩略图缓存目录中的文件
+fn thumbnail_protocol<R: tauri::Runtime>(
+    ctx: tauri::UriSchemeContext<'_, R>,
+    request: tauri::http::Request<Vec<u8>>,
+    responder: tauri::UriSchemeResponder,
+) {
+    let name = request.uri().path().trim_start_matches('/').to_string();
+    let cache = ctx.app_handle().path().app_cache_dir();
+    tauri::async_runtime::spawn_blocking(move || {
+        let bytes = match cache {
+            Ok(cache) if assets::thumbnail::is_cache_key(&name) => {
+                std::fs::read(assets::thumbnail::cache_dir(&cache).join(&name)).ok()
+            }
+            _ => None,
+        };
+        let response = match bytes {
+            // 键包含修改时间，内容不会变化，可长期缓存
+            Some(bytes) => tauri::http::Response::builder()
+                .header(tauri::http::header::CONTENT_TYPE, "image/png")
+                .header(
+                    tauri::http::header::CACHE_CONTROL,
+                    "max-age=31536000, immutable",
+                )
+                .body(bytes),
+            None => tauri::http::Response::builder()
+                .status(tauri::http::StatusCode::NOT_FOUND)
+                .body(Vec::new()),
+        };
+        responder.respond(response.unwrap_or_default());
+    });
+}
+
+/// mm-asset 协议：按当前文档解析工作区内的资源，支持 Range 请求
+fn asset_protocol<R: tauri::Runtime>(
+    ctx: tauri::UriSchemeContext<'_, R>,
+    request: tauri::http::Request<Vec<u8>>,
+    responder: tauri::UriSchemeResponder,
+) {
+    let label = ctx.webview_label().to_string();
+    // 开发时页面来自 devUrl / Pages come from devUrl during development
+    let dev_origin = cfg!(debug_assertions)
+        .then(|| ctx.app_handle().config().build.dev_url.clone())
+        .flatten()
+        .map(|url| url.origin().ascii_serialization());
+    tauri::async_runtime::spawn_blocking(move || {
+        responder.respond(asset_response(&label, dev_origin.as_deref(), &request));
+    });
+}
+
+fn asset_response(
+    label: &str,
+    dev_origin: Option<&str>,
+    request: &tauri::http::Request<Vec<u8>>,
+) -> tauri::http::Response<Vec<u8>> {
+    use assets::protocol::{self, ByteRange};
+    use tauri::http::{header, Response, StatusCode};
+
+    let error = |status: StatusCode, message: String| {
+        log::warn!("mm-asset {}: {}", status, message);
+        Response::builder()
+            .status(status)
+            .body(message.into_bytes())
+            .unwrap_or_default()
+    };
+
+    let (reference, document) =
+        protocol::parse_request(request.uri().path(), request.uri().query());
+    let path = match protocol::resolve(&reference, document.as_deref()) {
+        Ok(p) => p,
+        Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+    };
+    if let Err(e) = ensure_in_workspace(label, &path) {
+        return error(StatusCode::FORBIDDEN, e);
+    }
+    let len = match std::fs::metadata(&path) {
+        Ok(m) if m.is_file() => m.len(),
+        _ => return error(StatusCode::NOT_FOUND, reference),
+    };
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok());
+    let mut builder = Response::builder()
+        .header(header::CONTENT_TYPE, protocol::mime_type(&path))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::VARY, "origin");
+    // 只允许应用自身的页面用 fetch() 读取（例如前端压缩图片）
+    // Only the app's own pages may read the body via fetch(), e.g. to compress images
+    if let Some(origin) = request
+        .headers()
+        .get(header::ORIGIN)
+        .and_then(|v| v.to_str().ok())
+        .filter(|o| protocol::is_app_origin(o, dev_origin))
+    {
+        builder = builder.header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    }
+    let response = match protocol::parse_range(range, len) {
+        ByteRange::Full => match std::fs::read(&path) {
+            Ok(bytes) => builder.body(bytes),
+            Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        },
+        ByteRange::Partial(start, end) => match protocol::read_range(&path, start, end) {
+            Ok(bytes) => builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, len),
+                )
+                .body(bytes),
+            Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        },
+        ByteRange::Unsatisfiable => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Vec::new()),
+    };
+    response.unwrap_or_default()
+}
+
+/// get_thumbnail
+/// 生成（或复用缓存的）本地图片缩略图，返回可直接用于 `<img>` 的协议地址
+#[tauri::command]
+async fn get_thumbnail(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    path: String,
+    size: u32,
+) -> Result<String, String> {
+    let source = PathBuf::from(&path);
+    ensure_in_workspace(window.label(), &source)?;
+    let cache = app.path().app_cache_dir().map_err(|e| e.to_string())?;
+    // 解码大图较慢，放到阻塞线程池 / Decoding is CPU-bound
+    let key = tauri::async_runtime::spawn_blocking(move || {
+        assets::thumbnail::thumbnail(&cache, &source, size)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(thumbnail_url(&key))
+}
+
+/// 前端注册 `open-file` 监听前收到的打开请求 / Open requests received before the frontend listens
+#[derive(Default)]
+struct PendingOpen {
+    ready: bool,
+    queue: Vec<cli::open::OpenRequest>,
+}
+
+static PENDING_OPEN: Lazy<std::sync::Mutex<HashMap<String, PendingOpen>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// 前端就绪则立即向该窗口发送 `open-file`，否则排队等待 `frontend_ready`
+/// Emits `open-file` to the window once its frontend is ready, otherwise queues it
+fn dispatch_open(app: &tauri::AppHandle, label: &str, request: cli::open::OpenRequest) {
+    let mut pending = PENDING_OPEN.lock().unwrap_or_else(|e| e.into_inner());
+    let pending = pending.entry(label.to_string()).or_default();
+    if pending.ready {
+        app.emit_to(label, "open-file", request).ok();
+    } else {
+        pending.queue.push(request);
+    }
+}
+
+/// frontend_ready
+/// 窗口前端已注册 `open-file` 监听：发送排队中的打开请求，之后的请求直接发送
+#[tauri::command]
+fn frontend_ready(window: tauri::WebviewWindow) {
+    let mut pending = PENDING_OPEN.lock().unwrap_or_else(|e| e.into_inner());
+    let pending = pending.entry(window.label().to_string()).or_default();
+    pending.ready = true;
+    for request in pending.queue.drain(..) {
+        window.emit_to(window.label(), "open-file", request).ok();
+    }
+}
+
+/// 新建文档窗口的序号 / Counter for document window labels
+static WINDOW_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 新建文档窗口，前端就绪后打开请求中的文件或文件夹，返回窗口标签
+/// Creates a document window and queues the request for it; returns the window label
+fn create_document_window(
+    app: &tauri::AppHandle,
+    request: cli::open::OpenRequest,
+) -> Result<String, String> {
+    let label = format!("doc-{}", WINDOW_COUNTER.fetch_add(1, Ordering::SeqCst) + 1);
+    let name = request
+        .files
+        .first()
+        .map(|f| f.path.as_str())
+        .or(request.folders.last().map(String::as_str))
+        .and_then(|p| Path::new(p).file_name())
+        .map(|n| n.to_string_lossy().to_string());
+    let title = match name {
+        Some(name) => format!("{} - MarkdownMonkey", name),
+        None => "MarkdownMonkey".to_string(),
+    };
+    // 先排队再建窗，避免前端先于请求就绪 / Queue first so the request cannot miss the new frontend
+    dispatch_open(app, &label, request);
+    let built = tauri::WebviewWindowBuilder::new(app, &label, tauri::WebviewUrl::default())
+        .title(title)
+        .inner_size(1000.0, 700.0)
+        .build();
+    if let Err(e) = built {
+        forget_window(&label);
+        return Err(e.to_string());
+    }
+    Ok(label)
+}
+
+/// 清理已关闭窗口的工作区、监听器与待打开请求 / Drops all state kept for a closed window
+fn forget_window(label: &str) {
+    if let Ok(mut workspaces) = WORKSPACES.write() {
+        workspaces.remove(label);
+    }
+    WATCHERS.lock().unwrap().remove(label);
+    PENDING_OPEN
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(label);
+    GIT_REFRESH_GENERATION.lock().unwrap().remove(label);
+}
+
+/// open_in_new_window
+/// 在新窗口中打开 Markdown 文件或文件夹（作为该窗口的工作区），返回窗口标签
+#[tauri::command]
+async fn open_in_new_window(app: tauri::AppHandle, path: String) -> Result<String, String> {
+    let p = assets::normalize_path(Path::new(&path));
+    if !p.is_absolute() {
+        return Err("请提供绝对路径".to_string());
+    }
+    let mut request = cli::open::OpenRequest::default();
+    if !request.push(&p, None, None) {
+        return Err("只能在新窗口中打开 Markdown 文件或文件夹".to_string());
+    }
+    create_document_window(&app, request)
+}
+
+fn recent_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path().app_data_dir().map_err(|e| e.to_string())
+}
+
+/// recent_list
+/// 最近打开的文件与工作区（固定的在前，已不存在的路径会被剔除）
+#[tauri::command]
+async fn recent_list(app: tauri::AppHandle) -> Result<Vec<recent::RecentEntry>, String> {
+    Ok(recent::list(&recent_dir(&app)?)?)
+}
+
+/// recent_add
+/// 记录打开了文件或工作区，返回更新后的列表
+#[tauri::command]
+async fn recent_add(
+    app: tauri::AppHandle,
+    path: String,
+    kind: recent::RecentKind,
+) -> Result<Vec<recent::RecentEntry>, String> {
+    let entries = recent::add(&recent_dir(&app)?, &path, kind)?;
+    // GTK 只能在主线程访问 / GTK must be used from the main thread
+    #[cfg(target_os = "linux")]
+    app.run_on_main_thread(move || recent::register_desktop(Path::new(&path)))
+        .ok();
+    Ok(entries)
+}
+
+/// recent_pin
+/// 固定或取消固定最近条目
+#[tauri::command]
+async fn recent_pin(
+    app: tauri::AppHandle,
+    path: String,
+    pinned: bool,
+) -> Result<Vec<recent::RecentEntry>, String> {
+    Ok(recent::set_pinned(&recent_dir(&app)?, &path, pinned)?)
+}
+
+/// recent_remove
+/// 从最近列表中移除一项
+#[tauri::command]
+async fn recent_remove(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<Vec<recent::RecentEntry>, String> {
+    Ok(recent::remove(&recent_dir(&app)?, &path)?)
+}
+
+/// recent_clear
+/// 清空未固定的最近条目，可只清空文件或工作区
+#[tauri::command]
+async fn recent_clear(
+    app: tauri::AppHandle,
+    kind: Option<recent::RecentKind>,
+) -> Result<Vec<recent::RecentEntry>, String> {
+    Ok(recent::clear(&recent_dir(&app)?, kind)?)
+}
+
+// 各窗口的文件监听器 / File-system watcher per window label
+static WATCHERS: Lazy<std::sync::Mutex<HashMap<String, notify::RecommendedWatcher>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
 
 // 简易加密存储：使用系统凭据管理器（Windows Credential Manager / macOS Keychain / Secret Service）
 #[tauri::command]
@@ -258,24 +958,237 @@ async fn secret_delete(service: String, key: String) -> Result<(), String> {
     }
 }
 
+/// 文件变化后延迟刷新 Git 状态，合并短时间内的多次事件
+/// Debounce window for refreshing git status after file-system events
+const GIT_REFRESH_DELAY_MS: u64 = 500;
+static GIT_REFRESH_GENERATION: Lazy<std::sync::Mutex<HashMap<String, u64>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+fn schedule_git_refresh(app: tauri::AppHandle, label: String, dir: String) {
+    let generation = {
+        let mut generations = GIT_REFRESH_GENERATION.lock().unwrap();
+        let generation = generations.entry(label.clone()).or_default();
+        *generation += 1;
+        *generation
+    };
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(tokio::time::Duration::from_millis(GIT_REFRESH_DELAY_MS)).await;
+        if GIT_REFRESH_GENERATION.lock().unwrap().get(&label) != Some(&generation) {
+            return;
+        }
+        let result =
+            tauri::async_runtime::spawn_blocking(move || git::status(Path::new(&dir))).await;
+        match result {
+            Ok(Ok(status)) => {
+                app.emit_to(label.as_str(), "git:changed", status).ok();
+            }
+            Ok(Err(e)) => log::warn!("Failed to refresh git status: {}", e),
+            Err(e) => log::warn!("Git status task failed: {}", e),
+        }
+    });
+}
+
+/// git_status
+/// 查询工作区所在 Git 仓库中有变化的文件；不在仓库内时返回 null
+#[tauri::command]
+async fn git_status(
+    window: tauri::WebviewWindow,
+    dir: String,
+) -> Result<Option<git::RepoStatus>, String> {
+    let root = PathBuf::from(&dir);
+    ensure_in_workspace(window.label(), &root)?;
+    tauri::async_runtime::spawn_blocking(move || git::status(&root))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(String::from)
+}
+
+/// 已删除的文件无法规范化，改为检查其所在目录
+/// Deleted files cannot be canonicalized, so their parent folder is checked instead
+fn ensure_git_path_in_workspace(label: &str, path: &Path) -> Result<(), String> {
+    if path.exists() {
+        ensure_in_workspace(label, path)
+    } else {
+        ensure_in_workspace(
+            label,
+            path.parent().ok_or_else(|| "无效的文件路径".to_string())?,
+        )
+    }
+}
+
+/// git_stage
+/// 将文件加入暂存区
+#[tauri::command]
+async fn git_stage(window: tauri::WebviewWindow, paths: Vec<String>) -> Result<(), String> {
+    for p in &paths {
+        ensure_git_path_in_workspace(window.label(), Path::new(p))?;
+    }
+    let paths: Vec<&Path> = paths.iter().map(Path::new).collect();
+    Ok(git::commit::stage(&paths)?)
+}
+
+/// git_unstage
+/// 将文件移出暂存区（工作区内容不变）
+#[tauri::command]
+async fn git_unstage(window: tauri::WebviewWindow, paths: Vec<String>) -> Result<(), String> {
+    for p in &paths {
+        ensure_git_path_in_workspace(window.label(), Path::new(p))?;
+    }
+    let paths: Vec<&Path> = paths.iter().map(Path::new).collect();
+    Ok(git::commit::unstage(&paths)?)
+}
+
+/// git_diff
+/// 对比工作区文件与 HEAD 中的版本（统一格式或左右对照）
+#[tauri::command]
+async fn git_diff(
+    window: tauri::WebviewWindow,
+    path: String,
+    options: Option<git::diff::DiffRequest>,
+) -> Result<git::diff::FileDiff, String> {
+    let p = Path::new(&path);
+    ensure_git_path_in_workspace(window.label(), p)?;
+    Ok(git::diff::diff_file(p, &options.unwrap_or_default())?)
+}
+
+/// git_commit
+/// 以暂存区内容创建提交
+#[tauri::command]
+async fn git_commit(
+    window: tauri::WebviewWindow,
+    dir: String,
+    message: String,
+) -> Result<git::commit::CommitInfo, String> {
+    let root = Path::new(&dir);
+    ensure_in_workspace(window.label(), root)?;
+    Ok(git::commit::commit(root, &message)?)
+}
+
+/// file_history
+/// 列出修改过该文档的提交（跟随重命名）
 #[tauri::command]
-async fn watch_start(app: tauri::AppHandle, dir: String) -> Result<(), String> {
+async fn file_history(
+    window: tauri::WebviewWindow,
+    path: String,
+    limit: Option<usize>,
+) -> Result<Vec<git::history::CommitEntry>, String> {
+    let p = PathBuf::from(&path);
+    ensure_git_path_in_workspace(window.label(), &p)?;
+    tauri::async_runtime::spawn_blocking(move || git::history::file_history(&p, limit))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(String::from)
+}
+
+/// read_at_revision
+/// 读取文档在指定版本中的内容（供只读打开）
+#[tauri::command]
+async fn read_at_revision(
+    window: tauri::WebviewWindow,
+    path: String,
+    rev: String,
+) -> Result<String, String> {
+    let p = PathBuf::from(&path);
+    ensure_git_path_in_workspace(window.label(), &p)?;
+    tauri::async_runtime::spawn_blocking(move || git::history::read_at_revision(&p, &rev))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(String::from)
+}
+
+/// git_blame
+/// 逐行追溯文档当前内容的作者与提交
+#[tauri::command]
+async fn git_blame(
+    window: tauri::WebviewWindow,
+    path: String,
+) -> Result<Vec<git::history::BlameHunk>, String> {
+    let p = PathBuf::from(&path);
+    ensure_in_workspace(window.label(), &p)?;
+    tauri::async_runtime::spawn_blocking(move || git::history::blame(&p))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(String::from)
+}
+
+/// summarize_changes
+/// 让 AI 根据已暂存的 Markdown 差异生成提交说明与更新日志（过长的差异会被截断）
+#[tauri::command]
+async fn summarize_changes(
+    window: tauri::WebviewWindow,
+    dir: String,
+    req: AiRequest,
+    max_diff_chars: Option<usize>,
+) -> Result<git::summary::ChangeSummary, String> {
+    use git::summary;
+
+    let root = PathBuf::from(&dir);
+    ensure_in_workspace(window.label(), &root)?;
+    let patches = tauri::async_runtime::spawn_blocking(move || {
+        git::diff::staged_markdown_patches(&root, summary::PROMPT_CONTEXT)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if patches.is_empty() {
+        return Err("没有已暂存的 Markdown 更改".to_string());
+    }
+
+    let (diff, truncated) =
+        summary::render_diff(&patches, max_diff_chars.unwrap_or(summary::DEFAULT_BUDGET));
+    let reply = ai_complete(AiRequest {
+        prompt: summary::build_prompt(&diff),
+        system_prompt: Some(summary::SYSTEM_PROMPT.to_string()),
+        messages: None,
+        ..req
+    })
+    .await?;
+    let (commit_message, changelog) = summary::parse_response(&reply);
+    Ok(summary::ChangeSummary {
+        commit_message,
+        changelog,
+        files: patches.into_iter().map(|p| p.path).collect(),
+        truncated,
+    })
+}
+
+#[tauri::command]
+async fn watch_start(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    dir: String,
+) -> Result<(), String> {
     use notify::{RecursiveMode, Watcher};
     let app_handle = app.clone();
-    let mut watcher = notify::recommended_watcher(move |_res| {
-        let _ = app_handle.emit("fs:changed", "");
+    let label = window.label().to_string();
+    let watch_label = label.clone();
+    let watch_dir = dir.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        // 只通知监听该目录的窗口 / Notify only the window watching this directory
+        let _ = app_handle.emit_to(watch_label.as_str(), "fs:changed", "");
+        // `.git` 内部的变化（包括查询状态本身引起的）不触发刷新，避免循环
+        // Changes inside `.git`, including those caused by the status query, would loop
+        let in_git = res.as_ref().is_ok_and(|event| {
+            !event.paths.is_empty()
+                && event
+                    .paths
+                    .iter()
+                    .all(|p| p.components().any(|c| c.as_os_str() == ".git"))
+        });
+        if !in_git {
+            schedule_git_refresh(app_handle.clone(), watch_label.clone(), watch_dir.clone());
+        }
     })
     .map_err(|e| e.to_string())?;
     watcher
         .watch(std::path::Path::new(&dir), RecursiveMode::Recursive)
         .map_err(|e| e.to_string())?;
-    *WATCHER.lock().unwrap() = Some(watcher);
+    WATCHERS.lock().unwrap().insert(label, watcher);
     Ok(())
 }
 
 #[tauri::command]
-async fn watch_stop() -> Result<(), String> {
-    *WATCHER.lock().unwrap() = None;
+async fn watch_stop(window: tauri::WebviewWindow) -> Result<(), String> {
+    WATCHERS.lock().unwrap().remove(window.label());
     Ok(())
 }
 
@@ -392,9 +1305,8 @@ async fn ai_complete(req: AiRequest) -> Result<String, String> {
             if let Some(t) = req.temperature {
                 body["temperature"] = serde_json::json!(t);
             }
-            if let Some(mt) = req.max_tokens {
-                body["max_tokens"] = serde_json::json!(mt);
-            }
+            // Anthropic 要求必须指定 max_tokens / Anthropic rejects requests without max_tokens
+            body["max_tokens"] = serde_json::json!(req.max_tokens.unwrap_or(CLAUDE_MAX_TOKENS));
 
             let resp = client
                 .post(url)
@@ -534,6 +1446,7 @@ async fn ai_complete_stream(app: tauri::AppHandle, req: AiRequest) -> Result<(),
             if let Some(t) = req.temperature {
                 body["temperature"] = serde_json::json!(t);
             }
+            body["max_tokens"] = serde_json::json!(req.max_tokens.unwrap_or(CLAUDE_MAX_TOKENS));
             let resp = client
                 .post(url)
                 .header("x-api-key", req.api_key.trim())
@@ -623,6 +1536,7 @@ struct Message {
 struct AiRequest {
     provider: Provider,
     api_key: String,
+    #[serde(default)]
     prompt: String,
     model: Option<String>,
     system_prompt: Option<String>,
@@ -632,6 +1546,9 @@ struct AiRequest {
     messages: Option<Vec<Message>>,
 }
 
+/// 未指定时 Claude 请求的回复长度上限（Anthropic 要求必填） / Reply budget when a Claude request sets none
+const CLAUDE_MAX_TOKENS: u32 = 8192;
+
 /// default_base_for_provider
 /// 返回不同提供商的默认基础 URL
 fn default_base_for_provider(p: &Provider) -> &'static str {
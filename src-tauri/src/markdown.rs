@@ -0,0 +1,471 @@
+//! Markdown 解析公共工具 / Shared Markdown parsing helpers
+//!
+//! 导出、导入与资源管理命令共用的解析选项、标题锚点与路径解析逻辑。
+//! Parser options, heading anchors and link resolution shared by export/import/asset commands.
+
+use once_cell::sync::Lazy;
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// 内联 HTML 中的 `<img src>`：分组 1 为前缀，2/4 为引号，3 为地址
+/// `<img src>` inside raw HTML: group 3 is the URL
+pub static HTML_IMG_SRC: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r#"(<img\b[^>]*?\bsrc\s*=\s*)(["'])([^"']+)(["'])"#).unwrap());
+
+/// 与预览保持一致的解析选项 / Parser options matching the live preview
+pub fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_HEADING_ATTRIBUTES
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+}
+
+/// 文档标题 / Document heading
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    pub id: String,
+}
+
+/// 生成唯一锚点 ID（跨多个文件共享） / Generates unique anchor ids (shared across files)
+#[derive(Default)]
+pub struct SlugSet {
+    seen: HashMap<String, usize>,
+}
+
+impl SlugSet {
+    pub fn unique(&mut self, text: &str) -> String {
+        let base = slugify(text);
+        let base = if base.is_empty() {
+            "section".to_string()
+        } else {
+            base
+        };
+        let count = self.seen.entry(base.clone()).or_insert(0);
+        let slug = if *count == 0 {
+            base
+        } else {
+            format!("{}-{}", base, count)
+        };
+        *count += 1;
+        slug
+    }
+}
+
+/// slugify
+/// 类 GitHub 的锚点规则：小写、保留字母数字（含中文），空白转为 `-`
+pub fn slugify(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() || c == '_' {
+            out.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-') && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    out.trim_matches('-').to_string()
+}
+
+pub fn heading_level_number(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// 拆分 YAML front matter / Splits off YAML front matter
+/// 返回 (front matter 内容, 正文)
+pub fn split_front_matter(src: &str) -> (Option<&str>, &str) {
+    let rest = match src
+        .strip_prefix("---\n")
+        .or_else(|| src.strip_prefix("---\r\n"))
+    {
+        Some(r) => r,
+        None => return (None, src),
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" || line.trim_end() == "..." {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, src)
+}
+
+/// 读取 front matter 中的简单 `key: value` 字段 / Reads a flat `key: value` field
+pub fn front_matter_value<'a>(front: &'a str, key: &str) -> Option<&'a str> {
+    front.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        if k.trim() != key {
+            return None;
+        }
+        let v = v.trim().trim_matches('"').trim_matches('\'');
+        if v.is_empty() {
+            None
+        } else {
+            Some(v)
+        }
+    })
+}
+
+/// 文档标题：front matter 的 title，否则第一个一级标题，否则文件名
+pub fn document_title(src: &str, path: &Path) -> String {
+    if let Some(title) = split_front_matter(src)
+        .0
+        .and_then(|front| front_matter_value(front, "title"))
+    {
+        return title.to_string();
+    }
+    collect_headings(src, &mut SlugSet::default())
+        .into_iter()
+        .find(|h| h.level == 1)
+        .map(|h| h.text)
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "Document".to_string())
+        })
+}
+
+/// 解析事件并为所有标题补充唯一 id / Parses events and assigns unique heading ids
+pub fn parse_with_anchors<'a>(src: &'a str, slugs: &mut SlugSet) -> (Vec<Event<'a>>, Vec<Heading>) {
+    let mut events: Vec<Event<'a>> = Parser::new_ext(src, parser_options()).collect();
+    let mut headings = Vec::new();
+    let mut i = 0;
+    while i < events.len() {
+        if let Event::Start(Tag::Heading { level, .. }) = &events[i] {
+            let level = heading_level_number(*level);
+            let mut text = String::new();
+            let mut j = i + 1;
+            while j < events.len() {
+                match &events[j] {
+                    Event::End(TagEnd::Heading(_)) => break,
+                    Event::Text(t) | Event::Code(t) => text.push_str(t),
+                    _ => {}
+                }
+                j += 1;
+            }
+            let text = text.trim().to_string();
+            if let Event::Start(Tag::Heading { id, .. }) = &mut events[i] {
+                let anchor = match id {
+                    Some(existing) => existing.to_string(),
+                    None => slugs.unique(&text),
+                };
+                *id = Some(CowStr::from(anchor.clone()));
+                headings.push(Heading {
+                    level,
+                    text,
+                    id: anchor,
+                });
+            }
+            i = j;
+        }
+        i += 1;
+    }
+    (events, headings)
+}
+
+/// 仅收集标题 / Collects headings only
+pub fn collect_headings(src: &str, slugs: &mut SlugSet) -> Vec<Heading> {
+    parse_with_anchors(src, slugs).1
+}
+
+/// 文档中的链接或图片引用 / A link or image referenced by a document
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub url: String,
+    /// 所在行（从 1 开始） / 1-based line number
+    pub line: usize,
+    pub image: bool,
+    /// 在源文本中的字节范围 / Byte range in the source text
+    pub span: Range<usize>,
+}
+
+/// references
+/// 收集 Markdown 链接、图片与内联 HTML `<img>` 的地址
+pub fn references(src: &str) -> Vec<Reference> {
+    let line_of = |offset: usize| src[..offset.min(src.len())].matches('\n').count() + 1;
+    let mut out = Vec::new();
+    for (event, range) in Parser::new_ext(src, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Image { dest_url, .. }) => out.push(Reference {
+                url: dest_url.to_string(),
+                line: line_of(range.start),
+                image: true,
+                span: range,
+            }),
+            Event::Start(Tag::Link { dest_url, .. }) => out.push(Reference {
+                url: dest_url.to_string(),
+                line: line_of(range.start),
+                image: false,
+                span: range,
+            }),
+            Event::Html(html) | Event::InlineHtml(html) => {
+                for caps in HTML_IMG_SRC.captures_iter(&html) {
+                    out.push(Reference {
+                        url: caps[3].to_string(),
+                        line: line_of(range.start),
+                        image: true,
+                        span: range.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// 引用式链接定义（`[id]: url`）的地址与字节范围
+/// Destinations and byte ranges of reference definitions
+pub fn reference_definitions(src: &str) -> Vec<(String, Range<usize>)> {
+    let parser = Parser::new_ext(src, parser_options());
+    let mut out: Vec<(String, Range<usize>)> = parser
+        .reference_definitions()
+        .iter()
+        .map(|(_, def)| (def.dest.to_string(), def.span.clone()))
+        .collect();
+    out.sort_by_key(|(_, span)| span.start);
+    out
+}
+
+/// 引用中链接地址所在的字节范围（只取地址本身，不含链接文字与标题）
+/// Byte range of `url` as the destination of the reference at `span`, never its text or title
+fn destination_range(src: &str, span: &Range<usize>, url: &str) -> Option<Range<usize>> {
+    let text = src.get(span.clone())?;
+    if url.is_empty() {
+        return None;
+    }
+    let definition = text
+        .find(']')
+        .is_some_and(|i| text[i + 1..].starts_with(':'));
+    let from = if text.starts_with('<') {
+        // 内联 HTML：取 `src` 属性的值 / Inline HTML: the value of a `src` attribute
+        return HTML_IMG_SRC
+            .captures_iter(text)
+            .filter_map(|caps| caps.get(3))
+            .find(|m| m.as_str() == url)
+            .map(|m| span.start + m.start()..span.start + m.end());
+    } else if definition {
+        // 链接定义 `[id]: url "title"` / Reference definition
+        text.find("]:")? + 2
+    } else {
+        // 行内链接与图片：地址在最后一个 `](` 之后（链接文字里可能嵌套图片）
+        // Inline link or image: the destination follows the last `](`, after any nested image
+        text.rfind("](")? + 2
+    };
+    // 源文本中的地址可能含转义或实体（`a\_b.md`、`a&amp;b.md`），按解析结果比较整个地址
+    // The raw destination may hold escapes or entities, so compare its parsed value as a whole
+    let rest = text[from..].trim_start();
+    let raw = raw_destination(rest)?;
+    if unescape_destination(raw)? != url {
+        return None;
+    }
+    let start = span.start + text.len() - rest.len();
+    Some(if raw.starts_with('<') {
+        start + 1..start + raw.len() - 1
+    } else {
+        start..start + raw.len()
+    })
+}
+
+/// 源文本开头的链接地址（尖括号形式包含括号）
+/// The raw link destination at the start of `rest`, angle brackets included
+fn raw_destination(rest: &str) -> Option<&str> {
+    let mut chars = rest.char_indices();
+    if rest.starts_with('<') {
+        chars.next();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '>' => return Some(&rest[..=i]),
+                '\n' | '<' => return None,
+                _ => {}
+            }
+        }
+        return None;
+    }
+    let mut depth = 0usize;
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(&rest[..i]),
+            ')' => depth -= 1,
+            c if c.is_whitespace() => return Some(&rest[..i]),
+            _ => {}
+        }
+    }
+    Some(rest)
+}
+
+/// 按 CommonMark 规则解析原始地址（处理反斜杠转义与 HTML 实体）
+/// The destination value of a raw destination, with escapes and entities resolved
+fn unescape_destination(raw: &str) -> Option<String> {
+    Parser::new(&format!("[]({})", raw)).find_map(|event| match event {
+        Event::Start(Tag::Link { dest_url, .. }) => Some(dest_url.to_string()),
+        _ => None,
+    })
+}
+
+/// 把各引用的链接地址替换为新地址，返回新文本与替换次数；只改写地址本身
+/// Replaces the destinations of the references at `spans`; returns the text and the replacement count
+pub fn rewrite_urls(
+    src: &str,
+    spans: &[Range<usize>],
+    links: &HashMap<&str, String>,
+) -> (String, usize) {
+    let mut edits: Vec<(Range<usize>, &str)> = spans
+        .iter()
+        .flat_map(|span| {
+            links.iter().filter_map(move |(url, link)| {
+                destination_range(src, span, url).map(|range| (range, link.as_str()))
+            })
+        })
+        .collect();
+    edits.sort_by_key(|(range, _)| range.start);
+    edits.dedup_by_key(|(range, _)| range.start);
+
+    let mut out = String::with_capacity(src.len());
+    let mut last = 0;
+    let mut count = 0;
+    for (range, link) in edits {
+        if range.start < last {
+            continue;
+        }
+        out.push_str(&src[last..range.start]);
+        out.push_str(link);
+        last = range.end;
+        count += 1;
+    }
+    out.push_str(&src[last..]);
+    (out, count)
+}
+
+/// 是否为远程或内联资源 / Whether a link points to a remote or inline resource
+pub fn is_external(url: &str) -> bool {
+    let lower = url.trim().to_ascii_lowercase();
+    lower.starts_with("http://")
+        || lower.starts_with("https://")
+        || lower.starts_with("data:")
+        || lower.starts_with("mailto:")
+        || lower.starts_with("//")
+}
+
+/// 将文档中的本地链接解析为绝对路径（去掉 query/fragment 与百分号编码）
+/// Resolves a local link relative to the document directory
+pub fn resolve_local(base_dir: &Path, url: &str) -> Option<PathBuf> {
+    let url = url.trim();
+    if url.is_empty() || url.starts_with('#') || is_external(url) {
+        return None;
+    }
+    let url = url.strip_prefix("file://").unwrap_or(url);
+    let url = url.split(['?', '#']).next().unwrap_or(url);
+    let decoded = percent_decode(url);
+    let p = Path::new(&decoded);
+    if p.is_absolute() {
+        Some(p.to_path_buf())
+    } else {
+        Some(base_dir.join(p))
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = |b: u8| (b as char).to_digit(16);
+            if let (Some(hi), Some(lo)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                out.push((hi * 16 + lo) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// HTML 转义 / Escapes text for HTML output
+pub fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(src: &str, from: &str, to: &str) -> (String, usize) {
+        let mut spans: Vec<Range<usize>> = references(src).into_iter().map(|r| r.span).collect();
+        spans.extend(reference_definitions(src).into_iter().map(|(_, span)| span));
+        let mut links = HashMap::new();
+        links.insert(from, to.to_string());
+        rewrite_urls(src, &spans, &links)
+    }
+
+    #[test]
+    fn rewrite_urls_only_touches_destinations() {
+        let src = "[see a.png](a.png) ![a.png](a.png \"a.png\")\n\n[ref]: a.png \"a.png\"\n";
+        let (out, n) = rewrite(src, "a.png", "img/a.png");
+        assert_eq!(n, 3);
+        assert_eq!(
+            out,
+            "[see a.png](img/a.png) ![a.png](img/a.png \"a.png\")\n\n[ref]: img/a.png \"a.png\"\n"
+        );
+    }
+
+    #[test]
+    fn rewrite_urls_handles_html_and_angle_brackets() {
+        let src = "<img alt=\"a.png\" src=\"a.png\">\n\n[x](<a.png>)\n";
+        let (out, n) = rewrite(src, "a.png", "b.png");
+        assert_eq!(n, 2);
+        assert_eq!(out, "<img alt=\"a.png\" src=\"b.png\">\n\n[x](<b.png>)\n");
+    }
+
+    #[test]
+    fn rewrite_urls_skips_partial_matches() {
+        let src = "[other](a.png.bak) [nested ![i](a.png)](doc.md)\n";
+        let (out, n) = rewrite(src, "a.png", "b.png");
+        assert_eq!(n, 1);
+        assert_eq!(out, "[other](a.png.bak) [nested ![i](b.png)](doc.md)\n");
+    }
+
+    #[test]
+    fn rewrite_urls_matches_escaped_destinations() {
+        let src = "[a](a\\_b.md) [b](<a&amp;b.md>)\n\n[ref]: a\\_b.md\n";
+        let (out, n) = rewrite(src, "a_b.md", "c.md");
+        assert_eq!(n, 2);
+        assert_eq!(out, "[a](c.md) [b](<a&amp;b.md>)\n\n[ref]: c.md\n");
+        let (out, n) = rewrite(src, "a&b.md", "c.md");
+        assert_eq!(n, 1);
+        assert_eq!(out, "[a](a\\_b.md) [b](<c.md>)\n\n[ref]: a\\_b.md\n");
+    }
+}
@@ -0,0 +1,182 @@
+//! 最近打开 / Recently opened files and workspaces
+//!
+//! 在应用数据目录中保存最近打开的文件与工作区（`recent.json`），读取时剔除已不存在的路径；
+//! 固定的条目不计入数量上限，不会被清空，路径暂时不可用（如外接磁盘未挂载）时也保留。Linux 下同时登记到桌面的最近文件列表，供启动器使用。
+//! Persists an MRU list in the app data dir, pruned of missing paths, with pinning.
+
+use crate::error::AppError;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 记录文件名 / File name inside the app data dir
+const FILE_NAME: &str = "recent.json";
+
+/// 每种类型最多保留的未固定条目 / Unpinned entries kept per kind
+const MAX_PER_KIND: usize = 20;
+
+/// 多个窗口可能同时写入，读改写整体加锁 / Serializes read-modify-write across windows
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecentKind {
+    File,
+    Workspace,
+}
+
+/// RecentEntry
+/// 最近打开的一项
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecentEntry {
+    pub path: String,
+    pub kind: RecentKind,
+    #[serde(default)]
+    pub pinned: bool,
+    /// 最近打开时间（Unix 秒） / Last opened, Unix seconds
+    pub opened_at: i64,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct RecentStore {
+    #[serde(default)]
+    entries: Vec<RecentEntry>,
+}
+
+fn store_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(FILE_NAME)
+}
+
+fn exists(entry: &RecentEntry) -> bool {
+    let path = Path::new(&entry.path);
+    match entry.kind {
+        RecentKind::File => path.is_file(),
+        RecentKind::Workspace => path.is_dir(),
+    }
+}
+
+/// 读取记录；文件损坏时从空列表开始 / Loads the store, starting empty if it is unreadable
+fn load(data_dir: &Path) -> RecentStore {
+    let Ok(text) = std::fs::read_to_string(store_path(data_dir)) else {
+        return RecentStore::default();
+    };
+    serde_json::from_str(&text).unwrap_or_else(|e| {
+        log::warn!("Ignoring corrupt recent list: {}", e);
+        RecentStore::default()
+    })
+}
+
+/// 写入临时文件后重命名，避免写到一半被读取 / Writes via a temp file and rename
+fn save(data_dir: &Path, store: &RecentStore) -> Result<(), AppError> {
+    std::fs::create_dir_all(data_dir)?;
+    let json = serde_json::to_string_pretty(store)?;
+    let path = store_path(data_dir);
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// 固定的在前，其余按打开时间倒序 / Pinned first, then most recent first
+fn sorted(store: &RecentStore) -> Vec<RecentEntry> {
+    let mut entries = store.entries.clone();
+    entries.sort_by(|a, b| b.pinned.cmp(&a.pinned).then(b.opened_at.cmp(&a.opened_at)));
+    entries
+}
+
+/// 读取、修改并保存记录，返回排序后的列表（同时剔除已不存在的未固定路径）
+/// Loads, applies `change`, prunes missing unpinned paths and saves; returns the sorted list
+fn update(
+    data_dir: &Path,
+    change: impl FnOnce(&mut RecentStore),
+) -> Result<Vec<RecentEntry>, AppError> {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut store = load(data_dir);
+    let before = serde_json::to_string(&store).unwrap_or_default();
+    store.entries.retain(|e| e.pinned || exists(e));
+    change(&mut store);
+    if serde_json::to_string(&store).unwrap_or_default() != before {
+        save(data_dir, &store)?;
+    }
+    Ok(sorted(&store))
+}
+
+/// list
+/// 最近打开的文件与工作区
+pub fn list(data_dir: &Path) -> Result<Vec<RecentEntry>, AppError> {
+    update(data_dir, |_| {})
+}
+
+/// add
+/// 记录一次打开：移到最前，超出上限的未固定条目被移除
+pub fn add(data_dir: &Path, path: &str, kind: RecentKind) -> Result<Vec<RecentEntry>, AppError> {
+    let path = path.replace('\\', "/");
+    let opened_at = chrono::Utc::now().timestamp();
+    update(data_dir, |store| {
+        let pinned = store
+            .entries
+            .iter()
+            .any(|e| e.path == path && e.kind == kind && e.pinned);
+        store
+            .entries
+            .retain(|e| !(e.path == path && e.kind == kind));
+        let entry = RecentEntry {
+            path,
+            kind,
+            pinned,
+            opened_at,
+        };
+        // 固定项即使暂时不存在也保留 / Pinned entries survive even if the path is missing
+        if entry.pinned || exists(&entry) {
+            store.entries.insert(0, entry);
+        }
+        let mut kept = 0;
+        store.entries.retain(|e| {
+            if e.pinned || e.kind != kind {
+                return true;
+            }
+            kept += 1;
+            kept <= MAX_PER_KIND
+        });
+    })
+}
+
+/// set_pinned
+/// 固定或取消固定
+pub fn set_pinned(data_dir: &Path, path: &str, pinned: bool) -> Result<Vec<RecentEntry>, AppError> {
+    let path = path.replace('\\', "/");
+    update(data_dir, |store| {
+        for e in store.entries.iter_mut().filter(|e| e.path == path) {
+            e.pinned = pinned;
+        }
+    })
+}
+
+/// remove
+/// 从列表中移除（固定的条目也会移除）
+pub fn remove(data_dir: &Path, path: &str) -> Result<Vec<RecentEntry>, AppError> {
+    let path = path.replace('\\', "/");
+    update(data_dir, |store| store.entries.retain(|e| e.path != path))
+}
+
+/// clear
+/// 清空某一类（缺省为全部）未固定的条目
+pub fn clear(data_dir: &Path, kind: Option<RecentKind>) -> Result<Vec<RecentEntry>, AppError> {
+    update(data_dir, |store| {
+        store
+            .entries
+            .retain(|e| e.pinned || kind.is_some_and(|k| k != e.kind))
+    })
+}
+
+/// register_desktop
+/// 登记到桌面的最近文件列表（GTK `recently-used.xbel`），供启动器与文件管理器显示；须在主线程调用
+#[cfg(target_os = "linux")]
+pub fn register_desktop(path: &Path) {
+    use gtk::prelude::RecentManagerExt;
+    match gtk::glib::filename_to_uri(path, None) {
+        Ok(uri) => {
+            gtk::RecentManager::default().add_item(&uri);
+        }
+        Err(e) => log::warn!("Failed to register recent item {:?}: {}", path, e),
+    }
+}
@@ -0,0 +1,321 @@
+//! 文档模板 / Document templates
+//!
+//! 从工作区的 `.markdownmonkey/templates/` 读取模板，展开 `{{date}}`、`{{title}}`、`{{author}}`、
+//! `{{number}}`（目录内下一个编号，如 ADR）等变量后创建新文件；文件名中同样可以使用变量。
+//! Expands workspace templates with built-in and caller variables to scaffold new documents.
+
+use crate::config::CONFIG_DIR;
+use crate::error::AppError;
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// 模板目录（相对配置目录） / Templates folder inside the config directory
+const TEMPLATES_DIR: &str = "templates";
+
+/// 编号的最少位数，如 `0007` / Minimum digits of `{{number}}`
+const NUMBER_WIDTH: usize = 4;
+
+/// `{{name}}` 或 `{{name:format}}` / A placeholder with an optional format
+static PLACEHOLDER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{\s*([A-Za-z_][\w-]*)\s*(?::([^}]*))?\}\}").unwrap());
+
+/// 模板目录路径 / Templates folder of a workspace
+pub fn templates_dir(root: &Path) -> PathBuf {
+    root.join(CONFIG_DIR).join(TEMPLATES_DIR)
+}
+
+/// list
+/// 工作区内可用的模板名（相对模板目录，不含 `.md`）
+pub fn list(root: &Path) -> Result<Vec<String>, AppError> {
+    fn walk(dir: &Path, base: &Path, out: &mut Vec<String>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)?.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(&path, base, out)?;
+            } else if crate::export::is_markdown(&path) {
+                if let Ok(rel) = path.with_extension("").strip_prefix(base) {
+                    out.push(crate::git::display(rel));
+                }
+            }
+        }
+        Ok(())
+    }
+    let dir = templates_dir(root);
+    let mut out = Vec::new();
+    if dir.is_dir() {
+        walk(&dir, &dir, &mut out)?;
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// 按名称查找模板文件；不允许跳出模板目录 / Resolves a template name inside the templates folder
+pub fn resolve(root: &Path, name: &str) -> Result<PathBuf, AppError> {
+    let rel = Path::new(name.trim());
+    if name.trim().is_empty() || !rel.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(AppError::InvalidInput(format!("无效的模板名: {}", name)));
+    }
+    let mut path = templates_dir(root).join(rel);
+    if path.extension().is_none() {
+        path.set_extension("md");
+    }
+    if !path.is_file() {
+        return Err(AppError::InvalidInput(format!("找不到模板: {}", name)));
+    }
+    Ok(path)
+}
+
+/// 目录中以数字开头的文件的下一个编号 / Next number after the numeric prefixes in `dir`
+fn next_number(dir: &Path) -> u64 {
+    let max = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let digits: String = name.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse::<u64>().ok()
+        })
+        .max()
+        .unwrap_or(0);
+    max + 1
+}
+
+/// 由文件名推导标题：去掉编号前缀，连字符转空格 / Title from a file stem, minus any number prefix
+fn default_title(stem: &str) -> String {
+    let rest = stem.trim_start_matches(|c: char| c.is_ascii_digit());
+    let rest = rest.trim_start_matches(['-', '_', ' ', '.']);
+    let text = if rest.is_empty() { stem } else { rest };
+    let words = text.replace(['-', '_'], " ");
+    let mut chars = words.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// 作者：Git 配置的 user.name，其次为系统用户名 / Author from git config, then the OS user
+fn default_author() -> String {
+    git2::Config::open_default()
+        .and_then(|c| c.get_string("user.name"))
+        .ok()
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .unwrap_or_default()
+}
+
+fn format_time(time: &NaiveDateTime, format: &str) -> Result<String, AppError> {
+    use std::fmt::Write as _;
+    // 无效的格式串与没有时区的 `%z` 在输出时出错，`to_string()` 会因此 panic
+    // Invalid specifiers and `%z` without an offset fail on display, which `to_string()` turns into a panic
+    let mut out = String::new();
+    write!(out, "{}", time.format(format))
+        .map_err(|_| AppError::InvalidInput(format!("无效的日期格式: {}", format)))?;
+    Ok(out)
+}
+
+/// expand
+/// 展开文本中的变量：`date`/`time`/`datetime` 可带 strftime 格式（如 `{{date:%Y/%m}}`），
+/// 其他变量从 `vars` 中取值；未知变量保持原样
+pub fn expand(
+    text: &str,
+    time: &NaiveDateTime,
+    vars: &HashMap<String, String>,
+) -> Result<String, AppError> {
+    let mut error = None;
+    let out = PLACEHOLDER.replace_all(text, |caps: &Captures| {
+        let name = &caps[1];
+        let format = caps.get(2).map(|m| m.as_str().trim());
+        let default = match name {
+            "date" => Some("%Y-%m-%d"),
+            "time" => Some("%H:%M"),
+            "datetime" => Some("%Y-%m-%d %H:%M"),
+            _ => None,
+        };
+        if let Some(value) = vars.get(name).filter(|_| format.is_none()) {
+            return value.clone();
+        }
+        match default {
+            Some(default) => format_time(time, format.unwrap_or(default)).unwrap_or_else(|e| {
+                error.get_or_insert(e);
+                String::new()
+            }),
+            None => caps[0].to_string(),
+        }
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(out.into_owned()),
+    }
+}
+
+/// 内置变量 / Built-in variables for a target directory and date
+pub fn builtin_vars(dir: &Path, date: NaiveDate) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    vars.insert("year".to_string(), date.format("%Y").to_string());
+    vars.insert("month".to_string(), date.format("%m").to_string());
+    vars.insert("day".to_string(), date.format("%d").to_string());
+    vars.insert("weekday".to_string(), date.format("%A").to_string());
+    vars.insert(
+        "number".to_string(),
+        format!("{:0width$}", next_number(dir), width = NUMBER_WIDTH),
+    );
+    vars.insert("author".to_string(), default_author());
+    vars
+}
+
+/// render
+/// 展开目标文件名与模板内容，返回 (最终路径, 内容)；`date` 缺省为今天
+pub fn render(
+    root: &Path,
+    target: &Path,
+    template: &str,
+    vars: &HashMap<String, String>,
+    date: Option<NaiveDate>,
+) -> Result<(PathBuf, String), AppError> {
+    let template_path = resolve(root, template)?;
+    crate::check_file_size(&template_path).map_err(AppError::InvalidInput)?;
+    let text = std::fs::read_to_string(&template_path)?;
+
+    let dir = target.parent().unwrap_or(Path::new("."));
+    let now = Local::now().naive_local();
+    let time = match date {
+        Some(date) => date.and_time(now.time()),
+        None => now,
+    };
+    let mut all = builtin_vars(dir, time.date());
+    all.extend(vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+    if let Some(title) = vars.get("title") {
+        all.entry("slug".to_string())
+            .or_insert_with(|| crate::markdown::slugify(title));
+    }
+
+    // 文件名中也可以使用变量，如 `{{number}}-{{slug}}.md`
+    // Placeholders are allowed in the file name too
+    let file_name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| AppError::InvalidInput("无效的文件路径".to_string()))?;
+    let file_name = expand(&file_name, &time, &all)?;
+    if file_name.contains(['/', '\\'])
+        || file_name.trim().is_empty()
+        || matches!(file_name.as_str(), "." | "..")
+    {
+        return Err(AppError::InvalidInput(format!(
+            "无效的文件名: {}",
+            file_name
+        )));
+    }
+    let target = dir.join(&file_name);
+
+    let stem = target
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let title = all
+        .entry("title".to_string())
+        .or_insert_with(|| default_title(&stem))
+        .clone();
+    all.entry("slug".to_string())
+        .or_insert_with(|| crate::markdown::slugify(&title));
+    all.insert("filename".to_string(), stem);
+
+    Ok((target, expand(&text, &time, &all)?))
+}
+
+/// create
+/// 用模板创建新文件（已存在时报错），写入前用 `sandbox` 校验展开后的路径，返回实际创建的路径
+pub fn create(
+    root: &Path,
+    target: &Path,
+    template: &str,
+    vars: &HashMap<String, String>,
+    sandbox: &dyn Fn(&Path) -> Result<(), String>,
+) -> Result<PathBuf, AppError> {
+    let (target, content) = render(root, target, template, vars, None)?;
+    sandbox(&target).map_err(AppError::InvalidInput)?;
+    write_new(&target, &content)?;
+    Ok(target)
+}
+
+/// 新建文件并写入内容，不覆盖已有文件 / Creates the file, refusing to overwrite
+pub fn write_new(path: &Path, content: &str) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => {
+                AppError::InvalidInput(format!("文件已存在: {}", crate::git::display(path)))
+            }
+            _ => AppError::Io(e),
+        })?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 7)
+            .unwrap()
+            .and_hms_opt(9, 5, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn expand_builtin_and_caller_vars() {
+        let mut vars = HashMap::new();
+        vars.insert("title".to_string(), "Hello".to_string());
+        let out = expand(
+            "# {{ title }}\n{{date}} {{time}} {{datetime}} {{date:%Y/%m}} {{unknown}}",
+            &time(),
+            &vars,
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "# Hello\n2024-03-07 09:05 2024-03-07 09:05 2024/03 {{unknown}}"
+        );
+    }
+
+    #[test]
+    fn expand_caller_date_unless_formatted() {
+        let mut vars = HashMap::new();
+        vars.insert("date".to_string(), "someday".to_string());
+        let out = expand("{{date}} {{date:%d}}", &time(), &vars).unwrap();
+        assert_eq!(out, "someday 07");
+    }
+
+    #[test]
+    fn expand_rejects_bad_formats() {
+        let vars = HashMap::new();
+        assert!(expand("{{date:%Q}}", &time(), &vars).is_err());
+        // 没有时区信息的 `%z` 不能 panic / `%z` without an offset must not panic
+        assert!(expand("{{time:%z}}", &time(), &vars).is_err());
+    }
+
+    #[test]
+    fn render_rejects_dot_file_names() {
+        let root = std::env::temp_dir().join(format!("mm-templates-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(templates_dir(&root)).unwrap();
+        std::fs::write(templates_dir(&root).join("note.md"), "x").unwrap();
+        for name in [".", ".."] {
+            let mut vars = HashMap::new();
+            vars.insert("name".to_string(), name.to_string());
+            let target = root.join("notes").join("{{name}}");
+            assert!(render(&root, &target, "note", &vars, None).is_err());
+        }
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -17,7 +17,7 @@ import { Store } from '@tauri-apps/plugin-store'
 const monkeyIcon = new URL('../assets/icon.svg', import.meta.url).href
 import { open, save, type OpenDialogOptions } from '@tauri-apps/plugin-dialog'
 import { readTextFile, writeTextFile, writeFile } from '@tauri-apps/plugin-fs'
-import { listen } from '@tauri-apps/api/event'
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow'
 import CodeMirror from '@uiw/react-codemirror'
 import { EditorView, Decoration } from '@codemirror/view'
 import type { DecorationSet } from '@codemirror/view'
@@ -47,6 +47,59 @@ const PERF_CONSTANTS = {
   LARGE_FILE_MAX_SIZE: 10 * 1024 * 1024, // 10MB
 } as const
 
+// 命令行/第二实例的打开请求（与后端 cli::open::OpenRequest 对应） / Launch open request from the backend
+type OpenRequest = {
+  files: Array<{ path: string; line: number | null; column: number | null }>
+  folders: string[]
+  new_window: boolean
+  readonly: boolean
+}
+
+// 工作区配置（与后端 workspace_config 对应） / Workspace config from `.markdownmonkey/config.toml`
+type WorkspaceSettings = {
+  root: string
+  config: {
+    assets_dir: string | null
+    ignore: string[]
+    ai: { provider: string | null; model: string | null; base_url: string | null }
+    export: { theme: string | null; css: string | null }
+    journal: { path: string | null; template: string | null }
+    lint: Record<string, unknown>
+  }
+  error: string | null
+}
+
+type WorkspaceAi = WorkspaceSettings['config']['ai']
+
+// 工作区是否改变了 AI 服务或地址 / Whether the workspace points AI requests elsewhere
+function ai_redirects(ai: WorkspaceAi) {
+  return !!(ai.provider || ai.base_url)
+}
+
+// 信任标识包含服务与地址，配置改动后需重新确认 / Trust covers the exact provider and URL; edits ask again
+function ai_trust_key(settings: WorkspaceSettings) {
+  const ai = settings.config.ai
+  return [settings.root, ai.provider || '', ai.base_url || ''].join('\n')
+}
+
+type JournalLink = { date: string; path: string }
+
+type DailyNote = {
+  date: string
+  path: string
+  created: boolean
+  previous: JournalLink | null
+  next: JournalLink | null
+}
+
+// 最近打开的条目（与后端 recent::RecentEntry 对应） / Recent entry from the backend
+type RecentEntry = {
+  path: string
+  kind: 'file' | 'workspace'
+  pinned: boolean
+  opened_at: number
+}
+
 /**
  * App
  * 应用主组件：左侧 Markdown 编辑，右侧 HTML 预览（含代码高亮与 XSS 清理）
@@ -183,7 +236,12 @@ function App() {
   const [ctx_pos, set_ctx_pos] = useState<{ x: number; y: number }>({ x: 0, y: 0 })
   const cm_view_ref = useRef<EditorView | null>(null)
   const [ctx_has_selection, set_ctx_has_selection] = useState<boolean>(false)
-  const [recent_files, set_recent_files] = useState<string[]>([])
+  // 最近打开的文件与工作区（后端维护） / Recent files and workspaces, kept by the backend
+  const [recent_entries, set_recent_entries] = useState<RecentEntry[]>([])
+  const recent_files = useMemo(
+    () => recent_entries.filter((e) => e.kind === 'file').map((e) => e.path),
+    [recent_entries]
+  )
   const [recent_ai_actions, set_recent_ai_actions] = useState<Array<{ id: string; title: string }>>(
     []
   )
@@ -204,9 +262,17 @@ function App() {
   const [search_total, set_search_total] = useState<number>(0)
   const [side_tab, set_side_tab] = useState<'outline' | 'files'>('outline')
   const [workspace_root, set_workspace_root] = useState<string>('')
+  const [workspace_settings, set_workspace_settings] = useState<WorkspaceSettings | null>(null)
+  // 已信任其 AI 服务与地址的工作区配置 / Workspace AI configs the user has trusted
+  const [ai_trusted, set_ai_trusted] = useState<string[]>([])
+  const [daily_note, set_daily_note] = useState<DailyNote | null>(null)
   const [file_list, set_file_list] = useState<string[]>([])
   const [file_tree_fold, set_file_tree_fold] = useState<Record<string, boolean>>({})
   const [open_tabs, set_open_tabs] = useState<string[]>([])
+  // 以只读方式打开的文件（命令行 --readonly） / Files opened read-only via --readonly
+  const [readonly_paths, set_readonly_paths] = useState<Set<string>>(new Set())
+  // 待执行的跳转位置（命令行 file.md:行:列） / Pending cursor jump from file.md:line:column
+  const pending_jump_ref = useRef<{ path: string; line: number; column: number } | null>(null)
   const [tab_ctx_open, set_tab_ctx_open] = useState<boolean>(false)
   const [tab_ctx_pos, set_tab_ctx_pos] = useState<{ x: number; y: number }>({ x: 0, y: 0 })
   const [tab_ctx_path, set_tab_ctx_path] = useState<string>('')
@@ -555,6 +621,7 @@ function App() {
       set_open_tabs((prev) => (prev.includes(path) ? prev : [...prev, path]))
       set_save_status('saved')
       set_last_saved_time(new Date())
+      record_recent(path, 'file')
       // 恢复该文件的滚动位置（按比例） / Restore the scroll position for this file (by ratio)
       requestAnimationFrame(() => {
         setTimeout(() => {
@@ -591,7 +658,43 @@ function App() {
     const opts: OpenDialogOptions = { directory: true, defaultPath: workspace_root || undefined }
     const dir = await open(opts)
     if (typeof dir !== 'string') return
+    await open_folder_at(dir)
+  }
+
+  /**
+   * record_recent
+   * 记录到后端的最近列表并刷新本地状态
+   */
+  async function record_recent(path: string, kind: RecentEntry['kind']) {
+    try {
+      const { invoke } = await import('@tauri-apps/api/core')
+      set_recent_entries(await invoke<RecentEntry[]>('recent_add', { path, kind }))
+    } catch {
+      /* ignore */
+    }
+  }
+
+  // 工作区配置要把 AI 请求发往其他服务或地址时，须经用户明确信任
+  // A workspace config redirecting AI requests needs explicit trust
+  async function confirm_workspace_ai(settings: WorkspaceSettings | null) {
+    const ai = settings?.config.ai
+    if (!settings || !ai || !ai_redirects(ai) || ai_trusted.includes(ai_trust_key(settings))) return
+    const ok = window.confirm(
+      `此工作区的配置（.markdownmonkey/config.toml）要求将 AI 请求发送到 ${ai.base_url || ai.provider}。` +
+        '是否信任该工作区？\n全局 API Key 不会发送到工作区指定的地址；不信任则继续使用全局设置。'
+    )
+    if (!ok) return
+    const next = [...ai_trusted, ai_trust_key(settings)]
+    set_ai_trusted(next)
+    if (store_ref.current) {
+      await store_ref.current.set('ai_trusted_workspaces', next)
+      await store_ref.current.save()
+    }
+  }
+
+  async function open_folder_at(dir: string) {
     set_workspace_root(dir)
+    record_recent(dir, 'workspace')
     try {
       const { invoke } = await import('@tauri-apps/api/core')
       const paths = await invoke<string[]>('list_md_files', { dir })
@@ -599,6 +702,11 @@ function App() {
       set_file_list(unique.sort())
       if (unique.length === 0)
         console.warn('[handle_open_folder] no markdown files found or access denied in:', dir)
+      // 工作区配置在 list_md_files 时由后端读取 / The backend loads the workspace config on list_md_files
+      const settings = await invoke<WorkspaceSettings | null>('workspace_config')
+      set_workspace_settings(settings)
+      if (settings?.error) console.warn('[workspace_config]', settings.error)
+      await confirm_workspace_ai(settings)
     } catch (e) {
       console.error(e)
       set_file_list([])
@@ -615,6 +723,26 @@ function App() {
     }
   }
 
+  /**
+   * open_daily_note
+   * 打开某天（缺省为今天）的日记，不存在时由后端按工作区配置创建
+   * Opens the daily note for `date` (today by default); the backend creates it when missing
+   */
+  async function open_daily_note(date?: string) {
+    if (!workspace_root) return
+    try {
+      const { invoke } = await import('@tauri-apps/api/core')
+      const note = await invoke<DailyNote>('open_daily_note', { date: date ?? null })
+      set_daily_note(note)
+      await open_file_at(note.path)
+    } catch (e) {
+      console.error('[open_daily_note]', e)
+    }
+  }
+
+  // 当前标签是否为最近打开的日记 / Whether the active tab is the last opened daily note
+  const on_daily_note = !!daily_note && daily_note.path === current_file_path.replace(/\\/g, '/')
+
   useEffect(() => {
     render_markdown(markdown_text)
     // 更准确的中英文混排统计： / More accurate statistics for mixed Chinese/English text:
@@ -637,8 +765,9 @@ function App() {
     let unlisten: (() => void) | null = null
     ;(async () => {
       try {
-        const { listen } = await import('@tauri-apps/api/event')
-        unlisten = await listen('fs:changed', async () => {
+        // 只接收发给当前窗口的事件 / Only events targeted at this window
+        const { getCurrentWebviewWindow } = await import('@tauri-apps/api/webviewWindow')
+        unlisten = await getCurrentWebviewWindow().listen('fs:changed', async () => {
           if (!workspace_root) return
           try {
             const { invoke } = await import('@tauri-apps/api/core')
@@ -1120,68 +1249,98 @@ function App() {
     }
   }, [rendered_html, current_file_path, sync_scroll, untitled_counter])
 
-  // 监听从命令行参数打开文件的事件
+  // 处理从命令行参数打开文件的请求（多个文件、跳转位置、文件夹与只读选项）
+  async function handle_open_request(request: OpenRequest) {
+    // 文件夹作为工作区打开，多个时以最后一个为准
+    const folder = request.folders[request.folders.length - 1]
+    if (folder) await open_folder_at(folder)
+
+    let replace_untitled =
+      !!current_file_path && current_file_path.startsWith('untitled:') && !markdown_text
+    for (const target of request.files) {
+      const normalizedPath = target.path.replace(/\\/g, '/')
+      try {
+        const content = await readTextFile(normalizedPath)
+        set_markdown_text(content)
+        set_current_file_path(normalizedPath)
+        set_save_status('saved')
+        set_last_saved_time(new Date())
+        record_recent(normalizedPath, 'file')
+        if (replace_untitled) {
+          // 替换当前的空白未命名文档
+          replace_untitled = false
+          set_open_tabs((prev) => {
+            const idx = prev.indexOf(current_file_path)
+            if (idx >= 0) {
+              const next = [...prev]
+              next[idx] = normalizedPath
+              return next
+            }
+            return [...prev, normalizedPath]
+          })
+        } else {
+          // 添加新标签
+          set_open_tabs((prev) => {
+            if (prev.includes(normalizedPath)) return prev
+            return [...prev, normalizedPath]
+          })
+        }
+        set_readonly_paths((prev) => {
+          const next = new Set(prev)
+          if (request.readonly) next.add(normalizedPath)
+          else next.delete(normalizedPath)
+          return next
+        })
+        // 最后打开的文件成为当前文档，跳转到其指定位置
+        pending_jump_ref.current = target.line
+          ? { path: normalizedPath, line: target.line, column: target.column ?? 1 }
+          : null
+      } catch {
+        // 文件无法读取，静默处理
+      }
+    }
+  }
+
+  // 始终指向最新的处理函数，监听只需注册一次 / Always the latest handler, so the listener is registered once
+  const open_request_ref = useRef(handle_open_request)
   useEffect(() => {
-    const unlisten = listen<string>('open-file', async (event) => {
-      const filePath = event.payload
-      if (filePath && (filePath.endsWith('.md') || filePath.endsWith('.markdown'))) {
-        try {
-          // 规范化路径（将反斜杠转换为正斜杠）
-          const normalizedPath = filePath.replace(/\\/g, '/')
+    open_request_ref.current = handle_open_request
+  })
 
-          const content = await readTextFile(normalizedPath)
+  // 监听打开请求；只注册一次，避免重新注册的间隙中丢失事件
+  useEffect(() => {
+    const unlisten = getCurrentWebviewWindow().listen<OpenRequest>('open-file', async (event) => {
+      if (event.payload) await open_request_ref.current(event.payload)
+    })
 
-          // 如果当前是未命名文档且内容为空，直接替换；否则添加新标签
-          if (current_file_path && current_file_path.startsWith('untitled:') && !markdown_text) {
-            // 替换当前的空白未命名文档
-            set_markdown_text(content)
-            set_current_file_path(normalizedPath)
-            set_save_status('saved')
-            set_last_saved_time(new Date())
-            // 更新标签栏
-            set_open_tabs((prev) => {
-              const idx = prev.indexOf(current_file_path)
-              if (idx >= 0) {
-                const next = [...prev]
-                next[idx] = normalizedPath
-                return next
-              }
-              return [...prev, normalizedPath]
-            })
-          } else {
-            // 添加新标签
-            set_markdown_text(content)
-            set_current_file_path(normalizedPath)
-            set_save_status('saved')
-            set_last_saved_time(new Date())
-            set_open_tabs((prev) => {
-              if (prev.includes(normalizedPath)) return prev
-              return [...prev, normalizedPath]
-            })
-          }
-        } catch {
-          // 尝试使用原始路径作为备选
-          try {
-            const content = await readTextFile(filePath)
-            set_markdown_text(content)
-            set_current_file_path(filePath)
-            set_save_status('saved')
-            set_last_saved_time(new Date())
-            set_open_tabs((prev) => {
-              if (prev.includes(filePath)) return prev
-              return [...prev, filePath]
-            })
-          } catch {
-            // 文件无法读取，静默处理
-          }
-        }
+    // 监听注册后通知后端，发送启动时排队的打开请求（重复调用无副作用）
+    unlisten.then(async () => {
+      try {
+        const { invoke } = await import('@tauri-apps/api/core')
+        await invoke('frontend_ready')
+      } catch {
+        /* ignore */
       }
     })
 
     return () => {
       unlisten.then((fn) => fn())
     }
-  }, [current_file_path, markdown_text, handle_open_file, handle_save_file, untitled_counter])
+  }, [])
+
+  // 命令行指定的跳转位置：文档内容载入编辑器后移动光标
+  useEffect(() => {
+    const jump = pending_jump_ref.current
+    const view = cm_view_ref.current
+    if (!jump || !view || jump.path !== current_file_path) return
+    if (view.state.doc.toString() !== markdown_text) return
+    pending_jump_ref.current = null
+    const doc = view.state.doc
+    const line = doc.line(Math.min(jump.line, doc.lines))
+    const pos = line.from + Math.min(Math.max(jump.column - 1, 0), line.length)
+    view.dispatch({ selection: EditorSelection.cursor(pos), scrollIntoView: true })
+    view.focus()
+  }, [current_file_path, markdown_text])
 
   // 外部文件变更检测：当当前打开的真实文件被外部修改时，提示重新加载
   useEffect(() => {
@@ -1283,8 +1442,10 @@ function App() {
         }>
       >('ai_custom_templates')
       const saved_recent = (await s.get<string[]>('recent_files')) || []
+      const saved_trusted = (await s.get<string[]>('ai_trusted_workspaces')) || []
       set_api_base_url(saved_base)
       set_api_key(saved_key)
+      set_ai_trusted(saved_trusted)
       set_provider(saved_provider)
       set_model(saved_model)
       set_system_prompt(saved_system)
@@ -1295,7 +1456,20 @@ function App() {
       if (typeof saved_ai_enabled === 'boolean') set_ai_enabled(saved_ai_enabled)
       if (Array.isArray(saved_actions)) set_ai_actions_enabled(saved_actions)
       if (Array.isArray(saved_custom)) set_ai_custom_templates(saved_custom)
-      set_recent_files(saved_recent)
+      try {
+        const { invoke } = await import('@tauri-apps/api/core')
+        // 迁移旧版保存在设置中的最近文件 / Migrate recent files kept in the settings store
+        if (saved_recent.length) {
+          for (const p of [...saved_recent].reverse()) {
+            await invoke('recent_add', { path: p, kind: 'file' })
+          }
+          await s.delete('recent_files')
+          await s.save()
+        }
+        set_recent_entries(await invoke<RecentEntry[]>('recent_list'))
+      } catch {
+        /* ignore */
+      }
       const saved_outline_shown = await s.get<boolean>('outline_shown')
       const saved_outline_width = await s.get<number>('outline_width')
       if (typeof saved_outline_shown === 'boolean') set_show_outline(saved_outline_shown)
@@ -1526,16 +1700,6 @@ function App() {
     } catch {
       // Ignore error when listing files
     }
-    // 记录最近文件
-    set_recent_files((prev) => {
-      const next = [selected, ...prev.filter((p) => p !== selected)].slice(0, 20)
-      return next
-    })
-    if (store_ref.current) {
-      const next = [selected, ...recent_files.filter((p) => p !== selected)].slice(0, 20)
-      await store_ref.current.set('recent_files', next)
-      await store_ref.current.save()
-    }
   }
 
   /**
@@ -1616,14 +1780,32 @@ function App() {
     return view.state.sliceDoc(sel.from, sel.to)
   }
 
+  // 工作区配置覆盖全局的 AI 设置；切换了服务时不沿用全局的模型与地址。
+  // 改变服务或地址的配置须先经用户信任（见 confirm_workspace_ai），否则整体忽略
+  // Workspace config overrides the global AI settings; a different provider drops the global model/URL.
+  // Configs changing the provider or URL are ignored until trusted
+  const ws_ai_config = workspace_settings?.config.ai
+  const ws_ai =
+    ws_ai_config &&
+    workspace_settings &&
+    (!ai_redirects(ws_ai_config) || ai_trusted.includes(ai_trust_key(workspace_settings)))
+      ? ws_ai_config
+      : undefined
+  const ai_provider = ws_ai?.provider || provider
+  const ai_same_provider = ai_provider === provider
+  const ai_model = ws_ai?.model || (ai_same_provider ? model : '')
+  const ai_base_url = ws_ai?.base_url || (ai_same_provider ? api_base_url : '')
+  // 全局 API Key 不会发往工作区指定的地址 / The global API key is never sent to a workspace base URL
+  const ai_key = ws_ai?.base_url ? '' : api_key
+
   async function ai_invoke(prompt_text: string) {
     const { invoke } = await import('@tauri-apps/api/core')
     const { listen } = await import('@tauri-apps/api/event')
-    if (provider !== 'ollama' && (!api_key || api_key.trim() === '')) {
+    if (ai_provider !== 'ollama' && !ws_ai?.base_url && (!ai_key || ai_key.trim() === '')) {
       window.alert(t(ui_language, 'enter_api_key'))
       return
     }
-    if (provider === 'openrouter' && !api_key.trim().startsWith('sk-or-')) {
+    if (ai_provider === 'openrouter' && ai_key && !ai_key.trim().startsWith('sk-or-')) {
       const ok = window.confirm(
         '当前 Provider 为 OpenRouter，但 API Key 看起来不是 OpenRouter Key（通常以 sk-or- 开头）。仍要继续发送吗？'
       )
@@ -1690,13 +1872,13 @@ function App() {
     try {
       await invoke('ai_complete_stream', {
         req: {
-          provider,
-          api_key: api_key.trim(),
+          provider: ai_provider,
+          api_key: ai_key.trim(),
           prompt: prompt_text,
-          model,
+          model: ai_model || undefined,
           system_prompt,
           temperature,
-          base_url: api_base_url,
+          base_url: ai_base_url || undefined,
         },
       })
     } catch (e) {
@@ -1799,8 +1981,8 @@ function App() {
     const filename = current_file_path ? current_file_path.split(/[/\\]/).pop() || '' : ''
     prompt = prompt.replaceAll('{date}', date_str)
     prompt = prompt.replaceAll('{filename}', filename)
-    prompt = prompt.replaceAll('{model}', model)
-    prompt = prompt.replaceAll('{provider}', provider)
+    prompt = prompt.replaceAll('{model}', ai_model)
+    prompt = prompt.replaceAll('{provider}', ai_provider)
     set_ai_title(tpl.title || (ui_language === 'en-US' ? 'AI Result' : 'AI 结果'))
     set_ai_last_scope(tpl.scope)
     await ai_invoke(prompt)
@@ -2561,6 +2743,22 @@ function App() {
           >
             {t(ui_language, 'copy_path')}
           </button>
+          <button
+            className="settings_btn"
+            style={{ display: 'block', width: 180, textAlign: 'left' }}
+            disabled={!tab_ctx_path || tab_ctx_path.startsWith('untitled:')}
+            onClick={async () => {
+              set_tab_ctx_open(false)
+              try {
+                const { invoke } = await import('@tauri-apps/api/core')
+                await invoke<string>('open_in_new_window', { path: tab_ctx_path })
+              } catch (e) {
+                console.error(e)
+              }
+            }}
+          >
+            {t(ui_language, 'open_in_new_window')}
+          </button>
           <button
             className="settings_btn"
             style={{ display: 'block', width: 180, textAlign: 'left' }}
@@ -2755,6 +2953,30 @@ function App() {
                               >
                                 {t(ui_language, 'rename')}
                               </button>
+                              <button
+                                className="settings_btn"
+                                title={t(ui_language, 'duplicate')}
+                                onClick={async () => {
+                                  try {
+                                    const { invoke } = await import('@tauri-apps/api/core')
+                                    // 目标与源相同：后端自动改名为 name-1.md
+                                    // Same target as source: the backend picks name-1.md
+                                    await invoke('copy_path', {
+                                      src: safe,
+                                      dst: safe,
+                                      options: { conflict: 'auto_rename', rewrite_links: true },
+                                    })
+                                    const paths = await invoke<string[]>('list_md_files', {
+                                      dir: workspace_root,
+                                    })
+                                    set_file_list(Array.from(new Set(paths)).sort())
+                                  } catch (e) {
+                                    alert(t(ui_language, 'duplicate') + ' 失败：' + e)
+                                  }
+                                }}
+                              >
+                                {t(ui_language, 'duplicate')}
+                              </button>
                               <button
                                 className="settings_btn"
                                 title={t(ui_language, 'remove')}
@@ -2870,7 +3092,7 @@ function App() {
             style={{ height: '100%', maxHeight: 'calc(100vh - 120px)', minHeight: 0 }}
             // 浏览器原生拼写检查（仅英文），开启时对英文单词下划线提示
             basicSetup={true}
-            editable={true}
+            editable={!readonly_paths.has(current_file_path)}
             extensions={[
               markdown(),
               editorScrollSyncExt,
@@ -3085,19 +3307,33 @@ function App() {
         set_ai_actions_enabled={set_ai_actions_enabled}
         ai_custom_templates={ai_custom_templates}
         set_ai_custom_templates={set_ai_custom_templates}
-        recent_files={recent_files}
+        recent_entries={recent_entries}
         clear_recent_files={async () => {
-          set_recent_files([])
-          if (store_ref.current) {
-            await store_ref.current.set('recent_files', [])
-            await store_ref.current.save()
+          try {
+            const { invoke } = await import('@tauri-apps/api/core')
+            set_recent_entries(await invoke<RecentEntry[]>('recent_clear'))
+          } catch {
+            /* ignore */
           }
         }}
-        on_open_recent={async (p) => {
+        on_pin_recent={async (p, pinned) => {
+          try {
+            const { invoke } = await import('@tauri-apps/api/core')
+            set_recent_entries(await invoke<RecentEntry[]>('recent_pin', { path: p, pinned }))
+          } catch {
+            /* ignore */
+          }
+        }}
+        on_open_recent={async (p, kind) => {
+          if (kind === 'workspace') {
+            await open_folder_at(p)
+            return
+          }
           try {
             const content = await readTextFile(p)
             set_markdown_text(content)
             set_current_file_path(p)
+            record_recent(p, 'file')
           } catch {
             window.alert('打开失败')
           }
@@ -3317,10 +3553,10 @@ function App() {
       />
       <Ai_chat_modal
         is_open={show_ai_chat}
-        provider={provider}
-        api_base_url={api_base_url}
-        api_key={api_key}
-        model={model}
+        provider={ai_provider}
+        api_base_url={ai_base_url}
+        api_key={ai_key}
+        model={ai_model}
         system_prompt={system_prompt}
         temperature={temperature}
         ui_language={ui_language}
@@ -3401,6 +3637,33 @@ function App() {
               html2pdf().set(opt).from(html).save()
             },
           },
+          ...(workspace_root
+            ? [
+                {
+                  id: 'daily_note',
+                  label: t(ui_language, 'daily_note'),
+                  action: () => open_daily_note(),
+                },
+              ]
+            : []),
+          ...(on_daily_note && daily_note?.previous
+            ? [
+                {
+                  id: 'daily_note_previous',
+                  label: `${t(ui_language, 'daily_note_previous')} (${daily_note.previous.date})`,
+                  action: () => open_daily_note(daily_note.previous?.date),
+                },
+              ]
+            : []),
+          ...(on_daily_note && daily_note?.next
+            ? [
+                {
+                  id: 'daily_note_next',
+                  label: `${t(ui_language, 'daily_note_next')} (${daily_note.next.date})`,
+                  action: () => open_daily_note(daily_note.next?.date),
+                },
+              ]
+            : []),
           {
             id: 'settings',
             label: t(ui_language, 'settings'),
@@ -3600,6 +3863,7 @@ function App() {
             console.log('Clicked image:', img)
           }}
           getAbsolutePath={imageManager.getAbsolutePath}
+          getAssetUrl={imageManager.getAssetUrl}
           onClose={() => set_show_image_manager(false)}
         />
       )}
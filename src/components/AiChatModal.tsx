@@ -259,10 +259,11 @@ function AiChatModalComponent(props: AiChatModalProps) {
           provider: chat_provider,
           api_key: api_key.trim(),
           prompt,
-          model: chat_model,
+          // 留空时由后端使用该服务的默认值 / Empty values fall back to the provider defaults
+          model: chat_model || undefined,
           system_prompt,
           temperature,
-          base_url: chat_base_url,
+          base_url: chat_base_url || undefined,
           messages: messages.concat([{ role: 'user', content }]),
         },
       })
@@ -1,5 +1,4 @@
 import { memo, useState } from 'react'
-import { convertFileSrc } from '@tauri-apps/api/core'
 import imageCompression from 'browser-image-compression'
 
 type ImageInfo = {
@@ -13,6 +12,7 @@ type ImageManagerProps = {
   images: ImageInfo[]
   onImageClick: (image: ImageInfo) => void
   getAbsolutePath: (path: string) => string
+  getAssetUrl: (path: string) => string
   onClose: () => void
 }
 
@@ -22,7 +22,7 @@ type ImageManagerProps = {
  * Image manager component: displays all images in the document
  */
 function ImageManagerComponent(props: ImageManagerProps) {
-  const { images, onImageClick, getAbsolutePath, onClose } = props
+  const { images, onImageClick, getAbsolutePath, getAssetUrl, onClose } = props
   const [selectedImage, setSelectedImage] = useState<ImageInfo | null>(null)
   const [compressing, setCompressing] = useState(false)
   const [compressProgress, setCompressProgress] = useState(0)
@@ -48,7 +48,7 @@ function ImageManagerComponent(props: ImageManagerProps) {
       const absolutePath = getAbsolutePath(img.url)
 
       // 读取原始文件
-      const response = await fetch(convertFileSrc(absolutePath))
+      const response = await fetch(getAssetUrl(img.url))
       const blob = await response.blob()
       const file = new File([blob], img.url.split('/').pop() || 'image.jpg', {
         type: blob.type,
@@ -183,8 +183,7 @@ function ImageManagerComponent(props: ImageManagerProps) {
             }}
           >
             {images.map((img, index) => {
-              const absolutePath = img.isLocal ? getAbsolutePath(img.url) : img.url
-              const displaySrc = img.isLocal ? convertFileSrc(absolutePath) : img.url
+              const displaySrc = img.isLocal ? getAssetUrl(img.url) : img.url
 
               return (
                 <div
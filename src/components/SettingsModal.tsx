@@ -44,9 +44,11 @@ type SettingsModalProps = {
       vars?: { lang?: string; style?: string }
     }>
   ) => void
-  recent_files?: string[]
+  // 最近打开的文件与工作区，固定的在前 / Recent files and workspaces, pinned first
+  recent_entries?: Array<{ path: string; kind: 'file' | 'workspace'; pinned: boolean }>
   clear_recent_files?: () => void
-  on_open_recent?: (path: string) => void
+  on_pin_recent?: (path: string, pinned: boolean) => void
+  on_open_recent?: (path: string, kind: 'file' | 'workspace') => void
   on_save: () => Promise<void>
   on_close: () => void
   on_test: () => Promise<void>
@@ -83,8 +85,9 @@ function SettingsModalComponent(props: SettingsModalProps) {
     set_ai_actions_enabled,
     ai_custom_templates,
     set_ai_custom_templates,
-    recent_files = [],
+    recent_entries = [],
     clear_recent_files,
+    on_pin_recent,
     on_save,
     on_close,
     on_test,
@@ -170,22 +173,37 @@ function SettingsModalComponent(props: SettingsModalProps) {
 
           {active_tab === 'ui' && (
             <div className="tab_panel">
-              {recent_files.length > 0 && (
+              {recent_entries.length > 0 && (
                 <div style={{ marginBottom: 12 }}>
                   <div style={{ color: '#cfcfcf', fontSize: 13, marginBottom: 6 }}>
                     {t(ui_language || 'zh-CN', 'recent_files_label')}
                   </div>
                   <ul style={{ margin: 0, paddingLeft: 18 }}>
-                    {recent_files.slice(0, 10).map((p) => (
-                      <li key={p} style={{ wordBreak: 'break-all', margin: '4px 0' }}>
+                    {recent_entries.slice(0, 10).map((e) => (
+                      <li
+                        key={`${e.kind}:${e.path}`}
+                        style={{ wordBreak: 'break-all', margin: '4px 0' }}
+                      >
                         <button
                           className="settings_btn"
-                          onClick={() => on_open_recent && on_open_recent(p)}
-                          title={p}
+                          onClick={() => on_open_recent && on_open_recent(e.path, e.kind)}
+                          title={e.path}
                         >
                           {t(ui_language || 'zh-CN', 'open_label')}
                         </button>
-                        <span style={{ marginLeft: 8, opacity: 0.9 }}>{p}</span>
+                        {on_pin_recent && (
+                          <button
+                            className="settings_btn"
+                            style={{ marginLeft: 4 }}
+                            onClick={() => on_pin_recent(e.path, !e.pinned)}
+                          >
+                            {t(ui_language || 'zh-CN', e.pinned ? 'unpin_label' : 'pin_label')}
+                          </button>
+                        )}
+                        <span style={{ marginLeft: 8, opacity: 0.9 }}>
+                          {e.kind === 'workspace' ? '📁 ' : ''}
+                          {e.path}
+                        </span>
                       </li>
                     ))}
                   </ul>
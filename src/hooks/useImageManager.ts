@@ -1,4 +1,5 @@
 import { useState, useCallback, useMemo } from 'react'
+import { getAssetUrl as buildAssetUrl } from '../utils/pathUtils'
 
 type ImageInfo = {
   url: string
@@ -54,11 +55,18 @@ export function useImageManager(markdownText: string, currentFilePath: string) {
     [currentFilePath]
   )
 
+  // 获取图片预览地址（mm-asset 协议） / Get image preview URL (mm-asset protocol)
+  const getAssetUrl = useCallback(
+    (url: string): string => buildAssetUrl(url, currentFilePath),
+    [currentFilePath]
+  )
+
   return {
     images,
     selectedImage,
     setSelectedImage,
     getAbsolutePath,
+    getAssetUrl,
     imageCount: images.length,
   }
 }
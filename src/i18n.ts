@@ -7,6 +7,9 @@ const dict: Record<Lang, Record<string, string>> = {
     save: '保存',
     save_as: '另存为',
     settings: '设置',
+    daily_note: '今日日记',
+    daily_note_previous: '上一篇日记',
+    daily_note_next: '下一篇日记',
     export_html: '导出HTML',
     export_pdf: '导出PDF',
     ai_enabled: 'AI 已启用',
@@ -20,8 +23,10 @@ const dict: Record<Lang, Record<string, string>> = {
     new_file: '新建',
     refresh: '刷新',
     rename: '重命名',
+    duplicate: '创建副本',
     remove: '删除',
     copy_path: '复制路径',
+    open_in_new_window: '在新窗口中打开',
     close_others: '关闭其他',
     close_right: '关闭右侧',
     close_all_tabs: '关闭所有标签',
@@ -44,6 +49,8 @@ const dict: Record<Lang, Record<string, string>> = {
     tab_templates: '提示模板',
     recent_files_label: '最近文件：',
     open_label: '打开',
+    pin_label: '固定',
+    unpin_label: '取消固定',
     clear_recent: '清空最近文件',
     editor_font_size: '编辑器字号',
     preview_font_size: '预览字号',
@@ -140,6 +147,9 @@ const dict: Record<Lang, Record<string, string>> = {
     save: 'Save',
     save_as: 'Save As',
     settings: 'Settings',
+    daily_note: "Today's Note",
+    daily_note_previous: 'Previous Daily Note',
+    daily_note_next: 'Next Daily Note',
     export_html: 'Export HTML',
     export_pdf: 'Export PDF',
     ai_enabled: 'AI Enabled',
@@ -153,8 +163,10 @@ const dict: Record<Lang, Record<string, string>> = {
     new_file: 'New',
     refresh: 'Refresh',
     rename: 'Rename',
+    duplicate: 'Duplicate',
     remove: 'Delete',
     copy_path: 'Copy Path',
+    open_in_new_window: 'Open in New Window',
     close_others: 'Close Others',
     close_right: 'Close Right',
     close_all_tabs: 'Close All Tabs',
@@ -177,6 +189,8 @@ const dict: Record<Lang, Record<string, string>> = {
     tab_templates: 'Templates',
     recent_files_label: 'Recent Files:',
     open_label: 'Open',
+    pin_label: 'Pin',
+    unpin_label: 'Unpin',
     clear_recent: 'Clear Recents',
     editor_font_size: 'Editor Font Size',
     preview_font_size: 'Preview Font Size',
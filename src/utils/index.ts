@@ -33,6 +33,8 @@ export {
   isUntitledDocument,
   getUntitledNumber,
   getDisplayName,
+  ASSET_SCHEME,
+  getAssetUrl,
   saveToStore,
 } from './pathUtils'
 
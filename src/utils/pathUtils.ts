@@ -76,6 +76,28 @@ export function getDisplayName(path: string): string {
   return getFileName(path) || path.replace(/^[\s\S]*[\\/]/, '')
 }
 
+/**
+ * 工作区资源协议名 / Workspace asset protocol scheme
+ */
+export const ASSET_SCHEME = 'mm-asset'
+
+/**
+ * 生成 mm-asset 协议地址，由后端按当前文档解析相对路径
+ * Build an mm-asset URL; the backend resolves it relative to the current document
+ */
+export function getAssetUrl(reference: string, documentPath: string): string {
+  if (/^(https?:|data:|blob:)/i.test(reference)) return reference
+  // Windows / Android 上自定义协议通过 http://<scheme>.localhost 访问
+  const base = /Windows|Android/i.test(navigator.userAgent)
+    ? `http://${ASSET_SCHEME}.localhost/`
+    : `${ASSET_SCHEME}://localhost/`
+  const doc =
+    documentPath && !isUntitledDocument(documentPath)
+      ? `?doc=${encodeURIComponent(normalizePath(documentPath))}`
+      : ''
+  return `${base}${encodeURIComponent(reference)}${doc}`
+}
+
 /**
  * 安全地保存设置到 Store / Safely save settings to Store
  * @param store Store 实例 / Store instance